@@ -9,6 +9,9 @@ fn main() {
     // 运行 Tauri 构建
     tauri_build::build();
     
+    // native_audio：Swift 库已链接，代码中据此决定是否调用 FFI
+    println!("cargo:rustc-check-cfg=cfg(native_audio)");
+    
     // 获取项目根目录
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     
@@ -35,6 +38,7 @@ fn main() {
             println!("cargo:warning=Swift 库不存在于 {} 或 {}", 
                      libs_dir_tauri.display(), libs_dir_swift.display());
             println!("cargo:warning=请先编译 Swift 库：cd swift-plugin && swift build -c release");
+            println!("cargo:warning=本次构建不链接 Swift 库，应用将以降级模式运行");
             return;
         }
         
@@ -45,6 +49,7 @@ fn main() {
         
         // 链接静态库
         println!("cargo:rustc-link-lib=static=AudioCapture");
+        println!("cargo:rustc-cfg=native_audio");
         
         // 链接 macOS 系统框架
         println!("cargo:rustc-link-lib=framework=ScreenCaptureKit");
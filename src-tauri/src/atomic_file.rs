@@ -0,0 +1,69 @@
+// atomic_file.rs
+// 原子文件写入
+// 先写入同目录下的临时文件并刷盘，再重命名覆盖目标文件，写入中途崩溃不会损坏原文件
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// 临时文件后缀，完整文件名为 .<目标文件名>.tmp
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// 目标文件对应的临时文件路径（与目标同目录，保证 rename 不跨文件系统）
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{}{}", name, TEMP_SUFFIX))
+}
+
+/// 原子写入文件：读者只会看到完整的旧内容或完整的新内容
+/// 写入失败时删除临时文件，原文件保持不变
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let temp = temp_path(path);
+    let result = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn write_atomic_replaces_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        
+        write_atomic(&path, "{\"a\":1}").unwrap();
+        write_atomic(&path, "{\"a\":2}").unwrap();
+        
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"a\":2}");
+        assert!(!temp_path(&path).exists());
+    }
+    
+    #[test]
+    fn interrupted_write_leaves_original_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        write_atomic(&path, "{\"language\":\"zh-CN\"}").unwrap();
+        
+        // 模拟写入中途崩溃：临时文件只写了一部分，没有重命名
+        fs::write(temp_path(&path), "{\"language\":\"en").unwrap();
+        
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "{\"language\":\"zh-CN\"}");
+        let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(parsed["language"], "zh-CN");
+        
+        // 下次写入覆盖残留的临时文件
+        write_atomic(&path, "{\"language\":\"en-US\"}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"language\":\"en-US\"}");
+    }
+}
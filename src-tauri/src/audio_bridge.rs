@@ -2,41 +2,403 @@
 // Rust FFI 桥接层
 // 用于连接 Swift 音频捕获和语音识别模块
 
+use crate::error::AppError;
+use crate::language;
+use crate::lock::lock_recover;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::ffi::{c_char, c_float, c_int, CStr, CString};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex, Once};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 转录更新事件名
+pub const TRANSCRIPTION_UPDATE_EVENT: &str = "transcription-update";
+
+/// 识别错误事件名（负载为 SessionEvent<错误信息>）
+pub const TRANSCRIPTION_ERROR_EVENT: &str = "transcription-error";
+
+/// 音频电平事件名（负载为 SessionEvent<电平>）
+pub const AUDIO_LEVEL_EVENT: &str = "audio-level";
+
+/// 识别到停止关键词事件名（负载为 SessionEvent<关键词>），收到后执行停止流程
+pub const STOP_KEYWORD_EVENT: &str = "stop-keyword-detected";
+
+/// 检测到持续削波事件名（每次会话最多发送一次），前端应提示用户降低音源音量
+pub const CLIPPING_EVENT: &str = "audio-clipping";
+
+/// 音频文件转录进度事件名（负载为 SessionEvent<0.0 ~ 1.0 的进度>）
+pub const FILE_PROGRESS_EVENT: &str = "file-transcription-progress";
+
+/// 实时输出路径中的会话 ID 占位符
+pub const LIVE_OUTPUT_SESSION_PLACEHOLDER: &str = "{session_id}";
+
+/// 支持转录的音频文件扩展名
+pub const SUPPORTED_AUDIO_FILE_EXTENSIONS: &[&str] = &["wav", "m4a"];
+
+/// 自定义词汇（上下文短语）的最大数量
+pub const MAX_CONTEXTUAL_STRINGS: usize = 100;
+
+/// 音频捕获启动的最大尝试次数
+#[cfg(native_audio)]
+const CAPTURE_START_ATTEMPTS: u32 = 3;
+
+/// 捕获启动重试之间的等待时间（毫秒）
+#[cfg(native_audio)]
+const CAPTURE_RETRY_DELAY_MS: u64 = 300;
+
+/// 部分结果事件的默认最小发送间隔（毫秒）
+pub const DEFAULT_PARTIAL_EMIT_INTERVAL_MS: u64 = 150;
+
+/// 语音识别器需要的音频采样率，其他采样率的输入会先重采样
+#[cfg(native_audio)]
+const SAMPLE_RATE: f64 = 16000.0;
+
+/// 样本绝对值达到该值视为削波
+#[cfg(native_audio)]
+const CLIPPING_THRESHOLD: f32 = 0.99;
+
+/// 单次回调中削波样本占比达到该值时，该段音频计为削波
+#[cfg(native_audio)]
+const CLIPPING_RATIO: f32 = 0.001;
+
+/// 连续削波达到该时长（秒）时判定为持续削波
+#[cfg(native_audio)]
+const CLIPPING_SUSTAIN_SECONDS: f64 = 0.5;
+
+/// 静音分段：音频 RMS 低于该值时计为静音
+#[cfg(native_audio)]
+const SILENCE_RMS_THRESHOLD: f32 = 0.01;
+
+/// 静音分段的默认静音时长（毫秒）
+pub const DEFAULT_SILENCE_SPLIT_MS: u64 = 1500;
+
+/// 静音分段时长的有效范围（毫秒）
+pub const SILENCE_SPLIT_RANGE_MS: std::ops::RangeInclusive<u64> = 300..=10_000;
+
+/// 相邻音频回调之间超过该间隔（秒）视为音频中断，不计入有效时长
+#[cfg(native_audio)]
+const MAX_SAMPLE_GAP_SECONDS: f64 = 1.0;
+
+/// 音频电平事件的最小发送间隔（毫秒）
+const AUDIO_LEVEL_EMIT_INTERVAL_MS: u128 = 200;
+
+/// 最终片段与已确认文本末尾的重叠至少达到该字符数才会被去除，避免误删偶然相同的短词
+const MIN_OVERLAP_CHARS: usize = 4;
+
+/// 错误历史最多保留的条数，超出时丢弃最旧的
+const ERROR_HISTORY_CAPACITY: usize = 50;
+
+/// 期望的原生接口版本，与 Swift 侧 audioBridgeABIVersion 一致
+#[cfg(native_audio)]
+const NATIVE_ABI_VERSION: c_int = 1;
+
+/// 检测重叠时只比较已确认文本末尾的字符数
+const MAX_OVERLAP_CHARS: usize = 200;
+
+/// 带时间信息的转录片段（时间相对于捕获开始，单位毫秒）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// 识别置信度（0.0 ~ 1.0）
+    #[serde(default)]
+    pub confidence: f32,
+    /// 后处理前的原始识别文本，未经后处理修改时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_text: Option<String>,
+}
+
+/// 音频输入源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioSource {
+    /// 系统音频（ScreenCaptureKit）
+    #[default]
+    SystemAudio,
+    /// 麦克风（AVAudioEngine）
+    Microphone,
+}
+
+impl std::str::FromStr for AudioSource {
+    type Err = AppError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "system_audio" | "system" => Ok(Self::SystemAudio),
+            "microphone" | "mic" => Ok(Self::Microphone),
+            _ => Err(AppError::InvalidInput(format!("不支持的音频源: {}", s))),
+        }
+    }
+}
+
+/// 识别任务类型提示，对应 SFSpeechRecognitionTaskHint，影响识别器对语句的预期
+/// - dictation：连续口述（会议、长段讲话），默认值
+/// - search：简短的搜索词、关键词
+/// - confirmation：极短的确认或命令（"是"、"停止"）
+/// - unspecified：不提示，由系统决定
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskHint {
+    Unspecified,
+    #[default]
+    Dictation,
+    Search,
+    Confirmation,
+}
+
+impl TaskHint {
+    /// 对应 SFSpeechRecognitionTaskHint 的原始值
+    #[cfg(native_audio)]
+    fn native_value(self) -> c_int {
+        match self {
+            Self::Unspecified => 0,
+            Self::Dictation => 1,
+            Self::Search => 2,
+            Self::Confirmation => 3,
+        }
+    }
+}
+
+impl std::str::FromStr for TaskHint {
+    type Err = AppError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "unspecified" | "none" => Ok(Self::Unspecified),
+            "dictation" => Ok(Self::Dictation),
+            "search" => Ok(Self::Search),
+            "confirmation" | "command" => Ok(Self::Confirmation),
+            _ => Err(AppError::InvalidInput(format!(
+                "不支持的识别任务类型: {}（可选 dictation / search / confirmation / unspecified）",
+                s
+            ))),
+        }
+    }
+}
+
+/// 音频捕获状态，对应 audio_capture_get_status / mic_capture_get_status 的返回值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureStatus {
+    /// 0：空闲
+    Idle,
+    /// 1：启动中
+    Starting,
+    /// 2：捕获中
+    Running,
+    /// 3：停止中
+    Stopping,
+    /// -1：错误
+    Error,
+    /// 无法识别的状态码
+    Unknown(i32),
+}
+
+impl From<i32> for CaptureStatus {
+    fn from(code: i32) -> Self {
+        match code {
+            0 => Self::Idle,
+            1 => Self::Starting,
+            2 => Self::Running,
+            3 => Self::Stopping,
+            -1 => Self::Error,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// 语音识别状态，对应 speech_get_status 的返回值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecognitionStatus {
+    /// 0：空闲
+    Idle,
+    /// 1：启动中
+    Starting,
+    /// 2：识别中
+    Running,
+    /// 3：停止中
+    Stopping,
+    /// -1：错误
+    Error,
+    /// 无法识别的状态码
+    Unknown(i32),
+}
+
+impl From<i32> for RecognitionStatus {
+    fn from(code: i32) -> Self {
+        match code {
+            0 => Self::Idle,
+            1 => Self::Starting,
+            2 => Self::Running,
+            3 => Self::Stopping,
+            -1 => Self::Error,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// 当前语言的识别方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecognitionMode {
+    /// 支持端侧识别，不需要联网
+    OnDevice,
+    /// 仅支持服务器识别，识别时会上传音频
+    RequiresNetwork,
+    /// 本机识别器不支持该语言
+    Unsupported,
+}
+
+/// 音频输入设备
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioDevice {
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+}
+
+/// 回调吞吐量指标，用于判断卡顿发生在音频侧还是识别侧
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct BridgeMetrics {
+    /// 音频样本回调次数
+    pub audio_callbacks: u64,
+    /// 转录结果回调次数（部分 + 最终）
+    pub transcription_callbacks: u64,
+    /// 最终片段数
+    pub final_segments: u64,
+    /// 自开始转录以来的秒数
+    pub elapsed_seconds: f64,
+    /// 每秒音频回调次数
+    pub audio_callbacks_per_second: f64,
+    /// 每秒转录回调次数
+    pub transcription_callbacks_per_second: f64,
+}
+
+/// 识别器预热结果
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PrewarmResult {
+    /// 识别器是否已创建并可用
+    pub success: bool,
+    /// 端侧识别模型是否已加载
+    pub on_device_ready: bool,
+}
+
+/// 错误历史中的一条记录
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorRecord {
+    /// 发生时间，RFC 3339 UTC 格式
+    pub timestamp: String,
+    pub message: String,
+}
+
+/// 可单独捕获音频的应用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureTarget {
+    pub name: String,
+    pub bundle_id: String,
+}
+
+/// 转录更新事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionUpdate {
+    pub latest_text: String,
+    pub full_text: String,
+    pub is_final: bool,
+    /// latest_text 与上一次事件相同的前缀长度（UTF-16 码元，可直接用于 JS 的 slice）
+    pub stable_prefix_len: usize,
+    /// latest_text 中新增或被修订的尾部
+    pub revised_suffix: String,
+    /// 产生该更新的转录会话 ID
+    pub session_id: Option<String>,
+}
+
+/// 带会话 ID 的事件负载，用于负载本身不是结构体的事件（电平、错误、进度等）
+/// 前端据此忽略上一次会话迟到的事件
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionEvent<T> {
+    pub session_id: Option<String>,
+    pub payload: T,
+}
+
+/// 静音分段状态：识别器的部分结果是从当前识别段落开始的完整文本，
+/// 提前确认后需记住已确认的前缀，避免之后的结果重复追加
+struct SilenceSplit {
+    /// 最近一次收到的原始部分结果
+    last_partial: String,
+    /// 当前识别段落中已被提前确认的原始文本
+    forced_prefix: String,
+}
 
 /// 音频样本回调类型
-type AudioSampleCallback = extern "C" fn(*const c_float, c_int, f64);
+type AudioSampleCallback = extern "C" fn(*const c_float, c_int, f64, f64, c_int);
 
-/// 转录结果回调类型  
-type TranscriptionCallback = extern "C" fn(*const c_char, bool);
+/// 转录结果回调类型（文本、是否最终结果、置信度）
+type TranscriptionCallback = extern "C" fn(*const c_char, bool, c_float);
 
 /// 错误回调类型
 type ErrorCallback = extern "C" fn(*const c_char);
 
-// 条件编译：只在 swift_audio 特性启用时链接 Swift 库
-#[cfg(feature = "swift_audio")]
+/// 文件转录进度回调类型（0.0 ~ 1.0）
+#[cfg(native_audio)]
+type FileProgressCallback = extern "C" fn(f64);
+
+// 条件编译：启用 swift_audio 特性且构建时找到 Swift 库时，build.rs 设置 native_audio
+#[cfg(native_audio)]
 mod ffi {
     use super::*;
     
     #[link(name = "AudioCapture")]
     extern "C" {
+        /// 返回原生接口版本，见 NATIVE_ABI_VERSION
+        pub fn audio_bridge_health_check() -> c_int;
+        
         // 音频捕获函数
         pub fn audio_capture_check_permission() -> bool;
         pub fn audio_capture_start() -> bool;
         pub fn audio_capture_stop();
+        /// 状态码：0=空闲, 1=启动中, 2=捕获中, 3=停止中, -1=错误（见 CaptureStatus）
         pub fn audio_capture_get_status() -> c_int;
+        pub fn audio_capture_set_target(bundle_id: *const c_char);
+        pub fn audio_list_capture_targets() -> *mut c_char;
         pub fn audio_capture_set_callback(callback: AudioSampleCallback);
         pub fn audio_capture_set_error_callback(callback: ErrorCallback);
         
+        // 麦克风捕获函数
+        pub fn mic_capture_check_permission() -> bool;
+        pub fn mic_capture_start() -> bool;
+        pub fn mic_capture_stop();
+        /// 状态码同 audio_capture_get_status
+        pub fn mic_capture_get_status() -> c_int;
+        pub fn mic_capture_set_device(device_id: *const c_char);
+        pub fn audio_list_devices() -> *mut c_char;
+        pub fn audio_free_string(ptr: *mut c_char);
+        pub fn mic_capture_set_callback(callback: AudioSampleCallback);
+        pub fn mic_capture_set_error_callback(callback: ErrorCallback);
+        
         // 语音识别函数
         pub fn speech_check_permission() -> bool;
         pub fn speech_set_language(language_code: *const c_char);
         pub fn speech_supports_on_device() -> bool;
+        pub fn speech_set_on_device(require: bool);
+        /// 取值见 TaskHint::native_value
+        pub fn speech_set_task_hint(hint: c_int);
+        pub fn speech_supports_language(language_code: *const c_char) -> bool;
+        pub fn speech_set_contextual_strings(phrases: *const *const c_char, count: c_int);
+        /// -1=失败, 0=识别器就绪, 1=端侧模型已就绪
+        pub fn speech_prewarm() -> c_int;
         pub fn speech_start() -> bool;
         pub fn speech_append_audio(samples: *const c_float, count: c_int);
+        /// 阻塞直到文件送完并收到最终结果，返回音频时长（秒），失败返回 -1
+        pub fn speech_transcribe_file(path: *const c_char, progress: FileProgressCallback) -> f64;
         pub fn speech_stop();
+        /// 状态码：0=空闲, 1=启动中, 2=识别中, 3=停止中, -1=错误（见 RecognitionStatus）
         pub fn speech_get_status() -> c_int;
         pub fn speech_set_callback(callback: TranscriptionCallback);
         pub fn speech_set_error_callback(callback: ErrorCallback);
@@ -46,38 +408,834 @@ mod ffi {
 /// 全局转录结果存储
 static INIT: Once = Once::new();
 static IS_CAPTURING: AtomicBool = AtomicBool::new(false);
+// 是否正在启动捕获，与 IS_CAPTURING 一起保证同一时间只有一次启动
+static IS_STARTING: AtomicBool = AtomicBool::new(false);
+// 原生模块是否可用（回调注册完成后置位）
+static NATIVE_AVAILABLE: AtomicBool = AtomicBool::new(false);
+// 本次会话是否已因错误发送过通知，避免连续错误重复通知
+static ERROR_NOTIFIED: AtomicBool = AtomicBool::new(false);
+// 本次会话是否已因停止关键词请求停止，避免重复触发
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+// 识别器未运行时丢弃的音频采样数，每次开始转录时清零
+static DROPPED_SAMPLES: AtomicU64 = AtomicU64::new(0);
+// 回调计数（诊断用），每次开始转录时清零
+static AUDIO_CALLBACKS: AtomicU64 = AtomicU64::new(0);
+static TRANSCRIPTION_CALLBACKS: AtomicU64 = AtomicU64::new(0);
+static FINAL_SEGMENTS: AtomicU64 = AtomicU64::new(0);
+// 实际有音频输入的累计时长（微秒），暂停和音频中断期间不增加
+static ACTIVE_CAPTURE_MICROS: AtomicU64 = AtomicU64::new(0);
+// 部分结果事件的最小发送间隔（毫秒），0 表示不限制
+static PARTIAL_EMIT_INTERVAL_MS: AtomicU64 = AtomicU64::new(DEFAULT_PARTIAL_EMIT_INTERVAL_MS);
+// 节流窗口内有未发送的部分结果，窗口结束时补发
+static PARTIAL_PENDING: AtomicBool = AtomicBool::new(false);
+// 是否已安排窗口结束时的补发，同一时间最多一个
+static PARTIAL_FLUSH_SCHEDULED: AtomicBool = AtomicBool::new(false);
+// 已写入会话文件的已确认文本字符数
+static SPILLED_CHARS: AtomicUsize = AtomicUsize::new(0);
+// 内存中已确认文本的最大字符数，0 表示不限制
+static BUFFER_LIMIT: AtomicUsize = AtomicUsize::new(0);
+// 内存中片段的最大数量，0 表示不限制
+static SEGMENT_LIMIT: AtomicUsize = AtomicUsize::new(0);
+// 已写入片段溢出文件的片段数
+static SPILLED_SEGMENTS: AtomicUsize = AtomicUsize::new(0);
+// 是否已暂停（暂停时不向识别器输送音频）
+static IS_PAUSED: AtomicBool = AtomicBool::new(false);
+// 本次会话是否检测到持续削波，每次开始转录时清零
+static IS_CLIPPING: AtomicBool = AtomicBool::new(false);
+// 连续削波的音频时长（微秒），遇到未削波的音频时清零
+#[cfg(native_audio)]
+static CLIPPED_STREAK_MICROS: AtomicU64 = AtomicU64::new(0);
+// 静音分段：连续静音达到该时长（毫秒）时强制分段，0 表示关闭
+static SILENCE_SPLIT_MS: AtomicU64 = AtomicU64::new(0);
+// 连续静音的音频时长（微秒），遇到有声音的音频时清零
+#[cfg(native_audio)]
+static SILENCE_STREAK_MICROS: AtomicU64 = AtomicU64::new(0);
+// 静音分段后置位，下一个确认片段另起一行
+static PARAGRAPH_BREAK: AtomicBool = AtomicBool::new(false);
+// 是否强制直通音频（跳过格式检测与转换，由用户保证输入已是 16kHz 单声道）
+static AUDIO_PASSTHROUGH: AtomicBool = AtomicBool::new(false);
+// 本次会话是否已记录音频处理路径，每次开始转录时清零
+static AUDIO_PATH_LOGGED: AtomicBool = AtomicBool::new(false);
+// 是否对确认片段做标点、大小写后处理
+static POST_PROCESSING: AtomicBool = AtomicBool::new(false);
+// 是否启用自动语言检测
+static AUTO_LANGUAGE: AtomicBool = AtomicBool::new(false);
+// 当前音频电平（f32 的位表示）
+static AUDIO_LEVEL: AtomicU32 = AtomicU32::new(0);
+
+// 存储所有已确认（isFinal=true）的转录文本
+static CONFIRMED_BUFFER: Mutex<String> = Mutex::new(String::new());
+// 存储当前正在进行的识别结果（完整的当前句子）
+static CURRENT_TRANSCRIPTION: Mutex<String> = Mutex::new(String::new());
+// 原生模块错误历史（最旧的在前），跨会话保留，见 ERROR_HISTORY_CAPACITY
+static ERROR_HISTORY: Mutex<VecDeque<ErrorRecord>> = Mutex::new(VecDeque::new());
+// 最近一次错误尚未被读取（get_error / take_error 只报告未读取的错误），每次开始转录时清零
+static ERROR_PENDING: AtomicBool = AtomicBool::new(false);
+// 已确认的转录片段及其时间信息
+static SEGMENTS: Mutex<Vec<TranscriptSegment>> = Mutex::new(Vec::new());
+// 当前片段首次收到部分结果的时间偏移
+static CURRENT_SEGMENT_START: Mutex<Option<u64>> = Mutex::new(None);
+// 最近一次回调的识别置信度
+static LATEST_CONFIDENCE: Mutex<f32> = Mutex::new(0.0);
+// 静音分段时提前确认的部分结果，见 strip_forced_prefix
+static SILENCE_SPLIT: Mutex<SilenceSplit> = Mutex::new(SilenceSplit {
+    last_partial: String::new(),
+    forced_prefix: String::new(),
+});
+// 当前正在使用的音频源，停止时据此关闭对应的原生捕获
+static ACTIVE_SOURCE: Mutex<AudioSource> = Mutex::new(AudioSource::SystemAudio);
+// 捕获计时，用于计算片段时间偏移
+static CAPTURE_CLOCK: Mutex<CaptureClock> = Mutex::new(CaptureClock {
+    start: None,
+    paused_at: None,
+    paused_total: Duration::ZERO,
+});
+// 上次发送音频电平事件的时刻
+static LAST_LEVEL_EMIT: Mutex<Option<Instant>> = Mutex::new(None);
+// 上一次音频回调的时间戳（秒），用于累计有效时长
+static LAST_SAMPLE_TIMESTAMP: Mutex<Option<f64>> = Mutex::new(None);
+// 上一次事件中发送的当前识别文本，用于计算稳定前缀
+static LAST_EMITTED_PARTIAL: Mutex<String> = Mutex::new(String::new());
+// 上次发送部分结果事件的时刻
+static LAST_PARTIAL_EMIT: Mutex<Option<Instant>> = Mutex::new(None);
+// 自动检测模式下本次会话检测到的语言
+static DETECTED_LANGUAGE: Mutex<Option<String>> = Mutex::new(None);
+// 本次会话溢出文本的落盘文件
+static SESSION_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+// 本次会话溢出片段的落盘文件（每行一个 JSON 片段）
+static SEGMENT_SPILL_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+// 当前识别语言，用于确定默认的片段分隔符
+static RECOGNITION_LANGUAGE: Mutex<String> = Mutex::new(String::new());
+// 用户自定义的片段分隔符，None 时按语言选择默认值
+static SEGMENT_SEPARATOR: Mutex<Option<String>> = Mutex::new(None);
+// 停止关键词，最终片段中出现时自动停止转录
+static STOP_KEYWORD: Mutex<Option<String>> = Mutex::new(None);
+// 实时输出文件，每个最终片段追加一行，供外部工具 tail 读取
+static LIVE_OUTPUT_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+// 当前转录会话 ID，每次开始转录时由应用层生成
+static SESSION_ID: Mutex<Option<String>> = Mutex::new(None);
+// 用于向前端发送事件的应用句柄
+static APP_HANDLE: Mutex<Option<AppHandle>> = Mutex::new(None);
+
+/// 初始化时健康检查未通过（或尚未初始化）时拒绝调用原生接口
+#[cfg(native_audio)]
+fn ensure_native_available() -> Result<(), AppError> {
+    if NATIVE_AVAILABLE.load(Ordering::SeqCst) {
+        Ok(())
+    } else {
+        Err(AppError::Native("原生音频模块不可用，详见错误历史".to_string()))
+    }
+}
+
+/// 启动捕获的占位标记，离开作用域时释放
+struct StartGuard;
+
+impl StartGuard {
+    /// 原子地占用启动权；已在捕获或另一次启动尚未结束时返回 AlreadyCapturing
+    fn acquire() -> Result<Self, AppError> {
+        if IS_STARTING
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(AppError::AlreadyCapturing);
+        }
+        let guard = StartGuard;
+        if IS_CAPTURING.load(Ordering::SeqCst) {
+            return Err(AppError::AlreadyCapturing);
+        }
+        Ok(guard)
+    }
+}
+
+impl Drop for StartGuard {
+    fn drop(&mut self) {
+        IS_STARTING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// 捕获计时：开始时刻与暂停时长，片段时间按扣除暂停后的录制时间计算，与导出的字幕时间轴一致
+struct CaptureClock {
+    start: Option<Instant>,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+}
+
+impl CaptureClock {
+    /// 扣除暂停（包括正在进行的暂停）后的录制时长
+    fn elapsed(&self) -> Option<Duration> {
+        let paused = self.paused_total + self.paused_at.map(|t| t.elapsed()).unwrap_or_default();
+        self.start.map(|t| t.elapsed().saturating_sub(paused))
+    }
+}
+
+/// 重新开始计时（开始转录时调用）
+fn reset_capture_clock() {
+    *lock_recover(&CAPTURE_CLOCK, "CAPTURE_CLOCK") = CaptureClock {
+        start: Some(Instant::now()),
+        paused_at: None,
+        paused_total: Duration::ZERO,
+    };
+}
+
+/// 距离捕获开始的毫秒数（不含暂停时间）
+fn elapsed_ms() -> u64 {
+    lock_recover(&CAPTURE_CLOCK, "CAPTURE_CLOCK")
+        .elapsed()
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// 清零回调计数
+fn reset_metrics() {
+    AUDIO_CALLBACKS.store(0, Ordering::Relaxed);
+    TRANSCRIPTION_CALLBACKS.store(0, Ordering::Relaxed);
+    FINAL_SEGMENTS.store(0, Ordering::Relaxed);
+}
+
+/// 记录一个已确认的片段，开始时间取当前片段首个部分结果的时间
+fn push_segment(text: &str, raw_text: Option<String>, confidence: f32) {
+    let end_ms = elapsed_ms();
+    let start_ms = CURRENT_SEGMENT_START.lock()
+        .ok()
+        .and_then(|mut start| start.take())
+        .unwrap_or(end_ms);
+    
+    let mut segments = lock_recover(&SEGMENTS, "SEGMENTS");
+    segments.push(TranscriptSegment {
+        text: text.to_string(),
+        start_ms,
+        end_ms,
+        confidence,
+        raw_text,
+    });
+    rotate_segments(&mut segments);
+}
+
+/// 片段数量超过上限时，将最旧的片段逐行写入片段溢出文件并从内存中移除
+/// 溢出文件保留完整的时间信息，完整片段列表 = 溢出文件 + 内存，见 AudioBridge::for_each_segment
+fn rotate_segments(segments: &mut Vec<TranscriptSegment>) {
+    let limit = SEGMENT_LIMIT.load(Ordering::SeqCst);
+    if limit == 0 || segments.len() <= limit {
+        return;
+    }
+    
+    let Some(path) = spill_file_path(&SEGMENT_SPILL_FILE, "segments.ndjson") else {
+        return;
+    };
+    let mut file = match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            log::warn!("打开片段溢出文件失败: {}", e);
+            return;
+        }
+    };
+    
+    let excess = segments.len() - limit;
+    let mut written = 0;
+    for seg in &segments[..excess] {
+        let line = match serde_json::to_string(seg) {
+            Ok(line) => line,
+            Err(e) => {
+                log::warn!("序列化片段失败: {}", e);
+                break;
+            }
+        };
+        if let Err(e) = writeln!(file, "{}", line) {
+            log::warn!("写入片段溢出文件失败: {}", e);
+            break;
+        }
+        written += 1;
+    }
+    
+    // 只移除已成功写入的片段
+    segments.drain(..written);
+    SPILLED_SEGMENTS.fetch_add(written, Ordering::SeqCst);
+}
+
+/// 自动检测模式下，根据本次会话第一个确认片段推断语言并切换识别器
+/// 注意：正在运行的识别任务不会切换语言，新语言从下一次开始识别时生效
+fn detect_language_from_segment(text: &str) {
+    if !AUTO_LANGUAGE.load(Ordering::SeqCst) {
+        return;
+    }
+    
+    let Ok(mut detected) = DETECTED_LANGUAGE.lock() else {
+        return;
+    };
+    if detected.is_some() {
+        return;
+    }
+    
+    if let Some(code) = language::detect_language(text) {
+        log::info!("自动检测到语言: {}", code);
+        AudioBridge::set_language(code);
+        *detected = Some(code.to_string());
+    }
+}
+
+/// 获取（必要时创建）本次会话的溢出文件路径
+fn session_file_path() -> Option<PathBuf> {
+    spill_file_path(&SESSION_FILE, "txt")
+}
+
+/// 获取（必要时创建）本次会话的落盘文件路径，文件位于缓存目录，按会话 ID 和后缀命名
+/// （没有会话 ID 时按当前时间命名）
+fn spill_file_path(slot: &Mutex<Option<PathBuf>>, suffix: &str) -> Option<PathBuf> {
+    let mut session = slot.lock().ok()?;
+    if session.is_none() {
+        let dir = APP_HANDLE.lock()
+            .ok()
+            .and_then(|h| h.as_ref().and_then(|app| app.path().app_cache_dir().ok()))
+            .unwrap_or_else(std::env::temp_dir);
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            log::warn!("无法创建会话文件目录: {}", e);
+        }
+        let name = current_session_id()
+            .unwrap_or_else(|| chrono::Local::now().format("%Y%m%d_%H%M%S").to_string());
+        let filename = format!("session_{}.{}", name, suffix);
+        *session = Some(dir.join(filename));
+    }
+    session.clone()
+}
+
+/// 已确认片段之间的分隔符：优先使用自定义值，否则中日韩语言不加分隔符，其他语言换行
+fn segment_separator() -> String {
+    if let Some(sep) = lock_recover(&SEGMENT_SEPARATOR, "SEGMENT_SEPARATOR").clone() {
+        return sep;
+    }
+    
+    let language = lock_recover(&RECOGNITION_LANGUAGE, "RECOGNITION_LANGUAGE").clone();
+    if language::is_cjk(&language) {
+        String::new()
+    } else {
+        "\n".to_string()
+    }
+}
+
+/// 去除最终片段开头与已确认文本末尾重复的部分
+/// 识别器返回累积结果时，最终片段可能重复已确认的内容；整段重复时返回空字符串
+fn trim_overlap<'a>(confirmed: &str, text: &'a str) -> &'a str {
+    let tail_start = confirmed.char_indices()
+        .rev()
+        .nth(MAX_OVERLAP_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    let tail = confirmed[tail_start..].trim_end();
+    let text = text.trim();
+    if tail.is_empty() {
+        return text;
+    }
+    
+    // 拉丁字母的重叠必须落在词边界上，中日韩文字不要求
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || ('\u{00C0}'..='\u{024F}').contains(&c);
+    
+    // 从最长的候选前缀（整个片段）开始，找到与已确认文本末尾相同的最长前缀
+    let boundaries: Vec<usize> = text.char_indices()
+        .map(|(i, _)| i)
+        .skip(1)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    for &end in boundaries.iter().rev() {
+        let prefix = &text[..end];
+        let prefix_chars = prefix.chars().count();
+        if prefix_chars < MIN_OVERLAP_CHARS {
+            break;
+        }
+        if !tail.ends_with(prefix) {
+            continue;
+        }
+        
+        let before = tail[..tail.len() - prefix.len()].chars().next_back();
+        let after = text[end..].chars().next();
+        let splits_word = |c: Option<char>, edge: Option<char>| {
+            matches!((c, edge), (Some(a), Some(b)) if is_word_char(a) && is_word_char(b))
+        };
+        if splits_word(before, prefix.chars().next()) || splits_word(after, prefix.chars().next_back()) {
+            continue;
+        }
+        
+        return text[end..].trim_start();
+    }
+    
+    text
+}
+
+/// 在片段中查找停止关键词（不区分大小写），找到时返回关键词之前的文本
+fn strip_stop_keyword(text: &str) -> Option<String> {
+    let keyword = lock_recover(&STOP_KEYWORD, "STOP_KEYWORD").clone()?;
+    let keyword = keyword.to_lowercase();
+    
+    let position = text.char_indices()
+        .map(|(i, _)| i)
+        .find(|&i| text[i..].to_lowercase().starts_with(&keyword))?;
+    
+    let kept = text[..position].trim_end_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation() || "，。、！？".contains(c));
+    Some(kept.to_string())
+}
 
-lazy_static::lazy_static! {
-    // 存储所有已确认（isFinal=true）的转录文本
-    static ref CONFIRMED_BUFFER: Arc<Mutex<String>> = Arc::new(Mutex::new(String::new()));
-    // 存储当前正在进行的识别结果（完整的当前句子）
-    static ref CURRENT_TRANSCRIPTION: Arc<Mutex<String>> = Arc::new(Mutex::new(String::new()));
-    static ref ERROR_MESSAGE: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+/// 启用后处理时返回处理后的文本和原始文本；未启用或文本未变化时原始文本为 None
+fn post_process(text: &str) -> (String, Option<String>) {
+    if !POST_PROCESSING.load(Ordering::Relaxed) || text.is_empty() {
+        return (text.to_string(), None);
+    }
+    
+    let language = DETECTED_LANGUAGE.lock()
+        .ok()
+        .and_then(|d| d.clone())
+        .unwrap_or_else(|| lock_recover(&RECOGNITION_LANGUAGE, "RECOGNITION_LANGUAGE").clone());
+    let processed = crate::postprocess::process_segment(text, &language);
+    if processed == text {
+        (processed, None)
+    } else {
+        (processed, Some(text.to_string()))
+    }
+}
+
+/// 将片段追加到已确认文本，非空时先加分隔符
+fn append_segment(confirmed: &mut String, text: &str) {
+    // 静音分段后的第一个片段另起一行
+    let paragraph_break = PARAGRAPH_BREAK.swap(false, Ordering::SeqCst);
+    if !confirmed.is_empty() {
+        if paragraph_break {
+            confirmed.push('\n');
+        } else {
+            confirmed.push_str(&segment_separator());
+        }
+    }
+    confirmed.push_str(text);
+}
+
+/// 已确认文本超过上限时，将最旧的片段追加写入会话文件并从内存中移除
+/// 完整文本 = 会话文件内容 + 内存中的已确认文本，见 AudioBridge::get_complete_transcription
+fn rotate_buffer(confirmed: &mut String) {
+    let limit = BUFFER_LIMIT.load(Ordering::SeqCst);
+    if limit == 0 || confirmed.chars().count() <= limit {
+        return;
+    }
+    
+    let Some(path) = session_file_path() else {
+        return;
+    };
+    let mut file = match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            log::warn!("打开会话文件失败: {}", e);
+            return;
+        }
+    };
+    
+    // 按分隔符逐段移出（连同分隔符一起写入），保留最新的一段，避免当前片段被截断
+    // 没有分隔符时按字符数截断
+    let sep = segment_separator();
+    while confirmed.chars().count() > limit {
+        let end = if sep.is_empty() {
+            let excess = confirmed.chars().count() - limit;
+            confirmed.char_indices().nth(excess).map_or(confirmed.len(), |(i, _)| i)
+        } else {
+            let Some(pos) = confirmed.find(&sep) else {
+                break;
+            };
+            pos + sep.len()
+        };
+        if let Err(e) = write!(file, "{}", &confirmed[..end]) {
+            log::warn!("写入会话文件失败: {}", e);
+            return;
+        }
+        SPILLED_CHARS.fetch_add(confirmed[..end].chars().count(), Ordering::SeqCst);
+        confirmed.drain(..end);
+    }
+}
+
+/// 当前转录会话 ID
+fn current_session_id() -> Option<String> {
+    lock_recover(&SESSION_ID, "SESSION_ID").clone()
+}
+
+/// 将实时输出路径中的会话 ID 占位符替换为当前会话 ID
+fn resolve_live_output_path(path: &Path) -> PathBuf {
+    let raw = path.to_string_lossy();
+    if !raw.contains(LIVE_OUTPUT_SESSION_PLACEHOLDER) {
+        return path.to_path_buf();
+    }
+    let session_id = current_session_id().unwrap_or_else(|| "unknown".to_string());
+    PathBuf::from(raw.replace(LIVE_OUTPUT_SESSION_PLACEHOLDER, &session_id))
+}
+
+/// 将最终片段追加到实时输出文件并立即刷新
+fn write_live_output(text: &str) {
+    let Some(path) = LIVE_OUTPUT_FILE.lock().ok().and_then(|p| p.clone()) else {
+        return;
+    };
+    let path = resolve_live_output_path(&path);
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| {
+            writeln!(file, "{}", text)?;
+            file.flush()
+        });
+    if let Err(e) = result {
+        log::warn!("写入实时输出文件失败: {}", e);
+    }
+}
+
+/// 确认一个最终片段：去除与已确认文本末尾的重叠、后处理后追加到已确认缓冲区，
+/// 并记录片段、写入实时输出。识别回调与模拟转录共用，返回实际追加的文本
+fn confirm_segment(text: &str, confidence: f32) -> String {
+    let (processed, raw_text) = {
+        let mut confirmed = lock_recover(&CONFIRMED_BUFFER, "CONFIRMED_BUFFER");
+        let deduped = trim_overlap(&confirmed, text).to_string();
+        if deduped.len() < text.len() {
+            log::debug!("去除重叠文本: {} -> {}", text, deduped);
+        }
+        let (processed, raw_text) = post_process(&deduped);
+        if !processed.is_empty() {
+            append_segment(&mut confirmed, &processed);
+            rotate_buffer(&mut confirmed);
+        }
+        (processed, raw_text)
+    };
+    if !processed.is_empty() {
+        push_segment(&processed, raw_text, confidence);
+        detect_language_from_segment(&processed);
+        write_live_output(&processed);
+    }
+    // 清空当前转录，因为已经被确认了
+    lock_recover(&CURRENT_TRANSCRIPTION, "CURRENT_TRANSCRIPTION").clear();
+    processed
+}
+
+/// 部分结果事件节流：距上次发送未超过间隔时不立即发送，标记为待发送，
+/// 窗口结束时补发一次最新的部分结果，窗口内的多次更新合并为这一次
+fn partial_emit_due() -> bool {
+    let interval = Duration::from_millis(PARTIAL_EMIT_INTERVAL_MS.load(Ordering::Relaxed));
+    let remaining = LAST_PARTIAL_EMIT.lock()
+        .map(|mut last| match last.map(|t| t.elapsed()) {
+            Some(elapsed) if elapsed < interval => Some(interval - elapsed),
+            _ => {
+                *last = Some(Instant::now());
+                None
+            }
+        })
+        .unwrap_or(None);
+    
+    match remaining {
+        None => {
+            PARTIAL_PENDING.store(false, Ordering::SeqCst);
+            true
+        }
+        Some(delay) => {
+            PARTIAL_PENDING.store(true, Ordering::SeqCst);
+            schedule_partial_flush(delay);
+            false
+        }
+    }
+}
+
+/// 安排在节流窗口结束时补发最新的部分结果；期间已有新的发送或最终结果时不再补发
+fn schedule_partial_flush(delay: Duration) {
+    if PARTIAL_FLUSH_SCHEDULED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    
+    let spawned = std::thread::Builder::new()
+        .name("partial-flush".into())
+        .spawn(move || {
+            std::thread::sleep(delay);
+            PARTIAL_FLUSH_SCHEDULED.store(false, Ordering::SeqCst);
+            if !PARTIAL_PENDING.swap(false, Ordering::SeqCst) || !IS_CAPTURING.load(Ordering::SeqCst) {
+                return;
+            }
+            if let Ok(mut last) = LAST_PARTIAL_EMIT.lock() {
+                *last = Some(Instant::now());
+            }
+            emit_transcription_update(false);
+        });
+    if let Err(e) = spawned {
+        PARTIAL_FLUSH_SCHEDULED.store(false, Ordering::SeqCst);
+        log::warn!("无法安排部分结果补发: {}", e);
+    }
+}
+
+/// 向前端发送事件
+fn emit_event<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Ok(handle) = APP_HANDLE.lock() {
+        if let Some(app) = handle.as_ref() {
+            if let Err(e) = app.emit(event, payload) {
+                log::warn!("发送事件 {} 失败: {}", event, e);
+            }
+        }
+    }
+}
+
+/// 发送带当前会话 ID 的事件
+fn emit_session_event<S: Serialize + Clone>(event: &str, payload: S) {
+    emit_event(event, SessionEvent {
+        session_id: current_session_id(),
+        payload,
+    });
+}
+
+/// 向前端发送转录更新事件
+fn emit_transcription_update(is_final: bool) {
+    let latest_text = AudioBridge::get_latest_transcription();
+    
+    // 与上一次发送的文本比较，找出未变化的前缀
+    let (stable_prefix_len, revised_suffix) = {
+        let mut previous = lock_recover(&LAST_EMITTED_PARTIAL, "LAST_EMITTED_PARTIAL");
+        let prefix_bytes: usize = previous.chars()
+            .zip(latest_text.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(c, _)| c.len_utf8())
+            .sum();
+        let stable = latest_text[..prefix_bytes].encode_utf16().count();
+        let suffix = latest_text[prefix_bytes..].to_string();
+        *previous = latest_text.clone();
+        (stable, suffix)
+    };
+    
+    let payload = TranscriptionUpdate {
+        latest_text,
+        full_text: AudioBridge::get_full_transcription(),
+        is_final,
+        stable_prefix_len,
+        revised_suffix,
+        session_id: current_session_id(),
+    };
+    
+    emit_event(TRANSCRIPTION_UPDATE_EVENT, payload);
+}
+
+/// 计算样本的 RMS 电平（0.0 ~ 1.0）
+#[cfg(native_audio)]
+fn compute_rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_squares: f32 = samples.iter().map(|s| s * s).sum();
+    let rms = (sum_squares / samples.len() as f32).sqrt();
+    if rms.is_finite() { rms.clamp(0.0, 1.0) } else { 0.0 }
+}
+
+/// 更新音频电平，并按固定间隔向前端发送
+#[cfg(native_audio)]
+fn update_audio_level(level: f32) {
+    AUDIO_LEVEL.store(level.to_bits(), Ordering::Relaxed);
+    
+    let should_emit = LAST_LEVEL_EMIT.lock()
+        .map(|mut last| {
+            let due = last.map_or(true, |t| t.elapsed().as_millis() >= AUDIO_LEVEL_EMIT_INTERVAL_MS);
+            if due {
+                *last = Some(Instant::now());
+            }
+            due
+        })
+        .unwrap_or(false);
+    
+    if should_emit {
+        emit_session_event(AUDIO_LEVEL_EVENT, level);
+    }
+}
+
+/// 根据音频回调时间戳累计有效时长
+/// 与上次回调的间隔正常时按间隔累计；首次回调或间隔异常（音频中断）时只计入本次样本自身的时长
+#[cfg(native_audio)]
+fn accumulate_active_time(timestamp: f64, chunk_seconds: f64) {
+    let Ok(mut last) = LAST_SAMPLE_TIMESTAMP.lock() else {
+        return;
+    };
+    
+    let delta = match *last {
+        Some(prev) if timestamp > prev && timestamp - prev <= MAX_SAMPLE_GAP_SECONDS => timestamp - prev,
+        _ => chunk_seconds,
+    };
+    *last = Some(timestamp);
+    
+    ACTIVE_CAPTURE_MICROS.fetch_add((delta * 1_000_000.0) as u64, Ordering::Relaxed);
+}
+
+/// 削波检测：削波样本占比超过阈值的音频连续达到一定时长时置位削波标志，
+/// 每次会话只在首次置位时发送警告事件
+#[cfg(native_audio)]
+fn detect_clipping(samples: &[f32], chunk_seconds: f64) {
+    let clipped = samples.iter().filter(|s| s.abs() >= CLIPPING_THRESHOLD).count();
+    if clipped == 0 || (clipped as f32) < samples.len() as f32 * CLIPPING_RATIO {
+        CLIPPED_STREAK_MICROS.store(0, Ordering::Relaxed);
+        return;
+    }
+    
+    let micros = (chunk_seconds * 1_000_000.0) as u64;
+    let streak = CLIPPED_STREAK_MICROS.fetch_add(micros, Ordering::Relaxed) + micros;
+    if streak as f64 >= CLIPPING_SUSTAIN_SECONDS * 1_000_000.0 && !IS_CLIPPING.swap(true, Ordering::SeqCst) {
+        log::warn!("检测到持续削波，建议降低音源音量");
+        emit_session_event(CLIPPING_EVENT, ());
+    }
+}
+
+/// 静音分段：连续静音达到设定时长时强制分段，每段静音只触发一次，有声音后重新计时
+#[cfg(native_audio)]
+fn detect_silence(level: f32, chunk_seconds: f64) {
+    let split_ms = SILENCE_SPLIT_MS.load(Ordering::Relaxed);
+    if split_ms == 0 {
+        return;
+    }
+    if level >= SILENCE_RMS_THRESHOLD {
+        SILENCE_STREAK_MICROS.store(0, Ordering::Relaxed);
+        return;
+    }
+    
+    let micros = (chunk_seconds * 1_000_000.0) as u64;
+    let before = SILENCE_STREAK_MICROS.fetch_add(micros, Ordering::Relaxed);
+    let threshold = split_ms * 1000;
+    if before < threshold && before + micros >= threshold {
+        force_segment_boundary();
+    }
+}
+
+/// 强制分段：识别器尚未给出最终结果时，把当前部分结果提前确认，下一个片段另起一行
+/// 识别器之后的部分结果和最终结果仍包含已确认的文本，由 strip_forced_prefix 去掉
+#[cfg(native_audio)]
+fn force_segment_boundary() {
+    let pending = {
+        let mut split = lock_recover(&SILENCE_SPLIT, "SILENCE_SPLIT");
+        let pending = lock_recover(&CURRENT_TRANSCRIPTION, "CURRENT_TRANSCRIPTION").clone();
+        if !pending.trim().is_empty() {
+            split.forced_prefix = split.last_partial.clone();
+        }
+        pending
+    };
+    
+    if !pending.trim().is_empty() {
+        let confidence = LATEST_CONFIDENCE.lock().map(|c| *c).unwrap_or(0.0);
+        let text = confirm_segment(&pending, confidence);
+        log::info!("静音分段，提前确认: {}", text);
+    }
+    PARAGRAPH_BREAK.store(true, Ordering::SeqCst);
+    
+    if !pending.trim().is_empty() {
+        emit_transcription_update(true);
+    }
+}
+
+/// 去掉识别结果中已被静音分段提前确认的前缀，并记录最新的原始部分结果
+/// 识别器修订了已确认的部分导致前缀不匹配时原样返回，由 confirm_segment 的重叠检测兜底
+/// 最终结果到达后当前识别段落结束，清空记录
+fn strip_forced_prefix(text: &str, is_final: bool) -> String {
+    let mut split = lock_recover(&SILENCE_SPLIT, "SILENCE_SPLIT");
+    let stripped = match text.strip_prefix(split.forced_prefix.as_str()) {
+        Some(rest) if !split.forced_prefix.is_empty() => rest.trim_start().to_string(),
+        _ => text.to_string(),
+    };
+    
+    if is_final {
+        split.forced_prefix.clear();
+        split.last_partial.clear();
+    } else {
+        split.last_partial = text.to_string();
+    }
+    stripped
 }
 
 /// 音频样本回调 - 将音频数据传递给语音识别
-#[cfg(feature = "swift_audio")]
-extern "C" fn on_audio_sample(samples: *const c_float, count: c_int, _timestamp: f64) {
+#[cfg(native_audio)]
+extern "C" fn on_audio_sample(
+    samples: *const c_float,
+    count: c_int,
+    timestamp: f64,
+    sample_rate: f64,
+    channels: c_int,
+) {
     if samples.is_null() || count <= 0 {
         return;
     }
+    AUDIO_CALLBACKS.fetch_add(1, Ordering::Relaxed);
+    
+    let slice = unsafe { std::slice::from_raw_parts(samples, count as usize) };
+    let level = compute_rms(slice);
+    update_audio_level(level);
+    
+    if IS_PAUSED.load(Ordering::SeqCst) {
+        // 暂停期间不计时，恢复后重新建立时间基准
+        if let Ok(mut last) = LAST_SAMPLE_TIMESTAMP.lock() {
+            *last = None;
+        }
+        return;
+    }
+    
+    // 未在转录或识别器已停止时丢弃音频，避免向已停止的识别器追加数据
+    let capturing = IS_CAPTURING.load(Ordering::SeqCst) || IS_STARTING.load(Ordering::SeqCst);
+    let recognizing = matches!(
+        RecognitionStatus::from(unsafe { ffi::speech_get_status() }),
+        RecognitionStatus::Starting | RecognitionStatus::Running
+    );
+    if !capturing || !recognizing {
+        if DROPPED_SAMPLES.fetch_add(count as u64, Ordering::Relaxed) == 0 {
+            log::warn!("识别器未运行，开始丢弃音频采样");
+        }
+        return;
+    }
     
-    unsafe {
-        ffi::speech_append_audio(samples, count);
+    // 识别器需要 16kHz 单声道，其他格式先混音、重采样
+    let channels = channels.max(1) as usize;
+    let source_rate = if sample_rate > 0.0 { sample_rate } else { SAMPLE_RATE };
+    let frames = count as usize / channels;
+    accumulate_active_time(timestamp, frames as f64 / source_rate);
+    detect_clipping(slice, frames as f64 / source_rate);
+    detect_silence(level, frames as f64 / source_rate);
+    
+    let forced = AUDIO_PASSTHROUGH.load(Ordering::Relaxed);
+    let passthrough = forced || (channels == 1 && (source_rate - SAMPLE_RATE).abs() < f64::EPSILON);
+    if !AUDIO_PATH_LOGGED.swap(true, Ordering::Relaxed) {
+        if passthrough {
+            log::info!(
+                "音频直通：{}Hz, {} 声道{}",
+                source_rate, channels, if forced { "（已强制直通）" } else { "" }
+            );
+        } else {
+            log::info!("音频转换：{}Hz, {} 声道 -> {}Hz 单声道", source_rate, channels, SAMPLE_RATE);
+        }
+    }
+    
+    if passthrough {
+        unsafe {
+            ffi::speech_append_audio(samples, count);
+        }
+    } else {
+        let converted = crate::dsp::to_mono_at_rate(slice, channels, source_rate, SAMPLE_RATE);
+        if converted.is_empty() {
+            return;
+        }
+        unsafe {
+            ffi::speech_append_audio(converted.as_ptr(), converted.len() as c_int);
+        }
     }
 }
 
-#[cfg(not(feature = "swift_audio"))]
-extern "C" fn on_audio_sample(_samples: *const c_float, _count: c_int, _timestamp: f64) {}
+#[cfg(not(native_audio))]
+extern "C" fn on_audio_sample(
+    _samples: *const c_float,
+    _count: c_int,
+    _timestamp: f64,
+    _sample_rate: f64,
+    _channels: c_int,
+) {}
 
 /// 转录结果回调
 /// 注意：SFSpeechRecognizer 每次回调返回的是从识别开始到现在的完整转录
 /// - 当 is_final = false 时：是正在进行的识别，可能会被更新
 /// - 当 is_final = true 时：当前识别段落已确认，不会再更改
-extern "C" fn on_transcription(text: *const c_char, is_final: bool) {
+extern "C" fn on_transcription(text: *const c_char, is_final: bool, confidence: c_float) {
     if text.is_null() {
         return;
     }
+    TRANSCRIPTION_CALLBACKS.fetch_add(1, Ordering::Relaxed);
+    if is_final {
+        FINAL_SEGMENTS.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    let confidence = if confidence.is_finite() { confidence.clamp(0.0, 1.0) } else { 0.0 };
+    if let Ok(mut latest) = LATEST_CONFIDENCE.lock() {
+        *latest = confidence;
+    }
     
     let text_str = unsafe {
         match CStr::from_ptr(text).to_str() {
@@ -85,31 +1243,56 @@ extern "C" fn on_transcription(text: *const c_char, is_final: bool) {
             Err(_) => return,
         }
     };
+    let text_str = strip_forced_prefix(&text_str, is_final);
     
+    let mut keyword_hit = false;
     if is_final {
-        // 最终结果：将此文本追加到已确认缓冲区
-        if let Ok(mut confirmed) = CONFIRMED_BUFFER.lock() {
-            if !text_str.is_empty() {
-                if !confirmed.is_empty() {
-                    confirmed.push_str("\n");
-                }
-                confirmed.push_str(&text_str);
+        // 停止关键词：只保留关键词之前的文本，并请求停止转录
+        let text_str = match strip_stop_keyword(&text_str) {
+            Some(kept) => {
+                keyword_hit = true;
+                kept
             }
-        }
-        // 清空当前转录，因为已经被确认了
-        if let Ok(mut current) = CURRENT_TRANSCRIPTION.lock() {
-            current.clear();
-        }
+            None => text_str,
+        };
+        
+        let text_str = confirm_segment(&text_str, confidence);
         log::info!("转录(最终): {}", text_str);
     } else {
-        // 部分结果：更新当前正在进行的转录
-        if let Ok(mut current) = CURRENT_TRANSCRIPTION.lock() {
-            *current = text_str.clone();
+        // 部分结果：记录当前片段的开始时间并更新正在进行的转录
+        if let Ok(mut start) = CURRENT_SEGMENT_START.lock() {
+            if start.is_none() {
+                *start = Some(elapsed_ms());
+            }
         }
+        // 始终更新当前转录，轮询方可读到最新文本
+        *lock_recover(&CURRENT_TRANSCRIPTION, "CURRENT_TRANSCRIPTION") = text_str.clone();
         log::debug!("转录(部分): {}", text_str);
+        
+        if !partial_emit_due() {
+            return;
+        }
+    }
+    
+    // 最终结果总是立即发送，并取代尚未补发的部分结果
+    if is_final {
+        PARTIAL_PENDING.store(false, Ordering::SeqCst);
+    }
+    emit_transcription_update(is_final);
+    
+    if keyword_hit && !STOP_REQUESTED.swap(true, Ordering::SeqCst) {
+        let keyword = lock_recover(&STOP_KEYWORD, "STOP_KEYWORD").clone().unwrap_or_default();
+        log::info!("识别到停止关键词 \"{}\"，自动停止转录", keyword);
+        emit_session_event(STOP_KEYWORD_EVENT, keyword);
     }
 }
 
+/// 文件转录进度回调
+#[cfg(native_audio)]
+extern "C" fn on_file_progress(progress: f64) {
+    emit_session_event(FILE_PROGRESS_EVENT, progress.clamp(0.0, 1.0));
+}
+
 /// 错误回调
 extern "C" fn on_error(message: *const c_char) {
     if message.is_null() {
@@ -125,9 +1308,40 @@ extern "C" fn on_error(message: *const c_char) {
     
     log::error!("原生模块错误: {}", msg);
     
-    if let Ok(mut error) = ERROR_MESSAGE.lock() {
-        *error = Some(msg);
-    }
+    emit_session_event(TRANSCRIPTION_ERROR_EVENT, msg.clone());
+    notify_error_once();
+    record_error(msg);
+}
+
+/// 将错误加入错误历史，并标记为待读取
+fn record_error(message: String) {
+    let mut history = lock_recover(&ERROR_HISTORY, "ERROR_HISTORY");
+    if history.len() >= ERROR_HISTORY_CAPACITY {
+        history.pop_front();
+    }
+    history.push_back(ErrorRecord {
+        timestamp: crate::storage::now_timestamp(),
+        message,
+    });
+    ERROR_PENDING.store(true, Ordering::SeqCst);
+}
+
+/// 捕获过程中首次出错时发送系统通知
+fn notify_error_once() {
+    if !IS_CAPTURING.load(Ordering::SeqCst) || ERROR_NOTIFIED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    
+    let Some(app) = APP_HANDLE.lock().ok().and_then(|h| h.clone()) else {
+        return;
+    };
+    let language = DETECTED_LANGUAGE.lock()
+        .ok()
+        .and_then(|l| l.clone())
+        .unwrap_or_else(|| language::DEFAULT_LANGUAGE.to_string());
+    let words = language::count_words(&AudioBridge::get_full_transcription(), &language);
+    
+    crate::notify::notify_session_end(&app, "转录因错误中断", (elapsed_ms() / 1000) as i32, words);
 }
 
 /// 音频桥接模块
@@ -135,45 +1349,81 @@ pub struct AudioBridge;
 
 impl AudioBridge {
     /// 初始化回调
-    #[cfg(feature = "swift_audio")]
-    pub fn init() {
+    #[cfg(native_audio)]
+    pub fn init(app: AppHandle) {
         INIT.call_once(|| {
+            if let Ok(mut handle) = APP_HANDLE.lock() {
+                *handle = Some(app);
+            }
+            
+            // 运行时确认链接的库可用：接口版本不符（库过期或链接了错误的库）时进入降级模式
+            let version = unsafe { ffi::audio_bridge_health_check() };
+            if version != NATIVE_ABI_VERSION {
+                let message = format!(
+                    "原生音频模块版本不符（期望 {}，实际 {}），请重新编译 Swift 库后重新构建应用",
+                    NATIVE_ABI_VERSION, version
+                );
+                log::error!("{}，音频桥接进入降级模式", message);
+                record_error(message);
+                return;
+            }
+            
             unsafe {
                 ffi::audio_capture_set_callback(on_audio_sample);
                 ffi::audio_capture_set_error_callback(on_error);
+                ffi::mic_capture_set_callback(on_audio_sample);
+                ffi::mic_capture_set_error_callback(on_error);
                 ffi::speech_set_callback(on_transcription);
                 ffi::speech_set_error_callback(on_error);
             }
+            NATIVE_AVAILABLE.store(true, Ordering::SeqCst);
             log::info!("音频桥接已初始化 (Swift 模式)");
         });
     }
     
-    #[cfg(not(feature = "swift_audio"))]
-    pub fn init() {
+    #[cfg(not(native_audio))]
+    pub fn init(app: AppHandle) {
         INIT.call_once(|| {
-            log::info!("音频桥接已初始化 (模拟模式)");
+            if let Ok(mut handle) = APP_HANDLE.lock() {
+                *handle = Some(app);
+            }
+            if cfg!(feature = "swift_audio") {
+                log::error!("已启用 swift_audio 特性但构建时未找到 Swift 库，音频桥接进入降级模式");
+            } else {
+                log::info!("音频桥接已初始化 (模拟模式)");
+            }
         });
     }
     
-    /// 检查所有权限
-    #[cfg(feature = "swift_audio")]
-    pub fn check_permissions() -> (bool, bool) {
+    /// 原生模块是否可用
+    /// 启用 swift_audio 但未链接 Swift 库，或运行时健康检查失败时为降级模式，转录命令返回错误
+    pub fn is_native_available() -> bool {
+        NATIVE_AVAILABLE.load(Ordering::SeqCst)
+    }
+    
+    /// 检查所有权限（音频权限按音频源检查屏幕录制或麦克风）
+    #[cfg(native_audio)]
+    pub fn check_permissions(source: AudioSource) -> (bool, bool) {
         unsafe {
-            let audio_ok = ffi::audio_capture_check_permission();
+            let audio_ok = match source {
+                AudioSource::SystemAudio => ffi::audio_capture_check_permission(),
+                AudioSource::Microphone => ffi::mic_capture_check_permission(),
+            };
             let speech_ok = ffi::speech_check_permission();
             (audio_ok, speech_ok)
         }
     }
     
-    #[cfg(not(feature = "swift_audio"))]
-    pub fn check_permissions() -> (bool, bool) {
+    #[cfg(not(native_audio))]
+    pub fn check_permissions(_source: AudioSource) -> (bool, bool) {
         // 模拟模式：返回 false，提示需要权限
         (false, false)
     }
     
     /// 设置识别语言
-    #[cfg(feature = "swift_audio")]
+    #[cfg(native_audio)]
     pub fn set_language(language: &str) {
+        *lock_recover(&RECOGNITION_LANGUAGE, "RECOGNITION_LANGUAGE") = language.to_string();
         if let Ok(c_string) = CString::new(language) {
             unsafe {
                 ffi::speech_set_language(c_string.as_ptr());
@@ -181,127 +1431,727 @@ impl AudioBridge {
         }
     }
     
-    #[cfg(not(feature = "swift_audio"))]
-    pub fn set_language(_language: &str) {
+    #[cfg(not(native_audio))]
+    pub fn set_language(language: &str) {
+        *lock_recover(&RECOGNITION_LANGUAGE, "RECOGNITION_LANGUAGE") = language.to_string();
         log::info!("模拟模式：设置语言");
     }
     
+    /// 列出可用的音频输入设备
+    #[cfg(native_audio)]
+    pub fn list_audio_devices() -> Vec<AudioDevice> {
+        let ptr = unsafe { ffi::audio_list_devices() };
+        if ptr.is_null() {
+            return Vec::new();
+        }
+        
+        let devices = unsafe { CStr::from_ptr(ptr) }
+            .to_str()
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str(json).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                log::error!("解析音频设备列表失败: {}", e);
+                Vec::new()
+            });
+        
+        unsafe {
+            ffi::audio_free_string(ptr);
+        }
+        devices
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn list_audio_devices() -> Vec<AudioDevice> {
+        vec![AudioDevice {
+            id: "default".to_string(),
+            name: "Default".to_string(),
+            is_default: true,
+        }]
+    }
+    
+    /// 设置麦克风输入设备（None 表示使用系统默认设备），下次开始捕获时生效
+    #[cfg(native_audio)]
+    pub fn set_audio_device(id: Option<&str>) {
+        match id.map(CString::new) {
+            Some(Ok(c_string)) => unsafe { ffi::mic_capture_set_device(c_string.as_ptr()) },
+            Some(Err(_)) => log::error!("设备 ID 包含非法字符"),
+            None => unsafe { ffi::mic_capture_set_device(std::ptr::null()) },
+        }
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn set_audio_device(id: Option<&str>) {
+        log::info!("模拟模式：设置音频设备 {:?}", id);
+    }
+    
+    /// 列出可单独捕获音频的应用（系统音频源使用）
+    #[cfg(native_audio)]
+    pub fn list_capture_targets() -> Vec<CaptureTarget> {
+        let ptr = unsafe { ffi::audio_list_capture_targets() };
+        if ptr.is_null() {
+            return Vec::new();
+        }
+        
+        let targets = unsafe { CStr::from_ptr(ptr) }
+            .to_str()
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str(json).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                log::error!("解析可捕获应用列表失败: {}", e);
+                Vec::new()
+            });
+        
+        unsafe {
+            ffi::audio_free_string(ptr);
+        }
+        targets
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn list_capture_targets() -> Vec<CaptureTarget> {
+        Vec::new()
+    }
+    
+    /// 设置系统音频的捕获目标应用（None 表示整个系统），下次开始捕获时生效
+    #[cfg(native_audio)]
+    pub fn set_capture_target(bundle_id: Option<&str>) {
+        match bundle_id.map(CString::new) {
+            Some(Ok(c_string)) => unsafe { ffi::audio_capture_set_target(c_string.as_ptr()) },
+            Some(Err(_)) => log::error!("应用 bundle ID 包含非法字符"),
+            None => unsafe { ffi::audio_capture_set_target(std::ptr::null()) },
+        }
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn set_capture_target(bundle_id: Option<&str>) {
+        log::info!("模拟模式：设置捕获目标 {:?}", bundle_id);
+    }
+    
+    /// 设置自定义词汇（上下文短语），下次开始识别时生效
+    /// 忽略空字符串，超出 MAX_CONTEXTUAL_STRINGS 的部分会被截断
+    #[cfg(native_audio)]
+    pub fn set_contextual_strings(phrases: &[String]) {
+        let c_strings: Vec<CString> = phrases.iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .take(MAX_CONTEXTUAL_STRINGS)
+            .filter_map(|p| CString::new(p).ok())
+            .collect();
+        let pointers: Vec<*const c_char> = c_strings.iter().map(|c| c.as_ptr()).collect();
+        
+        unsafe {
+            ffi::speech_set_contextual_strings(pointers.as_ptr(), pointers.len() as c_int);
+        }
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn set_contextual_strings(phrases: &[String]) {
+        log::info!("模拟模式：设置 {} 个自定义词汇", phrases.len());
+    }
+    
+    /// 检查本机识别器是否支持指定语言
+    #[cfg(native_audio)]
+    pub fn supports_language(code: &str) -> bool {
+        match CString::new(code) {
+            Ok(c_string) => unsafe { ffi::speech_supports_language(c_string.as_ptr()) },
+            Err(_) => false,
+        }
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn supports_language(_code: &str) -> bool {
+        true // 模拟模式假设全部支持
+    }
+    
+    /// 获取应用语言列表中本机识别器实际支持的语言代码
+    pub fn supported_languages() -> Vec<String> {
+        language::SUPPORTED_LANGUAGES
+            .iter()
+            .filter(|(code, _)| Self::supports_language(code))
+            .map(|(code, _)| code.to_string())
+            .collect()
+    }
+    
+    /// 设置是否强制端侧识别
+    #[cfg(native_audio)]
+    pub fn set_on_device_required(required: bool) {
+        unsafe {
+            ffi::speech_set_on_device(required);
+        }
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn set_on_device_required(required: bool) {
+        log::info!("模拟模式：强制端侧识别 {}", required);
+    }
+    
+    /// 设置识别任务类型提示，下次开始识别时生效
+    #[cfg(native_audio)]
+    pub fn set_task_hint(hint: TaskHint) {
+        unsafe {
+            ffi::speech_set_task_hint(hint.native_value());
+        }
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn set_task_hint(hint: TaskHint) {
+        log::info!("模拟模式：识别任务类型 {:?}", hint);
+    }
+    
     /// 检查是否支持端侧识别
-    #[cfg(feature = "swift_audio")]
+    #[cfg(native_audio)]
     pub fn supports_on_device() -> bool {
         unsafe { ffi::speech_supports_on_device() }
     }
     
-    #[cfg(not(feature = "swift_audio"))]
+    #[cfg(not(native_audio))]
     pub fn supports_on_device() -> bool {
         true // 模拟模式假设支持
     }
     
-    /// 开始转录
-    #[cfg(feature = "swift_audio")]
-    pub fn start_transcription() -> Result<(), String> {
-        if IS_CAPTURING.load(Ordering::SeqCst) {
-            return Err("转录已在进行中".to_string());
+    /// 判断指定语言的识别方式；端侧支持按识别器当前语言检查，调用方需传入当前语言
+    pub fn recognition_mode(code: &str) -> RecognitionMode {
+        if !Self::supports_language(code) {
+            RecognitionMode::Unsupported
+        } else if Self::supports_on_device() {
+            RecognitionMode::OnDevice
+        } else {
+            RecognitionMode::RequiresNetwork
         }
+    }
+    
+    /// 预热识别器：提前创建识别器并加载端侧模型，不启动捕获，阻塞直到完成或超时
+    /// 预热期间占用启动权，捕获进行中时返回 AlreadyCapturing
+    #[cfg(native_audio)]
+    pub fn prewarm_recognizer() -> Result<PrewarmResult, AppError> {
+        ensure_native_available()?;
+        let _guard = StartGuard::acquire()?;
+        let code = unsafe { ffi::speech_prewarm() };
+        Ok(PrewarmResult {
+            success: code >= 0,
+            on_device_ready: code == 1,
+        })
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn prewarm_recognizer() -> Result<PrewarmResult, AppError> {
+        // 未启用 Swift 模块时无需预热；降级模式下原生模块缺失，如实报告失败
+        Ok(PrewarmResult {
+            success: !cfg!(feature = "swift_audio"),
+            on_device_ready: false,
+        })
+    }
+    
+    /// 开始转录
+    #[cfg(native_audio)]
+    pub fn start_transcription(source: AudioSource) -> Result<(), AppError> {
+        ensure_native_available()?;
+        let _guard = StartGuard::acquire()?;
         
-        // 清空之前的错误和缓冲
+        // 清空之前的错误、缓冲和诊断计数
         Self::clear_transcription();
-        if let Ok(mut error) = ERROR_MESSAGE.lock() {
-            *error = None;
-        }
+        DROPPED_SAMPLES.store(0, Ordering::Relaxed);
+        reset_metrics();
+        AUDIO_PATH_LOGGED.store(false, Ordering::Relaxed);
+        IS_CLIPPING.store(false, Ordering::SeqCst);
+        CLIPPED_STREAK_MICROS.store(0, Ordering::Relaxed);
+        SILENCE_STREAK_MICROS.store(0, Ordering::Relaxed);
+        ERROR_PENDING.store(false, Ordering::SeqCst);
         
-        // 启动语音识别
-        let speech_started = unsafe { ffi::speech_start() };
-        if !speech_started {
-            return Err("启动语音识别失败".to_string());
-        }
-        
-        // 启动音频捕获
-        let capture_started = unsafe { ffi::audio_capture_start() };
-        if !capture_started {
+        // 音频设备可能暂时被占用，捕获启动失败时短暂等待后重试
+        for attempt in 1..=CAPTURE_START_ATTEMPTS {
+            if attempt > 1 {
+                std::thread::sleep(std::time::Duration::from_millis(CAPTURE_RETRY_DELAY_MS));
+            }
+            
+            // 记录捕获开始时刻（在启动原生模块之前，保证回调能取到时间基准）
+            reset_capture_clock();
+            
+            // 启动语音识别
+            let speech_started = unsafe { ffi::speech_start() };
+            if !speech_started {
+                return Err(AppError::Native("启动语音识别失败".to_string()));
+            }
+            
+            // 按音频源启动对应的捕获
+            let capture_started = unsafe {
+                match source {
+                    AudioSource::SystemAudio => ffi::audio_capture_start(),
+                    AudioSource::Microphone => ffi::mic_capture_start(),
+                }
+            };
+            if capture_started {
+                if let Ok(mut active) = ACTIVE_SOURCE.lock() {
+                    *active = source;
+                }
+                IS_CAPTURING.store(true, Ordering::SeqCst);
+                log::info!("转录已开始，音频源: {:?}", source);
+                return Ok(());
+            }
+            
+            // 停止已启动的识别器，避免泄漏
             unsafe { ffi::speech_stop(); }
-            return Err("启动音频捕获失败".to_string());
+            log::warn!("启动音频捕获失败（第 {}/{} 次尝试）", attempt, CAPTURE_START_ATTEMPTS);
         }
         
-        IS_CAPTURING.store(true, Ordering::SeqCst);
-        log::info!("转录已开始");
-        Ok(())
+        Err(AppError::Native(format!("启动音频捕获失败（已尝试 {} 次）", CAPTURE_START_ATTEMPTS)))
     }
     
-    #[cfg(not(feature = "swift_audio"))]
-    pub fn start_transcription() -> Result<(), String> {
-        if IS_CAPTURING.load(Ordering::SeqCst) {
-            return Err("转录已在进行中".to_string());
+    #[cfg(not(native_audio))]
+    pub fn start_transcription(source: AudioSource) -> Result<(), AppError> {
+        // 降级模式：不模拟转录，明确报告原生模块不可用（单元测试始终使用模拟模式）
+        if cfg!(all(feature = "swift_audio", not(test))) {
+            return Err(AppError::Native(
+                "原生音频模块不可用，请先编译 Swift 库后重新构建应用".to_string(),
+            ));
         }
+        let _guard = StartGuard::acquire()?;
         
         Self::clear_transcription();
+        DROPPED_SAMPLES.store(0, Ordering::Relaxed);
+        reset_metrics();
+        IS_CLIPPING.store(false, Ordering::SeqCst);
+        reset_capture_clock();
         IS_CAPTURING.store(true, Ordering::SeqCst);
-        log::info!("转录已开始 (模拟模式)");
+        if let Ok(mut active) = ACTIVE_SOURCE.lock() {
+            *active = source;
+        }
+        log::info!("转录已开始 (模拟模式)，音频源: {:?}", source);
         Ok(())
     }
     
+    /// 转录音频文件：启动识别器后由原生模块解码文件并送入识别，结果进入已确认缓冲区
+    /// 转录期间占用启动权，不能同时开始实时转录；返回音频时长（秒）
+    #[cfg(native_audio)]
+    pub fn transcribe_file(path: &Path) -> Result<f64, AppError> {
+        let c_path = CString::new(path.to_string_lossy().as_bytes())
+            .map_err(|_| AppError::InvalidInput("文件路径包含非法字符".to_string()))?;
+        ensure_native_available()?;
+        let _guard = StartGuard::acquire()?;
+        
+        Self::clear_transcription();
+        ERROR_PENDING.store(false, Ordering::SeqCst);
+        reset_capture_clock();
+        
+        if !unsafe { ffi::speech_start() } {
+            return Err(AppError::Native("启动语音识别失败".to_string()));
+        }
+        log::info!("开始转录音频文件: {:?}", path);
+        
+        let duration = unsafe { ffi::speech_transcribe_file(c_path.as_ptr(), on_file_progress) };
+        unsafe { ffi::speech_stop(); }
+        
+        if duration < 0.0 {
+            return Err(AppError::Native(
+                Self::take_error().unwrap_or_else(|| "转录音频文件失败".to_string()),
+            ));
+        }
+        emit_session_event(FILE_PROGRESS_EVENT, 1.0);
+        log::info!("音频文件转录完成，时长: {:.1} 秒", duration);
+        Ok(duration)
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn transcribe_file(_path: &Path) -> Result<f64, AppError> {
+        Err(AppError::Native(
+            "当前构建未包含原生音频模块，不支持音频文件转录".to_string(),
+        ))
+    }
+    
     /// 停止转录
-    #[cfg(feature = "swift_audio")]
+    #[cfg(native_audio)]
     pub fn stop_transcription() {
         if !IS_CAPTURING.load(Ordering::SeqCst) {
             return;
         }
         
+        let source = ACTIVE_SOURCE.lock()
+            .map(|s| *s)
+            .unwrap_or_default();
+        
         unsafe {
-            ffi::audio_capture_stop();
+            match source {
+                AudioSource::SystemAudio => ffi::audio_capture_stop(),
+                AudioSource::Microphone => ffi::mic_capture_stop(),
+            }
             ffi::speech_stop();
         }
         
+        AUDIO_LEVEL.store(0, Ordering::Relaxed);
+        IS_PAUSED.store(false, Ordering::SeqCst);
         IS_CAPTURING.store(false, Ordering::SeqCst);
         log::info!("转录已停止");
     }
     
-    #[cfg(not(feature = "swift_audio"))]
+    #[cfg(not(native_audio))]
     pub fn stop_transcription() {
+        IS_PAUSED.store(false, Ordering::SeqCst);
         IS_CAPTURING.store(false, Ordering::SeqCst);
         log::info!("转录已停止 (模拟模式)");
     }
     
+    /// 暂停转录：停止向识别器输送音频，已确认的文本保持不变
+    pub fn pause_transcription() {
+        lock_recover(&CAPTURE_CLOCK, "CAPTURE_CLOCK").paused_at.get_or_insert_with(Instant::now);
+        IS_PAUSED.store(true, Ordering::SeqCst);
+        log::info!("转录已暂停");
+    }
+    
+    /// 恢复转录：继续向识别器输送音频
+    pub fn resume_transcription() {
+        {
+            let mut clock = lock_recover(&CAPTURE_CLOCK, "CAPTURE_CLOCK");
+            if let Some(paused_at) = clock.paused_at.take() {
+                clock.paused_total += paused_at.elapsed();
+            }
+        }
+        IS_PAUSED.store(false, Ordering::SeqCst);
+        log::info!("转录已恢复");
+    }
+    
+    /// 本次会话是否检测到持续削波（音源音量过大），开始转录时清除
+    pub fn is_clipping() -> bool {
+        IS_CLIPPING.load(Ordering::SeqCst)
+    }
+    
+    /// 是否已暂停
+    pub fn is_paused() -> bool {
+        IS_PAUSED.load(Ordering::SeqCst)
+    }
+    
     /// 获取当前正在进行的转录文本（实时显示用）
     pub fn get_latest_transcription() -> String {
-        CURRENT_TRANSCRIPTION.lock()
-            .map(|s| s.clone())
-            .unwrap_or_default()
+        lock_recover(&CURRENT_TRANSCRIPTION, "CURRENT_TRANSCRIPTION").clone()
     }
     
     /// 获取所有已确认的转录文本
     pub fn get_full_transcription() -> String {
-        CONFIRMED_BUFFER.lock()
-            .map(|s| s.clone())
-            .unwrap_or_default()
+        lock_recover(&CONFIRMED_BUFFER, "CONFIRMED_BUFFER").clone()
+    }
+    
+    /// 获取完整的已确认文本：会话文件中已落盘的部分 + 内存中的部分
+    pub fn get_complete_transcription() -> String {
+        let in_memory = Self::get_full_transcription();
+        let flushed = SESSION_FILE.lock()
+            .ok()
+            .and_then(|s| s.clone())
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        
+        if flushed.is_empty() {
+            in_memory
+        } else {
+            // 会话文件中每段连同其后的分隔符一起写入，可直接拼接
+            format!("{}{}", flushed, in_memory)
+        }
+    }
+    
+    /// 获取本次会话实际有音频输入的时长（秒）
+    /// 模拟模式下没有音频输入，返回 None，由调用方使用墙钟时长
+    #[cfg(native_audio)]
+    pub fn get_active_duration_seconds() -> Option<i32> {
+        Some((ACTIVE_CAPTURE_MICROS.load(Ordering::Relaxed) / 1_000_000) as i32)
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn get_active_duration_seconds() -> Option<i32> {
+        None
+    }
+    
+    /// 在已确认文本前插入已有内容（续写模式），新识别的文本会追加在其后
+    pub fn prepend_confirmed(text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        
+        let mut buffer = lock_recover(&CONFIRMED_BUFFER, "CONFIRMED_BUFFER");
+        if buffer.is_empty() {
+            *buffer = text.to_string();
+        } else {
+            *buffer = format!("{}{}{}", text, segment_separator(), buffer);
+        }
+        rotate_buffer(&mut buffer);
+    }
+    
+    /// 设置部分结果事件的最小发送间隔（毫秒，0 表示不限制）
+    pub fn set_partial_emit_interval(interval_ms: u64) {
+        PARTIAL_EMIT_INTERVAL_MS.store(interval_ms, Ordering::Relaxed);
+    }
+    
+    /// 设置内存中已确认文本的最大字符数（0 表示不限制）
+    pub fn set_buffer_limit(chars: usize) {
+        BUFFER_LIMIT.store(chars, Ordering::SeqCst);
+        
+        rotate_buffer(&mut lock_recover(&CONFIRMED_BUFFER, "CONFIRMED_BUFFER"));
+    }
+    
+    /// 设置片段分隔符，None 表示按语言使用默认值（中日韩不加分隔符，其他语言换行）
+    pub fn set_segment_separator(sep: Option<String>) {
+        *lock_recover(&SEGMENT_SEPARATOR, "SEGMENT_SEPARATOR") = sep;
+    }
+    
+    /// 当前生效的片段分隔符
+    pub fn get_segment_separator() -> String {
+        segment_separator()
+    }
+    
+    /// 强制音频直通：跳过混音与重采样，原样送入识别器
+    /// 仅适用于确认输入已是 16kHz 单声道的设置，格式不符时识别结果会失真
+    pub fn set_audio_passthrough(enabled: bool) {
+        AUDIO_PASSTHROUGH.store(enabled, Ordering::Relaxed);
+    }
+    
+    /// 设置静音分段：启用时连续静音达到 silence_ms 毫秒即强制分段，
+    /// 未确认的部分结果提前确认，下一个片段另起一行
+    pub fn set_silence_split(enabled: bool, silence_ms: u64) {
+        SILENCE_SPLIT_MS.store(if enabled { silence_ms } else { 0 }, Ordering::Relaxed);
+    }
+    
+    /// 启用或关闭确认片段的标点、大小写后处理（原始文本保留在片段的 raw_text 中）
+    pub fn set_post_processing(enabled: bool) {
+        POST_PROCESSING.store(enabled, Ordering::Relaxed);
+    }
+    
+    /// 设置停止关键词（None 表示关闭），空白关键词视为关闭
+    pub fn set_stop_keyword(keyword: Option<String>) {
+        let keyword = keyword
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty());
+        *lock_recover(&STOP_KEYWORD, "STOP_KEYWORD") = keyword;
+    }
+    
+    /// 设置实时输出文件（None 表示关闭），停止转录后文件保留
+    /// 路径包含 {session_id} 时每次会话写入单独的文件，此时只校验所在目录
+    pub fn set_live_output_file(path: Option<PathBuf>) -> Result<(), AppError> {
+        if let Some(path) = path.as_ref().filter(|p| p.to_string_lossy().contains(LIVE_OUTPUT_SESSION_PLACEHOLDER)) {
+            let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            if !dir.is_dir() {
+                return Err(AppError::Io(format!("实时输出目录不存在: {}", dir.display())));
+            }
+        } else if let Some(path) = &path {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| AppError::Io(format!("无法打开实时输出文件 {}: {}", path.display(), e)))?;
+        }
+        *lock_recover(&LIVE_OUTPUT_FILE, "LIVE_OUTPUT_FILE") = path;
+        Ok(())
+    }
+    
+    /// 设置当前转录会话 ID，之后发送的事件和生成的会话文件都使用该 ID
+    pub fn set_session_id(session_id: Option<String>) {
+        *lock_recover(&SESSION_ID, "SESSION_ID") = session_id;
+    }
+    
+    /// 当前转录会话 ID
+    pub fn session_id() -> Option<String> {
+        current_session_id()
+    }
+    
+    /// 本次转录的回调计数与每秒速率
+    pub fn get_metrics() -> BridgeMetrics {
+        let audio_callbacks = AUDIO_CALLBACKS.load(Ordering::Relaxed);
+        let transcription_callbacks = TRANSCRIPTION_CALLBACKS.load(Ordering::Relaxed);
+        let elapsed_seconds = elapsed_ms() as f64 / 1000.0;
+        let per_second = |count: u64| if elapsed_seconds > 0.0 { count as f64 / elapsed_seconds } else { 0.0 };
+        
+        BridgeMetrics {
+            audio_callbacks,
+            transcription_callbacks,
+            final_segments: FINAL_SEGMENTS.load(Ordering::Relaxed),
+            elapsed_seconds,
+            audio_callbacks_per_second: per_second(audio_callbacks),
+            transcription_callbacks_per_second: per_second(transcription_callbacks),
+        }
+    }
+    
+    /// 本次转录中因识别器未运行而丢弃的音频采样数（诊断用）
+    pub fn dropped_sample_count() -> u64 {
+        DROPPED_SAMPLES.load(Ordering::Relaxed)
     }
     
     /// 清空转录缓冲区
     pub fn clear_transcription() {
-        if let Ok(mut buffer) = CONFIRMED_BUFFER.lock() {
-            buffer.clear();
+        lock_recover(&CONFIRMED_BUFFER, "CONFIRMED_BUFFER").clear();
+        ACTIVE_CAPTURE_MICROS.store(0, Ordering::Relaxed);
+        ERROR_NOTIFIED.store(false, Ordering::SeqCst);
+        if let Ok(mut last) = LAST_SAMPLE_TIMESTAMP.lock() {
+            *last = None;
+        }
+        if let Ok(mut session) = SESSION_FILE.lock() {
+            *session = None;
+        }
+        SPILLED_CHARS.store(0, Ordering::SeqCst);
+        if let Ok(mut spill) = SEGMENT_SPILL_FILE.lock() {
+            *spill = None;
+        }
+        SPILLED_SEGMENTS.store(0, Ordering::SeqCst);
+        PARAGRAPH_BREAK.store(false, Ordering::SeqCst);
+        {
+            let mut split = lock_recover(&SILENCE_SPLIT, "SILENCE_SPLIT");
+            split.last_partial.clear();
+            split.forced_prefix.clear();
+        }
+        lock_recover(&LAST_EMITTED_PARTIAL, "LAST_EMITTED_PARTIAL").clear();
+        STOP_REQUESTED.store(false, Ordering::SeqCst);
+        lock_recover(&CURRENT_TRANSCRIPTION, "CURRENT_TRANSCRIPTION").clear();
+        lock_recover(&SEGMENTS, "SEGMENTS").clear();
+        if let Ok(mut start) = CURRENT_SEGMENT_START.lock() {
+            *start = None;
+        }
+        if let Ok(mut latest) = LATEST_CONFIDENCE.lock() {
+            *latest = 0.0;
+        }
+        if let Ok(mut detected) = DETECTED_LANGUAGE.lock() {
+            *detected = None;
+        }
+    }
+    
+    /// 启用或关闭自动语言检测
+    pub fn set_auto_language(enabled: bool) {
+        AUTO_LANGUAGE.store(enabled, Ordering::SeqCst);
+    }
+    
+    /// 获取本次会话自动检测到的语言
+    pub fn get_detected_language() -> Option<String> {
+        DETECTED_LANGUAGE.lock()
+            .ok()
+            .and_then(|d| d.clone())
+    }
+    
+    /// 获取最近一次识别结果的置信度（0.0 ~ 1.0）
+    pub fn get_latest_confidence() -> f32 {
+        LATEST_CONFIDENCE.lock()
+            .map(|c| *c)
+            .unwrap_or(0.0)
+    }
+    
+    /// 获取已确认片段的平均置信度，没有片段时返回 0.0
+    pub fn get_average_confidence() -> f32 {
+        let mut count = 0usize;
+        let mut total = 0.0f32;
+        let _ = Self::for_each_segment(|seg| {
+            count += 1;
+            total += seg.confidence;
+            Ok(())
+        });
+        if count == 0 {
+            return 0.0;
+        }
+        total / count as f32
+    }
+    
+    /// 已确认文本的字符数（含已写入会话文件的部分），不复制文本
+    pub fn get_transcription_length() -> usize {
+        let in_memory = lock_recover(&CONFIRMED_BUFFER, "CONFIRMED_BUFFER").chars().count();
+        SPILLED_CHARS.load(Ordering::SeqCst) + in_memory
+    }
+    
+    /// 已确认片段的数量（含已写入片段溢出文件的部分）
+    pub fn get_segment_count() -> usize {
+        SPILLED_SEGMENTS.load(Ordering::SeqCst) + lock_recover(&SEGMENTS, "SEGMENTS").len()
+    }
+    
+    /// 设置内存中片段的最大数量（0 表示不限制），超出的最旧片段写入片段溢出文件
+    pub fn set_segment_limit(limit: usize) {
+        SEGMENT_LIMIT.store(limit, Ordering::SeqCst);
+        
+        rotate_segments(&mut lock_recover(&SEGMENTS, "SEGMENTS"));
+    }
+    
+    /// 按时间顺序逐个访问所有已确认片段：先逐行读取片段溢出文件，再访问内存中的片段
+    /// 不会一次性载入溢出文件，回调返回错误时停止并返回该错误
+    pub fn for_each_segment<F>(mut f: F) -> Result<(), AppError>
+    where
+        F: FnMut(&TranscriptSegment) -> Result<(), AppError>,
+    {
+        // 持锁取得内存部分和已溢出数量的一致快照，读文件时只读取快照时已写入的行，
+        // 读取期间新移入溢出文件的片段已包含在内存快照中，不会重复或遗漏
+        let (in_memory, spilled) = {
+            let segments = lock_recover(&SEGMENTS, "SEGMENTS");
+            (segments.clone(), SPILLED_SEGMENTS.load(Ordering::SeqCst))
+        };
+        let spill_path = SEGMENT_SPILL_FILE.lock().ok().and_then(|p| p.clone());
+        
+        if let Some(path) = spill_path.filter(|_| spilled > 0) {
+            match std::fs::File::open(&path) {
+                Ok(file) => {
+                    for line in BufReader::new(file).lines().take(spilled) {
+                        let line = line
+                            .map_err(|e| AppError::Io(format!("读取片段溢出文件失败: {}", e)))?;
+                        match serde_json::from_str::<TranscriptSegment>(&line) {
+                            Ok(seg) => f(&seg)?,
+                            Err(e) => log::warn!("跳过无法解析的溢出片段: {}", e),
+                        }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(AppError::Io(format!("打开片段溢出文件失败: {}", e))),
+            }
         }
-        if let Ok(mut current) = CURRENT_TRANSCRIPTION.lock() {
-            current.clear();
+        
+        in_memory.iter().try_for_each(f)
+    }
+    
+    /// 获取所有已确认的转录片段（含时间信息，包括已写入片段溢出文件的部分）
+    pub fn get_segments() -> Vec<TranscriptSegment> {
+        let mut segments = Vec::with_capacity(Self::get_segment_count());
+        if let Err(e) = Self::for_each_segment(|seg| {
+            segments.push(seg.clone());
+            Ok(())
+        }) {
+            log::warn!("读取完整片段列表失败，仅返回已读取的部分: {}", e);
         }
+        segments
     }
     
     /// 模拟追加文本（用于测试）
     pub fn simulate_text(text: &str) {
-        // 模拟模式：直接追加到已确认缓冲区
-        if let Ok(mut buffer) = CONFIRMED_BUFFER.lock() {
-            if !buffer.is_empty() {
-                buffer.push('\n');
+        // 模拟模式：与识别回调的最终结果走同一确认路径（去重、分隔符、片段计数）
+        if let Ok(mut start) = CURRENT_SEGMENT_START.lock() {
+            if start.is_none() {
+                *start = Some(elapsed_ms());
             }
-            buffer.push_str(text);
         }
+        confirm_segment(text, 1.0);
+        
+        emit_transcription_update(true);
+    }
+    
+    /// 获取当前音频电平（0.0 ~ 1.0），未在捕获时返回 0.0
+    #[cfg(native_audio)]
+    pub fn get_audio_level() -> f32 {
+        if !IS_CAPTURING.load(Ordering::SeqCst) {
+            return 0.0;
+        }
+        f32::from_bits(AUDIO_LEVEL.load(Ordering::Relaxed))
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn get_audio_level() -> f32 {
+        0.0
     }
     
-    /// 获取错误信息
+    /// 获取最近一次尚未读取的错误信息
     pub fn get_error() -> Option<String> {
-        ERROR_MESSAGE.lock()
-            .ok()
-            .and_then(|e| e.clone())
+        if !ERROR_PENDING.load(Ordering::SeqCst) {
+            return None;
+        }
+        lock_recover(&ERROR_HISTORY, "ERROR_HISTORY").back().map(|e| e.message.clone())
+    }
+    
+    /// 获取并清除最近一次错误信息（错误历史保留）
+    pub fn take_error() -> Option<String> {
+        if !ERROR_PENDING.swap(false, Ordering::SeqCst) {
+            return None;
+        }
+        lock_recover(&ERROR_HISTORY, "ERROR_HISTORY").back().map(|e| e.message.clone())
+    }
+    
+    /// 最近的错误历史（最旧的在前，最多 ERROR_HISTORY_CAPACITY 条），用于诊断会话中途的问题
+    pub fn get_error_history() -> Vec<ErrorRecord> {
+        lock_recover(&ERROR_HISTORY, "ERROR_HISTORY").iter().cloned().collect()
     }
     
     /// 是否正在捕获
@@ -309,25 +2159,249 @@ impl AudioBridge {
         IS_CAPTURING.load(Ordering::SeqCst)
     }
     
-    /// 获取捕获状态
-    #[cfg(feature = "swift_audio")]
-    pub fn get_capture_status() -> i32 {
-        unsafe { ffi::audio_capture_get_status() }
+    /// 获取捕获状态（当前音频源）
+    #[cfg(native_audio)]
+    pub fn get_capture_status() -> CaptureStatus {
+        let source = ACTIVE_SOURCE.lock()
+            .map(|s| *s)
+            .unwrap_or_default();
+        
+        let code = unsafe {
+            match source {
+                AudioSource::SystemAudio => ffi::audio_capture_get_status(),
+                AudioSource::Microphone => ffi::mic_capture_get_status(),
+            }
+        };
+        CaptureStatus::from(code)
     }
     
-    #[cfg(not(feature = "swift_audio"))]
-    pub fn get_capture_status() -> i32 {
-        if IS_CAPTURING.load(Ordering::SeqCst) { 2 } else { 0 }
+    #[cfg(not(native_audio))]
+    pub fn get_capture_status() -> CaptureStatus {
+        if IS_CAPTURING.load(Ordering::SeqCst) { CaptureStatus::Running } else { CaptureStatus::Idle }
     }
     
     /// 获取识别状态
-    #[cfg(feature = "swift_audio")]
-    pub fn get_recognition_status() -> i32 {
-        unsafe { ffi::speech_get_status() }
+    #[cfg(native_audio)]
+    pub fn get_recognition_status() -> RecognitionStatus {
+        RecognitionStatus::from(unsafe { ffi::speech_get_status() })
+    }
+    
+    #[cfg(not(native_audio))]
+    pub fn get_recognition_status() -> RecognitionStatus {
+        if IS_CAPTURING.load(Ordering::SeqCst) { RecognitionStatus::Running } else { RecognitionStatus::Idle }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::serial_test;
+    use std::sync::{Arc, Barrier};
+    
+    /// 以指定语言开始一段新的转录，关闭自动检测、自定义分隔符、停止关键词和后处理
+    fn reset_recognition(language: &str) {
+        AudioBridge::clear_transcription();
+        AudioBridge::set_language(language);
+        AudioBridge::set_segment_separator(None);
+        AudioBridge::set_stop_keyword(None);
+        AudioBridge::set_post_processing(false);
+        AUTO_LANGUAGE.store(false, Ordering::SeqCst);
+        *lock_recover(&DETECTED_LANGUAGE, "DETECTED_LANGUAGE") = None;
+    }
+    
+    /// 依次通过识别回调送入结果（文本，是否最终），返回已确认的完整文本
+    fn feed(results: &[(&str, bool)]) -> String {
+        for (text, is_final) in results {
+            let text = CString::new(*text).unwrap();
+            on_transcription(text.as_ptr(), *is_final, 0.9);
+        }
+        AudioBridge::get_full_transcription()
+    }
+    
+    #[test]
+    fn trim_overlap_full_overlap() {
+        assert_eq!(trim_overlap("hello world", "hello world"), "");
+        assert_eq!(trim_overlap("so I said hello world", " hello world "), "");
+    }
+    
+    #[test]
+    fn trim_overlap_partial_overlap() {
+        assert_eq!(trim_overlap("the quick brown fox", "brown fox jumps over"), "jumps over");
+    }
+    
+    #[test]
+    fn trim_overlap_keeps_partial_word() {
+        // "ting" 只是 "testing" 的词尾，不是重复的词
+        assert_eq!(trim_overlap("we are testing", "ting is fun"), "ting is fun");
+    }
+    
+    #[test]
+    fn trim_overlap_cjk() {
+        assert_eq!(trim_overlap("今天天气很好", "天气很好我们去公园"), "我们去公园");
+    }
+    
+    #[test]
+    fn trim_overlap_no_overlap() {
+        assert_eq!(trim_overlap("hello there", "general kenobi"), "general kenobi");
+        assert_eq!(trim_overlap("", "first words"), "first words");
+        // 少于 MIN_OVERLAP_CHARS 的重叠不去除
+        assert_eq!(trim_overlap("a b c", "c d"), "c d");
+    }
+    
+    #[test]
+    fn partial_then_final_sequence_has_no_duplicated_runs() {
+        let _serial = serial_test();
+        
+        reset_recognition("en-US");
+        let text = feed(&[
+            ("hello", false),
+            ("hello world", false),
+            ("hello world", true),
+            ("hello world how", false),
+            ("hello world how are you", true),
+            ("how are you doing", false),
+            ("how are you doing today", true),
+        ]);
+        let separator = segment_separator();
+        assert_eq!(text, ["hello world", "how are you", "doing today"].join(&separator));
+        assert_eq!(AudioBridge::get_segments().len(), 3);
+        
+        // 中日韩语言默认不加分隔符
+        reset_recognition("zh-CN");
+        let text = feed(&[
+            ("我们", false),
+            ("我们明天", true),
+            ("我们明天一起", false),
+            ("我们明天一起去公园", true),
+            ("一起去公园散步吧", true),
+        ]);
+        assert_eq!(text, "我们明天一起去公园散步吧");
+        
+        // 整段重复的最终结果不追加
+        let before = AudioBridge::get_segments().len();
+        assert_eq!(feed(&[("一起去公园散步吧", true)]), text);
+        assert_eq!(AudioBridge::get_segments().len(), before);
+        
+        AudioBridge::clear_transcription();
+    }
+    
+    #[test]
+    fn confirmed_segments_use_separator_and_post_processing() {
+        let _serial = serial_test();
+        
+        reset_recognition("en-US");
+        AudioBridge::set_segment_separator(Some(" | ".to_string()));
+        AudioBridge::set_post_processing(true);
+        let text = feed(&[("good morning", false), ("good morning", true), ("see you later", true)]);
+        assert_eq!(text, "Good morning. | See you later.");
+        
+        let segments = AudioBridge::get_segments();
+        assert_eq!(segments[0].text, "Good morning.");
+        assert_eq!(segments[0].raw_text.as_deref(), Some("good morning"));
+        
+        // 恢复默认配置，不影响其他测试
+        reset_recognition("en-US");
+    }
+    
+    #[test]
+    fn concurrent_starts_only_one_acquires() {
+        let _serial = serial_test();
+        IS_CAPTURING.store(false, Ordering::SeqCst);
+        
+        // 两个线程同时尝试占用启动权，都尝试过之后才释放
+        let attempted = Arc::new(Barrier::new(2));
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let attempted = Arc::clone(&attempted);
+                std::thread::spawn(move || {
+                    let result = StartGuard::acquire();
+                    attempted.wait();
+                    result.map(|_guard| ())
+                })
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        assert!(results.iter().any(|r| matches!(r, Err(AppError::AlreadyCapturing))));
+        // 占用者退出后启动权已释放
+        assert!(StartGuard::acquire().is_ok());
+    }
+    
+    #[test]
+    fn start_refused_while_capturing() {
+        let _serial = serial_test();
+        IS_CAPTURING.store(true, Ordering::SeqCst);
+        let result = StartGuard::acquire();
+        IS_CAPTURING.store(false, Ordering::SeqCst);
+        
+        assert!(matches!(result, Err(AppError::AlreadyCapturing)));
+        assert!(!IS_STARTING.load(Ordering::SeqCst));
+    }
+    
+    #[test]
+    fn buffers_survive_concurrent_access() {
+        const WRITERS: usize = 4;
+        const SEGMENTS_PER_WRITER: usize = 50;
+        
+        let _serial = serial_test();
+        AudioBridge::clear_transcription();
+        
+        let start = Arc::new(Barrier::new(WRITERS * 2));
+        let mut handles = Vec::new();
+        for writer in 0..WRITERS {
+            let start = Arc::clone(&start);
+            handles.push(std::thread::spawn(move || {
+                start.wait();
+                for i in 0..SEGMENTS_PER_WRITER {
+                    let partial = CString::new(format!("线程{}第{}句", writer, i)).unwrap();
+                    on_transcription(partial.as_ptr(), false, 0.5);
+                    AudioBridge::simulate_text(&format!("线程{}第{}句。", writer, i));
+                }
+            }));
+        }
+        for _ in 0..WRITERS {
+            let start = Arc::clone(&start);
+            handles.push(std::thread::spawn(move || {
+                start.wait();
+                for _ in 0..SEGMENTS_PER_WRITER {
+                    let _ = AudioBridge::get_latest_transcription();
+                    let _ = AudioBridge::get_complete_transcription();
+                    let _ = AudioBridge::get_segments();
+                    let _ = AudioBridge::get_average_confidence();
+                }
+            }));
+        }
+        for handle in handles {
+            handle.join().expect("访问缓冲区的线程 panic");
+        }
+        
+        // 每个确认的片段都完整出现一次，没有丢失或交错
+        let segments = AudioBridge::get_segments();
+        assert_eq!(segments.len(), WRITERS * SEGMENTS_PER_WRITER);
+        let full_text = AudioBridge::get_complete_transcription();
+        for writer in 0..WRITERS {
+            for i in 0..SEGMENTS_PER_WRITER {
+                let sentence = format!("线程{}第{}句。", writer, i);
+                assert_eq!(full_text.matches(&sentence).count(), 1, "{}", sentence);
+                assert!(segments.iter().any(|s| s.text == sentence));
+            }
+        }
+        AudioBridge::clear_transcription();
     }
     
-    #[cfg(not(feature = "swift_audio"))]
-    pub fn get_recognition_status() -> i32 {
-        if IS_CAPTURING.load(Ordering::SeqCst) { 2 } else { 0 }
+    #[test]
+    fn capture_clock_excludes_paused_time() {
+        let now = Instant::now();
+        let clock = CaptureClock {
+            start: Some(now - Duration::from_secs(10)),
+            paused_at: Some(now - Duration::from_secs(2)),
+            paused_total: Duration::from_secs(3),
+        };
+        let elapsed = clock.elapsed().unwrap();
+        assert!(elapsed >= Duration::from_secs(5) && elapsed < Duration::from_millis(5500), "{:?}", elapsed);
+        
+        let idle = CaptureClock { start: None, paused_at: None, paused_total: Duration::ZERO };
+        assert_eq!(idle.elapsed(), None);
     }
 }
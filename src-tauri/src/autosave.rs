@@ -0,0 +1,171 @@
+// autosave.rs
+// 会话自动保存
+// 转录进行中定期将已确认文本写入 session_autosave_<会话 ID>.json，应用崩溃后下次启动可恢复
+
+use crate::atomic_file::write_atomic;
+use crate::audio_bridge::AudioBridge;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 自动保存文件名前缀，完整文件名为 session_autosave_<会话 ID>.json
+/// （旧版本使用不带会话 ID 的 session_autosave.json，启动时一并识别）
+pub const AUTOSAVE_FILE_PREFIX: &str = "session_autosave";
+
+/// 默认自动保存间隔（秒）
+pub const DEFAULT_INTERVAL_SECONDS: u64 = 30;
+
+static INTERVAL_SECONDS: AtomicU64 = AtomicU64::new(DEFAULT_INTERVAL_SECONDS);
+// 每次开始转录递增，旧会话的保存线程据此退出
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// 自动保存文件所在目录，setup 中设置
+static AUTOSAVE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+// 当前会话的自动保存文件路径，开始转录时按会话 ID 生成
+static AUTOSAVE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+// 启动时发现的上次会话残留文本，等待前端恢复
+static RECOVERED_TEXT: Mutex<Option<String>> = Mutex::new(None);
+// 启动时发现的残留自动保存文件，恢复后删除
+static RECOVERED_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// 自动保存文件内容
+#[derive(Debug, Serialize, Deserialize)]
+struct AutosaveFile {
+    saved_at: String,
+    text: String,
+}
+
+/// 设置自动保存文件所在目录，并载入上次未正常结束的会话
+/// 存在多个残留文件时恢复最近保存的一个，其余在恢复时一并删除
+pub fn init(dir: &Path) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("读取自动保存目录失败: {}", e);
+            return;
+        }
+    };
+    
+    let mut latest: Option<AutosaveFile> = None;
+    let mut leftovers = Vec::new();
+    for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+        let is_autosave = path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(AUTOSAVE_FILE_PREFIX) && name.ends_with(".json"));
+        if !is_autosave {
+            continue;
+        }
+        
+        match std::fs::read_to_string(&path).map(|json| serde_json::from_str::<AutosaveFile>(&json)) {
+            Ok(Ok(file)) if !file.text.trim().is_empty() => {
+                if latest.as_ref().map_or(true, |l| file.saved_at > l.saved_at) {
+                    latest = Some(file);
+                }
+                leftovers.push(path);
+            }
+            Ok(Ok(_)) => clear_file(&path),
+            Ok(Err(e)) => {
+                log::warn!("自动保存文件无法解析，已忽略: {}", e);
+                clear_file(&path);
+            }
+            Err(e) => log::warn!("读取自动保存文件失败: {}", e),
+        }
+    }
+    
+    if let Some(file) = latest {
+        log::warn!("发现上次会话的自动保存（{}），可通过 recover_last_session 恢复", file.saved_at);
+        if let Ok(mut recovered) = RECOVERED_TEXT.lock() {
+            *recovered = Some(file.text);
+        }
+    }
+    if let Ok(mut files) = RECOVERED_FILES.lock() {
+        *files = leftovers;
+    }
+    if let Ok(mut autosave_dir) = AUTOSAVE_DIR.lock() {
+        *autosave_dir = Some(dir.to_path_buf());
+    }
+}
+
+/// 设置自动保存间隔（秒），下一次保存后生效
+pub fn set_interval_seconds(seconds: u64) {
+    INTERVAL_SECONDS.store(seconds.max(1), Ordering::Relaxed);
+}
+
+/// 开始转录后启动后台保存线程，转录停止后线程自动退出
+/// 自动保存文件按会话 ID 命名
+pub fn start(session_id: &str) {
+    let Some(dir) = AUTOSAVE_DIR.lock().ok().and_then(|d| d.clone()) else {
+        return;
+    };
+    let path = dir.join(format!("{}_{}.json", AUTOSAVE_FILE_PREFIX, session_id));
+    if let Ok(mut autosave_path) = AUTOSAVE_PATH.lock() {
+        *autosave_path = Some(path.clone());
+    }
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    
+    let spawned = std::thread::Builder::new()
+        .name("session-autosave".into())
+        .spawn(move || loop {
+            std::thread::sleep(Duration::from_secs(INTERVAL_SECONDS.load(Ordering::Relaxed)));
+            if GENERATION.load(Ordering::SeqCst) != generation || !AudioBridge::is_capturing() {
+                break;
+            }
+            write_file(&path, AudioBridge::get_complete_transcription());
+        });
+    if let Err(e) = spawned {
+        log::error!("无法启动自动保存线程: {}", e);
+    }
+}
+
+/// 立即写入一次自动保存（应用退出时使用，保留文件供下次启动恢复）
+pub fn flush() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Some(path) = AUTOSAVE_PATH.lock().ok().and_then(|p| p.clone()) {
+        write_file(&path, AudioBridge::get_complete_transcription());
+    }
+}
+
+/// 正常停止转录后删除自动保存文件
+pub fn finish() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Some(path) = AUTOSAVE_PATH.lock().ok().and_then(|p| p.clone()) {
+        clear_file(&path);
+    }
+}
+
+/// 取出上次会话残留的文本并删除残留的自动保存文件（不影响当前会话的文件）
+pub fn take_recovered() -> Option<String> {
+    let text = RECOVERED_TEXT.lock().ok()?.take()?;
+    if let Ok(mut files) = RECOVERED_FILES.lock() {
+        for path in files.drain(..) {
+            clear_file(&path);
+        }
+    }
+    Some(text)
+}
+
+fn write_file(path: &Path, text: String) {
+    if text.trim().is_empty() {
+        return;
+    }
+    let file = AutosaveFile {
+        saved_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        text,
+    };
+    let result = serde_json::to_string(&file)
+        .map_err(|e| e.to_string())
+        .and_then(|json| write_atomic(path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("自动保存会话失败: {}", e);
+    }
+}
+
+fn clear_file(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("删除自动保存文件失败: {}", e);
+        }
+    }
+}
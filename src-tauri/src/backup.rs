@@ -0,0 +1,140 @@
+// backup.rs
+// 数据目录备份与恢复
+// 将整个数据目录（数据库、设置、导出文件等）打包为 zip，恢复时解压回数据目录
+
+use crate::error::AppError;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 恢复前自动生成的安全备份所在的子目录（备份时不打包该目录）
+pub const SAFETY_BACKUP_DIR: &str = "backups";
+
+/// 有效备份必须包含的文件
+const REQUIRED_ENTRIES: &[&str] = &["transcripts.db"];
+
+/// 将数据目录打包为 zip，返回备份文件大小（字节）
+/// 跳过安全备份目录，以及位于数据目录内的输出文件本身
+pub fn create_backup(data_dir: &Path, output: &Path) -> Result<u64, AppError> {
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::Io(format!("无法创建备份目录: {}", e)))?;
+    }
+    
+    let file = File::create(output)
+        .map_err(|e| AppError::Io(format!("无法创建备份文件: {}", e)))?;
+    let mut writer = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    
+    let mut entries = 0;
+    let mut pending = vec![data_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let read_dir = fs::read_dir(&dir)
+            .map_err(|e| AppError::Io(format!("读取数据目录失败: {}", e)))?;
+        for entry in read_dir {
+            let path = entry
+                .map_err(|e| AppError::Io(format!("读取数据目录失败: {}", e)))?
+                .path();
+            let Ok(relative) = path.strip_prefix(data_dir) else {
+                continue;
+            };
+            if relative == Path::new(SAFETY_BACKUP_DIR) || path == output {
+                continue;
+            }
+            
+            // zip 内统一使用 / 分隔路径
+            let name = relative.to_string_lossy().replace('\\', "/");
+            if path.is_dir() {
+                writer.add_directory(name, options)
+                    .map_err(|e| AppError::Io(format!("写入备份失败: {}", e)))?;
+                pending.push(path);
+            } else {
+                writer.start_file(name, options)
+                    .map_err(|e| AppError::Io(format!("写入备份失败: {}", e)))?;
+                let mut source = File::open(&path)
+                    .map_err(|e| AppError::Io(format!("读取文件 {:?} 失败: {}", path, e)))?;
+                io::copy(&mut source, &mut writer)
+                    .map_err(|e| AppError::Io(format!("写入备份失败: {}", e)))?;
+                entries += 1;
+            }
+        }
+    }
+    
+    writer.finish()
+        .map_err(|e| AppError::Io(format!("写入备份失败: {}", e)))?
+        .flush()
+        .map_err(|e| AppError::Io(format!("写入备份失败: {}", e)))?;
+    
+    let size = fs::metadata(output)
+        .map_err(|e| AppError::Io(format!("读取备份文件失败: {}", e)))?
+        .len();
+    log::info!("已备份 {} 个文件到 {:?}（{} 字节）", entries, output, size);
+    Ok(size)
+}
+
+/// 打开备份文件并校验：包含必需文件，且所有条目路径都位于解压目录内
+fn open_backup(zip_path: &Path) -> Result<ZipArchive<File>, AppError> {
+    let file = File::open(zip_path)
+        .map_err(|e| AppError::Io(format!("无法打开备份文件: {}", e)))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| AppError::InvalidInput(format!("备份文件不是有效的 zip: {}", e)))?;
+    
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)
+            .map_err(|e| AppError::InvalidInput(format!("读取备份条目失败: {}", e)))?;
+        if entry.enclosed_name().is_none() {
+            return Err(AppError::InvalidInput(format!("备份包含非法路径: {}", entry.name())));
+        }
+    }
+    
+    let missing: Vec<&str> = REQUIRED_ENTRIES.iter()
+        .copied()
+        .filter(|name| archive.index_for_name(name).is_none())
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::InvalidInput(format!("备份缺少必需文件: {}", missing.join(", "))));
+    }
+    
+    Ok(archive)
+}
+
+/// 校验备份文件，不解压
+pub fn validate_backup(zip_path: &Path) -> Result<(), AppError> {
+    open_backup(zip_path).map(|_| ())
+}
+
+/// 将备份解压到数据目录，覆盖同名文件（备份中没有的文件保留），返回解压的文件数
+/// 调用方需先关闭数据库连接
+pub fn restore_backup(zip_path: &Path, data_dir: &Path) -> Result<usize, AppError> {
+    let mut archive = open_backup(zip_path)?;
+    
+    let mut restored = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)
+            .map_err(|e| AppError::InvalidInput(format!("读取备份条目失败: {}", e)))?;
+        let Some(relative) = entry.enclosed_name() else {
+            continue;
+        };
+        let target: PathBuf = data_dir.join(relative);
+        
+        if entry.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| AppError::Io(format!("创建目录 {:?} 失败: {}", target, e)))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::Io(format!("创建目录 {:?} 失败: {}", parent, e)))?;
+        }
+        let mut output = File::create(&target)
+            .map_err(|e| AppError::Io(format!("写入文件 {:?} 失败: {}", target, e)))?;
+        io::copy(&mut entry, &mut output)
+            .map_err(|e| AppError::Io(format!("写入文件 {:?} 失败: {}", target, e)))?;
+        restored += 1;
+    }
+    
+    log::info!("已从 {:?} 恢复 {} 个文件", zip_path, restored);
+    Ok(restored)
+}
@@ -0,0 +1,92 @@
+// dsp.rs
+// 音频格式转换
+// 将原生模块送来的任意采样率、声道数的交错浮点样本转换为识别器需要的 16kHz 单声道
+
+/// 将交错的多声道样本按帧取平均，混合为单声道
+pub fn downmix_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    
+    samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// 线性插值重采样
+/// 每次回调独立处理，不保留跨回调的插值状态，块边界处的误差对语音识别可以忽略
+pub fn resample_linear(samples: &[f32], from_rate: f64, to_rate: f64) -> Vec<f32> {
+    if samples.is_empty() || from_rate <= 0.0 || to_rate <= 0.0 || (from_rate - to_rate).abs() < f64::EPSILON {
+        return samples.to_vec();
+    }
+    
+    let ratio = from_rate / to_rate;
+    let output_len = ((samples.len() as f64) / ratio).round().max(1.0) as usize;
+    let last = samples.len() - 1;
+    
+    (0..output_len)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = (position.floor() as usize).min(last);
+            let next = (index + 1).min(last);
+            let fraction = (position - index as f64) as f32;
+            samples[index] + (samples[next] - samples[index]) * fraction
+        })
+        .collect()
+}
+
+/// 转换为目标采样率的单声道样本
+pub fn to_mono_at_rate(samples: &[f32], channels: usize, from_rate: f64, to_rate: f64) -> Vec<f32> {
+    let mono = downmix_to_mono(samples, channels);
+    resample_linear(&mono, from_rate, to_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn downmix_averages_stereo_frames() {
+        let stereo = [1.0, 0.0, 0.5, 0.5, -1.0, 1.0, 0.25, 0.75];
+        assert_eq!(downmix_to_mono(&stereo, 2), vec![0.5, 0.5, 0.0, 0.5]);
+    }
+    
+    #[test]
+    fn downmix_drops_trailing_partial_frame() {
+        let stereo = [1.0, 1.0, 0.5, 0.5, 0.9];
+        assert_eq!(downmix_to_mono(&stereo, 2), vec![1.0, 0.5]);
+    }
+    
+    #[test]
+    fn downmix_mono_is_unchanged() {
+        let mono = [0.1, 0.2, 0.3];
+        assert_eq!(downmix_to_mono(&mono, 1), mono.to_vec());
+    }
+    
+    #[test]
+    fn resample_48k_to_16k_ramp() {
+        let ramp: Vec<f32> = (0..480).map(|i| i as f32).collect();
+        let output = resample_linear(&ramp, 48_000.0, 16_000.0);
+        
+        assert_eq!(output.len(), 160);
+        for (i, sample) in output.iter().enumerate() {
+            assert!((sample - (i * 3) as f32).abs() < 1e-3, "第 {} 个样本: {}", i, sample);
+        }
+    }
+    
+    #[test]
+    fn resample_interpolates_between_samples() {
+        let output = resample_linear(&[0.0, 1.0, 2.0, 3.0], 16_000.0, 32_000.0);
+        assert_eq!(output.len(), 8);
+        assert!((output[1] - 0.5).abs() < 1e-6);
+        assert!((output[3] - 1.5).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn resample_equal_rate_is_passthrough() {
+        let samples = [0.25, -0.5, 0.75];
+        assert_eq!(resample_linear(&samples, 16_000.0, 16_000.0), samples.to_vec());
+        assert_eq!(to_mono_at_rate(&samples, 1, 16_000.0, 16_000.0), samples.to_vec());
+    }
+}
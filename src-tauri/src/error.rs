@@ -0,0 +1,77 @@
+// error.rs
+// 应用错误类型
+// 序列化为 { kind, message }，前端可根据 kind 区分错误类型
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+/// 应用错误
+#[derive(Debug)]
+pub enum AppError {
+    /// 存储尚未初始化
+    StorageNotInitialized,
+    /// 状态锁已中毒
+    LockPoisoned,
+    /// 转录已在进行中
+    AlreadyCapturing,
+    /// 转录未在进行中
+    NotCapturing,
+    /// 未找到指定 ID 的记录
+    RecordNotFound(i64),
+    /// 参数无效
+    InvalidInput(String),
+    /// 文件读写错误
+    Io(String),
+    /// 序列化/反序列化错误
+    Serde(String),
+    /// 数据库错误
+    Database(String),
+    /// 原生模块（音频捕获/语音识别）错误
+    Native(String),
+}
+
+impl AppError {
+    /// 错误类型标识，供前端匹配
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::StorageNotInitialized => "storage_not_initialized",
+            Self::LockPoisoned => "lock_poisoned",
+            Self::AlreadyCapturing => "already_capturing",
+            Self::NotCapturing => "not_capturing",
+            Self::RecordNotFound(_) => "record_not_found",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::Io(_) => "io",
+            Self::Serde(_) => "serde",
+            Self::Database(_) => "database",
+            Self::Native(_) => "native",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StorageNotInitialized => write!(f, "存储未初始化"),
+            Self::LockPoisoned => write!(f, "无法获取状态锁"),
+            Self::AlreadyCapturing => write!(f, "转录已在进行中"),
+            Self::NotCapturing => write!(f, "转录未在进行中"),
+            Self::RecordNotFound(id) => write!(f, "未找到 ID 为 {} 的记录", id),
+            Self::InvalidInput(msg)
+            | Self::Io(msg)
+            | Self::Serde(msg)
+            | Self::Database(msg)
+            | Self::Native(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
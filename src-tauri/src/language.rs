@@ -0,0 +1,98 @@
+// language.rs
+// 识别语言定义与自动检测
+// 根据已确认文本的字符类别比例推断语言
+
+/// 自动检测语言的标识
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// 自动检测时识别器使用的初始语言
+pub const DEFAULT_LANGUAGE: &str = "zh-CN";
+
+/// 支持的语言列表（代码, 显示名称）
+pub const SUPPORTED_LANGUAGES: &[(&str, &str)] = &[
+    ("zh-CN", "简体中文"),
+    ("zh-TW", "繁體中文"),
+    ("en-US", "English (US)"),
+    ("en-GB", "English (UK)"),
+    ("ja-JP", "日本語"),
+    ("ko-KR", "한국어"),
+    ("es-ES", "Español"),
+    ("fr-FR", "Français"),
+    ("de-DE", "Deutsch"),
+];
+
+/// 判断语言代码是否在支持列表中
+pub fn is_supported(code: &str) -> bool {
+    SUPPORTED_LANGUAGES.iter().any(|(c, _)| *c == code)
+}
+
+/// 获取语言的显示名称，未知代码原样返回
+pub fn display_name(code: &str) -> &str {
+    if code == AUTO_LANGUAGE {
+        return "自动检测";
+    }
+    SUPPORTED_LANGUAGES.iter()
+        .find(|(c, _)| *c == code)
+        .map_or(code, |(_, name)| name)
+}
+
+/// 是否为中日韩语言（不以空格分词）
+pub fn is_cjk(language: &str) -> bool {
+    ["zh", "ja", "ko"].iter().any(|prefix| language.starts_with(prefix))
+}
+
+/// 统计单条内容的字数：中日韩语言按字符计数，其他语言按空白分词计数
+pub fn count_words(content: &str, language: &str) -> i64 {
+    if is_cjk(language) {
+        content.chars().filter(|c| !c.is_whitespace()).count() as i64
+    } else {
+        content.split_whitespace().count() as i64
+    }
+}
+
+/// 根据字符类别比例推断文本语言，返回支持列表中的语言代码
+/// 文本中没有可识别的字母时返回 None
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut han = 0usize;
+    let mut kana = 0usize;
+    let mut hangul = 0usize;
+    let mut latin = 0usize;
+    
+    for c in text.chars() {
+        match c {
+            '\u{3040}'..='\u{30FF}' => kana += 1,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => hangul += 1,
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => han += 1,
+            c if c.is_alphabetic() && (c.is_ascii() || ('\u{00C0}'..='\u{024F}').contains(&c)) => latin += 1,
+            _ => {}
+        }
+    }
+    
+    let total = han + kana + hangul + latin;
+    if total == 0 {
+        return None;
+    }
+    
+    // 假名占比达到 10% 即视为日语（日语文本通常混有汉字）
+    if kana * 10 >= total {
+        return Some("ja-JP");
+    }
+    if hangul >= han && hangul >= latin {
+        return Some("ko-KR");
+    }
+    if han >= latin {
+        return Some("zh-CN");
+    }
+    
+    // 拉丁字母：根据特征字符区分欧洲语言，默认英语
+    let lower = text.to_lowercase();
+    if lower.contains(['ñ', '¿', '¡']) {
+        Some("es-ES")
+    } else if lower.contains(['ß', 'ä', 'ö', 'ü']) {
+        Some("de-DE")
+    } else if lower.contains(['ç', 'è', 'ê', 'à', 'œ', 'ù']) {
+        Some("fr-FR")
+    } else {
+        Some("en-US")
+    }
+}
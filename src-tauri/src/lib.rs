@@ -2,28 +2,248 @@
 // Tauri 应用主入口
 // Mac Voice to Text - 实时语音转文字应用
 
+mod atomic_file;
 mod audio_bridge;
+mod autosave;
+mod backup;
+#[cfg(any(native_audio, test))]
+mod dsp;
+mod error;
+mod language;
+mod lock;
+mod notify;
+mod postprocess;
 mod storage;
+mod tray;
 
-use audio_bridge::AudioBridge;
+use audio_bridge::{
+    AudioBridge, AudioDevice, AudioSource, BridgeMetrics, CaptureStatus, CaptureTarget, ErrorRecord,
+    PrewarmResult, RecognitionMode, RecognitionStatus, TaskHint, TranscriptSegment,
+};
+use error::AppError;
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
-use storage::{StorageManager, TranscriptRecord};
-use tauri::{Manager, State};
+use std::io::Write;
+use std::sync::{Mutex, MutexGuard};
+use storage::{AppSettings, ExportAllResult, ExportResult, StorageManager, TranscriptChunk, TranscriptPage, TranscriptRecord, TranscriptStats, TrashedTranscript};
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri_plugin_log::{Target, TargetKind};
+
+/// 日志文件名（不含扩展名），位于应用日志目录
+const LOG_FILE_NAME: &str = "mac-voice-to-text";
+
+/// 时长上限计时线程的检查间隔
+const MAX_DURATION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// 因停止关键词或达到时长上限自动停止后发送的事件名（负载为最终的 TranscriptionStatus）
+const AUTO_STOPPED_EVENT: &str = "transcription-auto-stopped";
+
+/// 捕获开始事件名（负载为 CaptureStartedPayload），每次成功开始时发送一次
+const CAPTURE_STARTED_EVENT: &str = "capture-started";
+
+/// 捕获停止事件名（负载为 CaptureStoppedPayload），每次从捕获中停止时发送一次
+const CAPTURE_STOPPED_EVENT: &str = "capture-stopped";
+
+/// 捕获取消事件名（负载为 CaptureCancelledPayload），取消时不保存、不发送 capture-stopped
+const CAPTURE_CANCELLED_EVENT: &str = "capture-cancelled";
 
 /// 应用状态
+/// 存储单独加锁；设置与捕获相关字段统一放在 SessionState 中，一次加锁即可得到一致的快照
 struct AppState {
     storage: Mutex<Option<StorageManager>>,
-    current_language: Mutex<String>,
-    capture_start_time: Mutex<Option<std::time::Instant>>,
+    session: Mutex<SessionState>,
+    /// 按住说话状态；按下 / 松开的处理全程持有该锁，快速连按时按顺序执行
+    push_to_talk: Mutex<PushToTalkState>,
+}
+
+/// 按住说话模式状态：多次按键的文本累积到同一条笔记中
+#[derive(Default)]
+struct PushToTalkState {
+    /// 当前是否按住（正在由按住说话捕获）
+    active: bool,
+    /// 笔记对应的记录 ID（开启自动保存时，首次松开后生成）
+    note_id: Option<i64>,
+    /// 笔记已有的文本，下次按下时先载入
+    note_text: String,
+}
+
+/// 续写来源：需要先载入的已有文本，以及停止时更新的记录（None 表示保存为新记录）
+struct AppendSource {
+    target: Option<i64>,
+    content: String,
+}
+
+/// 会话状态（设置 + 捕获计时）
+struct SessionState {
+    current_language: String,
+    audio_source: AudioSource,
+    /// 选定的麦克风输入设备，None 表示系统默认设备
+    audio_device: Option<String>,
+    /// 系统音频只捕获该应用（bundle ID），None 表示整个系统
+    capture_target: Option<String>,
+    global_shortcut: String,
+    auto_save: bool,
+    custom_vocabulary: Vec<String>,
+    on_device_recognition: bool,
+    notifications_enabled: bool,
+    notification_min_seconds: u64,
+    autosave_interval_seconds: u64,
+    confidence_threshold: f32,
+    segment_separator: Option<String>,
+    stop_keyword: Option<String>,
+    max_history_records: usize,
+    post_processing: bool,
+    audio_passthrough: bool,
+    tray_enabled: bool,
+    export_templates: std::collections::BTreeMap<String, String>,
+    silence_split_enabled: bool,
+    silence_split_ms: u64,
+    task_hint: TaskHint,
+    skip_duplicate_saves: bool,
+    /// 单次会话的最长有效录制时长（秒），达到后自动停止并保存，0 表示不限制
+    max_session_duration_seconds: u64,
+    capture_start_time: Option<std::time::Instant>,
+    /// 捕获开始的本地时间，用于展示和保存记录（时长计算仍使用 Instant）
+    capture_started_at: Option<chrono::DateTime<chrono::Local>>,
+    pause: PauseState,
+    /// 续写模式下追加到的记录 ID
+    append_target: Option<i64>,
+    /// 当前（或最近一次）转录会话的 ID，每次开始转录时生成
+    session_id: Option<String>,
+}
+
+/// 暂停状态，用于从录制时长中扣除暂停的时间
+#[derive(Default)]
+struct PauseState {
+    paused_at: Option<std::time::Instant>,
+    paused_total: std::time::Duration,
+}
+
+impl PauseState {
+    fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+    
+    /// 累计暂停时长（包含当前正在进行的暂停）
+    fn total(&self) -> std::time::Duration {
+        self.paused_total + self.paused_at.map(|t| t.elapsed()).unwrap_or_default()
+    }
+}
+
+impl SessionState {
+    fn new(settings: AppSettings) -> Self {
+        Self {
+            current_language: settings.language,
+            audio_source: settings.audio_source,
+            audio_device: None,
+            capture_target: None,
+            global_shortcut: settings.global_shortcut,
+            auto_save: settings.auto_save,
+            custom_vocabulary: settings.custom_vocabulary,
+            on_device_recognition: settings.on_device_recognition,
+            notifications_enabled: settings.notifications_enabled,
+            notification_min_seconds: settings.notification_min_seconds,
+            autosave_interval_seconds: settings.autosave_interval_seconds,
+            confidence_threshold: settings.confidence_threshold,
+            segment_separator: settings.segment_separator,
+            stop_keyword: settings.stop_keyword,
+            max_history_records: settings.max_history_records,
+            post_processing: settings.post_processing,
+            audio_passthrough: settings.audio_passthrough,
+            tray_enabled: settings.tray_enabled,
+            export_templates: settings.export_templates,
+            silence_split_enabled: settings.silence_split_enabled,
+            silence_split_ms: settings.silence_split_ms,
+            task_hint: settings.task_hint,
+            skip_duplicate_saves: settings.skip_duplicate_saves,
+            max_session_duration_seconds: settings.max_session_duration_seconds,
+            capture_start_time: None,
+            capture_started_at: None,
+            pause: PauseState::default(),
+            append_target: None,
+            session_id: None,
+        }
+    }
+    
+    /// 实际有音频输入的时长（秒），模拟模式下退回扣除暂停后的录制时长
+    fn active_duration_seconds(&self) -> i32 {
+        AudioBridge::get_active_duration_seconds()
+            .unwrap_or_else(|| self.duration_seconds())
+    }
+    
+    /// 需要持久化的设置
+    fn settings(&self) -> AppSettings {
+        AppSettings {
+            language: self.current_language.clone(),
+            audio_source: self.audio_source,
+            auto_save: self.auto_save,
+            global_shortcut: self.global_shortcut.clone(),
+            custom_vocabulary: self.custom_vocabulary.clone(),
+            on_device_recognition: self.on_device_recognition,
+            notifications_enabled: self.notifications_enabled,
+            notification_min_seconds: self.notification_min_seconds,
+            autosave_interval_seconds: self.autosave_interval_seconds,
+            confidence_threshold: self.confidence_threshold,
+            segment_separator: self.segment_separator.clone(),
+            stop_keyword: self.stop_keyword.clone(),
+            max_history_records: self.max_history_records,
+            post_processing: self.post_processing,
+            audio_passthrough: self.audio_passthrough,
+            tray_enabled: self.tray_enabled,
+            export_templates: self.export_templates.clone(),
+            silence_split_enabled: self.silence_split_enabled,
+            silence_split_ms: self.silence_split_ms,
+            task_hint: self.task_hint,
+            skip_duplicate_saves: self.skip_duplicate_saves,
+            max_session_duration_seconds: self.max_session_duration_seconds,
+        }
+    }
+    
+    /// 计算录制时长（秒），扣除暂停的时间
+    fn duration_seconds(&self) -> i32 {
+        let elapsed = self.capture_start_time
+            .map(|t| t.elapsed())
+            .unwrap_or_default();
+        elapsed.saturating_sub(self.pause.total()).as_secs() as i32
+    }
+    
+    /// 捕获开始时间的 UTC 时间戳（RFC 3339），用作保存记录的创建时间
+    fn started_at_utc(&self) -> Option<String> {
+        self.capture_started_at
+            .map(|t| storage::utc_timestamp(t.with_timezone(&chrono::Utc)))
+    }
+    
+    /// 捕获开始时间（本地时间 YYYY-MM-DD HH:MM:SS，供界面显示），尚未开始过时为 None
+    fn started_at(&self) -> Option<String> {
+        self.capture_started_at
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+    }
+    
+    /// 获取保存记录时使用的语言，自动检测模式下使用检测结果
+    fn effective_language(&self) -> String {
+        if self.current_language == language::AUTO_LANGUAGE {
+            AudioBridge::get_detected_language()
+                .unwrap_or_else(|| language::DEFAULT_LANGUAGE.to_string())
+        } else {
+            self.current_language.clone()
+        }
+    }
+}
+
+impl AppState {
+    /// 获取会话状态锁（中毒时恢复，会话字段均为简单值，恢复后仍可安全使用）
+    fn session(&self) -> MutexGuard<'_, SessionState> {
+        lock::lock_recover(&self.session, "session")
+    }
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
             storage: Mutex::new(None),
-            current_language: Mutex::new("zh-CN".to_string()),
-            capture_start_time: Mutex::new(None),
+            session: Mutex::new(SessionState::new(AppSettings::default())),
+            push_to_talk: Mutex::new(PushToTalkState::default()),
         }
     }
 }
@@ -35,272 +255,2161 @@ struct PermissionStatus {
     speech_recognition: bool,
 }
 
-/// 转录状态返回结构
-#[derive(Debug, Serialize, Deserialize)]
-struct TranscriptionStatus {
-    is_capturing: bool,
-    latest_text: String,
-    full_text: String,
-    duration_seconds: i32,
+/// 后端整体状态快照，用于诊断
+#[derive(Debug, Serialize)]
+struct AppStatus {
+    native_available: bool,
+    audio_permission: bool,
+    speech_permission: bool,
+    is_capturing: bool,
+    current_language: String,
+    on_device_supported: bool,
+    storage_record_count: i64,
+    data_dir: String,
+}
+
+/// 当前语言识别方式返回结构
+#[derive(Debug, Serialize)]
+struct RecognitionModeInfo {
+    language: String,
+    mode: RecognitionMode,
+    /// 是否开启了强制端侧识别
+    on_device_required: bool,
+    /// 强制端侧识别但当前语言需要联网（或不受支持），此时开始转录会失败
+    conflict: bool,
+}
+
+/// 语言可用性返回结构
+#[derive(Debug, Serialize, Deserialize)]
+struct AvailableLanguage {
+    code: String,
+    name: String,
+    /// 本机识别器是否支持
+    supported: bool,
+}
+
+/// 转录状态返回结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TranscriptionStatus {
+    is_capturing: bool,
+    is_paused: bool,
+    latest_text: String,
+    full_text: String,
+    duration_seconds: i32,
+    /// 实际有音频输入的时长（秒），不含暂停和音频中断
+    active_duration_seconds: i32,
+    average_confidence: f32,
+    /// 停止时自动保存生成的记录 ID
+    saved_record_id: Option<i64>,
+    /// 停止时自动保存生成（或续写更新）的完整记录
+    #[serde(skip_serializing_if = "Option::is_none")]
+    saved_record: Option<TranscriptRecord>,
+    /// 原生模块最近一次报告的错误
+    error: Option<String>,
+    /// 本次捕获开始的本地时间（YYYY-MM-DD HH:MM:SS）
+    started_at: Option<String>,
+    /// 本次会话是否检测到持续削波
+    is_clipping: bool,
+    /// 当前（或最近一次）转录会话的 ID
+    session_id: Option<String>,
+}
+
+/// 按相关度搜索的单条结果
+#[derive(Debug, Clone, Serialize)]
+struct RankedSearchResult {
+    record: TranscriptRecord,
+    /// 相关度评分，越高越相关
+    score: f32,
+    /// 首个匹配位置前后的上下文预览
+    snippet: String,
+}
+
+/// 捕获开始事件负载
+#[derive(Debug, Clone, Serialize)]
+struct CaptureStartedPayload {
+    session_id: String,
+    /// 开始的本地时间（YYYY-MM-DD HH:MM:SS）
+    started_at: Option<String>,
+    /// 续写模式下的目标记录 ID
+    append_target: Option<i64>,
+}
+
+/// 捕获停止原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StopReason {
+    /// 用户手动停止（命令、快捷键、托盘、按住说话）
+    Manual,
+    /// 识别到停止关键词
+    StopKeyword,
+    /// 达到会话时长上限
+    MaxDuration,
+}
+
+/// 捕获停止事件负载
+#[derive(Debug, Clone, Serialize)]
+struct CaptureStoppedPayload {
+    session_id: Option<String>,
+    reason: StopReason,
+    duration_seconds: i32,
+    active_duration_seconds: i32,
+    /// 自动保存生成（或续写更新）的记录 ID
+    record_id: Option<i64>,
+}
+
+/// 捕获取消事件负载
+#[derive(Debug, Clone, Serialize)]
+struct CaptureCancelledPayload {
+    session_id: Option<String>,
+}
+
+// ============= 转录控制 =============
+
+/// 向前端发送事件，失败时仅记录日志
+fn emit_app_event<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app.emit(event, payload) {
+        log::warn!("发送事件 {} 失败: {}", event, e);
+    }
+}
+
+/// 生成新的转录会话 ID 并同步到音频桥接层，之后的事件和会话文件都使用该 ID
+fn new_session_id(session: &mut SessionState) -> String {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    AudioBridge::set_session_id(Some(session_id.clone()));
+    session.session_id = Some(session_id.clone());
+    log::info!("转录会话 ID: {}", session_id);
+    session_id
+}
+
+/// 开始捕获（供命令和全局快捷键共用）
+fn begin_capture(app: &AppHandle, state: &AppState) -> Result<(), AppError> {
+    begin_capture_with(app, state, None)
+}
+
+/// 开始捕获，指定续写来源时先载入其内容，新识别的文本追加在后面
+fn begin_capture_with(app: &AppHandle, state: &AppState, append: Option<AppendSource>) -> Result<(), AppError> {
+    log::info!("开始转录");
+    
+    // 整个启动过程持有会话锁，状态查询不会看到启动到一半的状态
+    let mut session = state.session();
+    let session_id = start_capture(&mut session, append)?;
+    
+    emit_app_event(app, CAPTURE_STARTED_EVENT, CaptureStartedPayload {
+        session_id: session_id.clone(),
+        started_at: session.started_at(),
+        append_target: session.append_target,
+    });
+    
+    spawn_max_duration_timer(app, session_id);
+    Ok(())
+}
+
+/// 启动捕获并重置会话计时，返回新会话 ID；不发送事件，调用方需持有会话锁
+fn start_capture(session: &mut SessionState, append: Option<AppendSource>) -> Result<String, AppError> {
+    // 检查是否已在捕获
+    if AudioBridge::is_capturing() {
+        return Err(AppError::AlreadyCapturing);
+    }
+    
+    // 强制端侧识别时，不支持的语言直接报错，而不是回退到服务器识别
+    if session.on_device_recognition && !AudioBridge::supports_on_device() {
+        return Err(AppError::InvalidInput(format!(
+            "当前语言 {} 不支持端侧识别，请关闭强制端侧识别或更换语言",
+            session.current_language
+        )));
+    }
+    
+    // 应用选定的输入设备，并在每次开始前重新应用自定义词汇和识别模式
+    AudioBridge::set_audio_device(session.audio_device.as_deref());
+    AudioBridge::set_capture_target(session.capture_target.as_deref());
+    AudioBridge::set_contextual_strings(&session.custom_vocabulary);
+    AudioBridge::set_on_device_required(session.on_device_recognition);
+    AudioBridge::set_task_hint(session.task_hint);
+    
+    // 启动音频捕获和语音识别（会话 ID 在启动前生成，启动过程中的事件也带上该 ID）
+    // 启动失败时恢复上一次会话的 ID
+    let previous_session_id = session.session_id.clone();
+    let session_id = new_session_id(session);
+    if let Err(e) = AudioBridge::start_transcription(session.audio_source) {
+        AudioBridge::set_session_id(previous_session_id.clone());
+        session.session_id = previous_session_id;
+        return Err(e);
+    }
+    autosave::start(&session_id);
+    
+    // 续写模式：载入已有内容
+    session.append_target = append.and_then(|source| {
+        AudioBridge::prepend_confirmed(&source.content);
+        source.target
+    });
+    
+    // 记录开始时间并重置暂停状态
+    session.capture_start_time = Some(std::time::Instant::now());
+    session.capture_started_at = Some(chrono::Local::now());
+    session.pause = PauseState::default();
+    
+    Ok(session_id)
+}
+
+/// 启动会话时长上限计时线程，每秒检查一次有效录制时长
+/// 会话结束（或已开始新的会话）后线程自动退出；上限在会话进行中修改也会生效
+fn spawn_max_duration_timer(app: &AppHandle, session_id: String) {
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("max-session-duration".into())
+        .spawn(move || loop {
+            std::thread::sleep(MAX_DURATION_CHECK_INTERVAL);
+            
+            // 在会话锁内确认仍是同一会话，避免手动停止后作用到之后的会话
+            let state = app.state::<AppState>();
+            let mut session = state.session();
+            if !AudioBridge::is_capturing() || session.session_id.as_deref() != Some(session_id.as_str()) {
+                break;
+            }
+            let limit = session.max_session_duration_seconds;
+            if limit == 0 || (session.active_duration_seconds().max(0) as u64) < limit {
+                continue;
+            }
+            
+            log::info!("已达到会话时长上限 {} 秒，自动停止转录", limit);
+            match finish_capture(&app, &state, &mut session, StopReason::MaxDuration) {
+                Ok(status) => emit_app_event(&app, AUTO_STOPPED_EVENT, status),
+                Err(e) => log::error!("达到时长上限后的停止失败: {}", e),
+            }
+            break;
+        });
+    if let Err(e) = spawned {
+        log::error!("无法启动会话时长计时线程: {}", e);
+    }
+}
+
+/// 最终转录文本：已确认文本（包含因超出缓冲上限而落盘的部分）加上尚未确认的当前片段
+fn final_transcription() -> String {
+    let mut full_text = AudioBridge::get_complete_transcription();
+    let latest_text = AudioBridge::get_latest_transcription();
+    
+    if !latest_text.is_empty() && !full_text.ends_with(&latest_text) {
+        if !full_text.is_empty() {
+            full_text.push_str(&AudioBridge::get_segment_separator());
+        }
+        full_text.push_str(&latest_text);
+    }
+    full_text
+}
+
+/// 停止捕获并返回最终转录结果（供命令和全局快捷键共用）
+fn end_capture(app: &AppHandle, state: &AppState) -> Result<TranscriptionStatus, AppError> {
+    end_capture_with(app, state, StopReason::Manual)
+}
+
+/// 按指定原因停止捕获
+fn end_capture_with(app: &AppHandle, state: &AppState, reason: StopReason) -> Result<TranscriptionStatus, AppError> {
+    let mut session = state.session();
+    finish_capture(app, state, &mut session, reason)
+}
+
+/// 停止捕获、保存并发送停止事件，调用方需持有会话锁
+fn finish_capture(
+    app: &AppHandle,
+    state: &AppState,
+    session: &mut SessionState,
+    reason: StopReason,
+) -> Result<TranscriptionStatus, AppError> {
+    log::info!("停止转录（{:?}）", reason);
+    
+    let status = stop_capture(state, session)?;
+    
+    let language = session.effective_language();
+    notify::notify_session_end(
+        app,
+        "转录已结束",
+        status.active_duration_seconds,
+        language::count_words(&status.full_text, &language),
+    );
+    
+    emit_app_event(app, CAPTURE_STOPPED_EVENT, CaptureStoppedPayload {
+        session_id: status.session_id.clone(),
+        reason,
+        duration_seconds: status.duration_seconds,
+        active_duration_seconds: status.active_duration_seconds,
+        record_id: status.saved_record_id,
+    });
+    
+    Ok(status)
+}
+
+/// 保存刚结束的会话：续写模式下更新原记录，否则新建记录
+fn save_session(
+    state: &AppState,
+    session: &mut SessionState,
+    full_text: &str,
+    active_duration_seconds: i32,
+) -> Result<TranscriptRecord, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    let segments = AudioBridge::get_segments();
+    match session.append_target.take() {
+        Some(id) => storage.append_session_to_transcript(id, full_text, active_duration_seconds, &segments),
+        None => storage.save_transcript(
+            full_text,
+            &session.effective_language(),
+            active_duration_seconds,
+            &segments,
+            session.started_at_utc().as_deref(),
+            session.session_id.as_deref(),
+        ),
+    }
+}
+
+/// 停止捕获并按设置保存，返回最终结果；不发送事件和通知，调用方需持有会话锁
+fn stop_capture(state: &AppState, session: &mut SessionState) -> Result<TranscriptionStatus, AppError> {
+    // 持有会话锁后判断：并发或重复的停止请求（如自动停止后再次停止）直接返回，
+    // 不会对上一次会话残留的缓冲区再次通知和保存
+    if !AudioBridge::is_capturing() {
+        return Err(AppError::NotCapturing);
+    }
+    let duration_seconds = session.duration_seconds();
+    let active_duration_seconds = session.active_duration_seconds();
+    
+    // 结束暂停状态
+    if let Some(paused_at) = session.pause.paused_at.take() {
+        session.pause.paused_total += paused_at.elapsed();
+    }
+    
+    // 先获取当前的转录内容（在停止之前）
+    let full_text = final_transcription();
+    
+    // 停止音频捕获
+    AudioBridge::stop_transcription();
+    
+    log::info!("转录停止，最终文本长度: {} 字符", full_text.len());
+    
+    // 自动保存（文本为空时跳过），续写模式下更新原记录
+    let saved_record = if session.auto_save && !full_text.trim().is_empty() {
+        match save_session(state, session, &full_text, active_duration_seconds) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                // 保存失败时保留自动保存文件，下次启动可通过 recover_last_session 恢复
+                log::error!("保存转录记录失败，已保留自动保存文件: {}", e);
+                autosave::flush();
+                return Err(e);
+            }
+        }
+    } else {
+        None
+    };
+    // 会话已保存（或无需保存），不再需要自动保存文件
+    autosave::finish();
+    
+    Ok(TranscriptionStatus {
+        is_capturing: false,
+        is_paused: false,
+        latest_text: String::new(), // 停止后清空
+        full_text,
+        duration_seconds,
+        active_duration_seconds,
+        average_confidence: AudioBridge::get_average_confidence(),
+        saved_record_id: saved_record.as_ref().map(|r| r.id),
+        saved_record,
+        error: AudioBridge::get_error(),
+        is_clipping: AudioBridge::is_clipping(),
+        started_at: session.started_at(),
+        session_id: session.session_id.clone(),
+    })
+}
+
+/// 将设置应用到各模块（会话状态由调用方另行设置）
+fn apply_settings(settings: &AppSettings) {
+    if settings.language == language::AUTO_LANGUAGE {
+        AudioBridge::set_auto_language(true);
+    } else {
+        AudioBridge::set_auto_language(false);
+        AudioBridge::set_language(&settings.language);
+    }
+    notify::set_enabled(settings.notifications_enabled);
+    notify::set_min_session_seconds(settings.notification_min_seconds);
+    autosave::set_interval_seconds(settings.autosave_interval_seconds);
+    storage::set_confidence_threshold(settings.confidence_threshold);
+    storage::set_skip_duplicates(settings.skip_duplicate_saves);
+    storage::set_export_templates(settings.export_templates.clone());
+    AudioBridge::set_segment_separator(settings.segment_separator.clone());
+    AudioBridge::set_stop_keyword(settings.stop_keyword.clone());
+    AudioBridge::set_post_processing(settings.post_processing);
+    AudioBridge::set_audio_passthrough(settings.audio_passthrough);
+    AudioBridge::set_silence_split(settings.silence_split_enabled, settings.silence_split_ms);
+    AudioBridge::set_task_hint(settings.task_hint);
+}
+
+/// 将当前设置写入 settings.json（调用方不能持有会话锁）
+fn persist_settings(state: &AppState) -> Result<(), AppError> {
+    let settings = state.session().settings();
+    
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.save_settings(&settings)
+}
+
+/// 识别到停止关键词后停止转录，并通知前端最终结果
+/// 在独立线程中执行，避免在识别回调所在线程中停止识别器
+fn stop_by_keyword(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // 用户可能已手动停止
+        if !AudioBridge::is_capturing() {
+            return;
+        }
+        
+        let state = app.state::<AppState>();
+        match end_capture_with(&app, &state, StopReason::StopKeyword) {
+            Ok(status) => emit_app_event(&app, AUTO_STOPPED_EVENT, status),
+            Err(e) => log::error!("停止关键词触发的停止失败: {}", e),
+        }
+    });
+}
+
+/// 应用退出时停止原生捕获
+/// 先写入自动保存，下次启动可通过 recover_last_session 恢复；未在捕获时不做任何事，可重复调用
+/// 持有会话锁执行，不会与进行中的停止（保存）交错
+fn shutdown_capture(state: &AppState) {
+    let _session = state.session();
+    if !AudioBridge::is_capturing() {
+        return;
+    }
+    
+    log::info!("应用退出，停止正在进行的转录");
+    autosave::flush();
+    AudioBridge::stop_transcription();
+}
+
+/// 根据当前状态切换开始/停止转录
+fn toggle_capture(app: &AppHandle, state: &AppState) -> Result<(), AppError> {
+    if AudioBridge::is_capturing() {
+        end_capture(app, state).map(|_| ())
+    } else {
+        begin_capture(app, state)
+    }
+}
+
+/// 解析并注册全局快捷键
+fn register_global_shortcut(app: &AppHandle, accelerator: &str) -> Result<Shortcut, AppError> {
+    let shortcut: Shortcut = accelerator.parse()
+        .map_err(|e| AppError::InvalidInput(format!("无效的快捷键 {}: {}", accelerator, e)))?;
+    
+    app.global_shortcut()
+        .register(shortcut)
+        .map_err(|e| AppError::Native(format!("注册全局快捷键失败: {}", e)))?;
+    
+    Ok(shortcut)
+}
+
+// ============= Tauri 命令 =============
+
+/// 检查权限状态
+#[tauri::command]
+async fn check_permissions(state: State<'_, AppState>) -> Result<PermissionStatus, AppError> {
+    log::info!("检查权限状态");
+    
+    let source = state.session().audio_source;
+    
+    let (audio_ok, speech_ok) = AudioBridge::check_permissions(source);
+    
+    Ok(PermissionStatus {
+        audio_capture: audio_ok,
+        speech_recognition: speech_ok,
+    })
+}
+
+/// 请求权限（打开系统设置中对应的隐私面板）
+/// permission: "screen" / "microphone" / "speech"，未知值时打开屏幕录制面板
+#[tauri::command]
+async fn request_permissions(permission: String) -> Result<(), AppError> {
+    log::info!("请求用户授权: {}", permission);
+    
+    let pane = match permission.as_str() {
+        "microphone" => "Privacy_Microphone",
+        "speech" => "Privacy_SpeechRecognition",
+        "screen" => "Privacy_ScreenCapture",
+        other => {
+            log::warn!("未知的权限类型 {}，打开屏幕录制设置", other);
+            "Privacy_ScreenCapture"
+        }
+    };
+    
+    // 打开系统偏好设置 - 隐私与安全 - 对应面板
+    std::process::Command::new("open")
+        .arg(format!("x-apple.systempreferences:com.apple.preference.security?{}", pane))
+        .spawn()
+        .map_err(|e| AppError::Io(format!("无法打开系统设置: {}", e)))?;
+    
+    Ok(())
+}
+
+/// 设置识别语言（"auto" 表示根据识别内容自动检测）
+#[tauri::command]
+async fn set_language(state: State<'_, AppState>, language: String) -> Result<(), AppError> {
+    log::info!("设置识别语言: {}", language);
+    
+    if language == language::AUTO_LANGUAGE {
+        AudioBridge::set_auto_language(true);
+    } else if language::is_supported(&language) {
+        AudioBridge::set_auto_language(false);
+        AudioBridge::set_language(&language);
+    } else {
+        return Err(AppError::InvalidInput(format!("不支持的语言: {}", language)));
+    }
+    
+    state.session().current_language = language;
+    
+    persist_settings(&state)
+}
+
+/// 获取当前语言
+#[tauri::command]
+async fn get_language(state: State<'_, AppState>) -> Result<String, AppError> {
+    Ok(state.session().current_language.clone())
+}
+
+/// 获取支持的语言列表
+#[tauri::command]
+async fn get_supported_languages() -> Result<Vec<(String, String)>, AppError> {
+    // 常用语言列表，首项为自动检测
+    let mut languages = vec![(
+        language::AUTO_LANGUAGE.to_string(),
+        language::display_name(language::AUTO_LANGUAGE).to_string(),
+    )];
+    languages.extend(
+        language::SUPPORTED_LANGUAGES
+            .iter()
+            .map(|(code, name)| (code.to_string(), name.to_string())),
+    );
+    Ok(languages)
+}
+
+/// 获取语言代码的显示名称（如 zh-CN → 简体中文），未知代码原样返回
+#[tauri::command]
+async fn language_display_name(code: String) -> Result<String, AppError> {
+    Ok(language::display_name(&code).to_string())
+}
+
+/// 获取语言列表及本机是否支持（不支持的语言供界面置灰）
+#[tauri::command]
+async fn get_available_languages() -> Result<Vec<AvailableLanguage>, AppError> {
+    let supported = AudioBridge::supported_languages();
+    
+    let mut languages = vec![AvailableLanguage {
+        code: language::AUTO_LANGUAGE.to_string(),
+        name: "自动检测".to_string(),
+        supported: true,
+    }];
+    languages.extend(language::SUPPORTED_LANGUAGES.iter().map(|(code, name)| AvailableLanguage {
+        code: code.to_string(),
+        name: name.to_string(),
+        supported: supported.iter().any(|c| c == code),
+    }));
+    Ok(languages)
+}
+
+/// 设置音频输入源（system_audio / microphone）
+#[tauri::command]
+async fn set_audio_source(state: State<'_, AppState>, source: String) -> Result<(), AppError> {
+    log::info!("设置音频源: {}", source);
+    
+    if AudioBridge::is_capturing() {
+        return Err(AppError::InvalidInput("转录进行中，无法切换音频源".to_string()));
+    }
+    
+    let source: AudioSource = source.parse()?;
+    
+    state.session().audio_source = source;
+    
+    persist_settings(&state)
+}
+
+/// 设置识别任务类型提示（dictation / search / confirmation / unspecified），下次开始转录时生效
+/// 连续口述（会议、长段讲话）用 dictation；简短的关键词用 search；只说"是"、"停止"这类短命令用 confirmation
+#[tauri::command]
+async fn set_task_hint(state: State<'_, AppState>, hint: String) -> Result<(), AppError> {
+    log::info!("设置识别任务类型: {}", hint);
+    
+    let hint: TaskHint = hint.parse()?;
+    AudioBridge::set_task_hint(hint);
+    state.session().task_hint = hint;
+    
+    persist_settings(&state)
+}
+
+/// 获取当前音频输入源
+#[tauri::command]
+async fn get_audio_source(state: State<'_, AppState>) -> Result<AudioSource, AppError> {
+    Ok(state.session().audio_source)
+}
+
+/// 列出可用的音频输入设备
+#[tauri::command]
+async fn list_audio_devices() -> Result<Vec<AudioDevice>, AppError> {
+    Ok(AudioBridge::list_audio_devices())
+}
+
+/// 设置麦克风输入设备，下次开始转录时生效
+#[tauri::command]
+async fn set_audio_device(state: State<'_, AppState>, id: String) -> Result<(), AppError> {
+    log::info!("设置音频设备: {}", id);
+    
+    if !AudioBridge::list_audio_devices().iter().any(|d| d.id == id) {
+        return Err(AppError::InvalidInput(format!("未找到音频设备: {}", id)));
+    }
+    
+    state.session().audio_device = Some(id);
+    
+    Ok(())
+}
+
+/// 获取当前选定的音频输入设备 ID（None 表示系统默认设备）
+#[tauri::command]
+async fn get_audio_device(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    Ok(state.session().audio_device.clone())
+}
+
+/// 列出可单独捕获音频的应用（名称和 bundle ID）
+#[tauri::command]
+async fn list_capture_targets() -> Result<Vec<CaptureTarget>, AppError> {
+    Ok(AudioBridge::list_capture_targets())
+}
+
+/// 设置系统音频的捕获目标应用（None 表示整个系统），下次开始转录时生效
+/// 例如只转录会议软件的声音而忽略音乐播放器
+#[tauri::command]
+async fn set_capture_target(state: State<'_, AppState>, bundle_id: Option<String>) -> Result<(), AppError> {
+    let bundle_id = bundle_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    log::info!("设置捕获目标: {:?}", bundle_id);
+    
+    if let Some(id) = &bundle_id {
+        if !AudioBridge::list_capture_targets().iter().any(|t| &t.bundle_id == id) {
+            return Err(AppError::InvalidInput(format!("未找到可捕获的应用: {}", id)));
+        }
+    }
+    
+    state.session().capture_target = bundle_id;
+    
+    Ok(())
+}
+
+/// 获取当前的捕获目标应用（None 表示整个系统）
+#[tauri::command]
+async fn get_capture_target(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    Ok(state.session().capture_target.clone())
+}
+
+/// 预热语音识别器（提前创建识别器、加载端侧模型），缩短第一次开始转录的等待
+/// 返回是否成功以及端侧模型是否就绪；未启用原生模块时直接返回成功
+#[tauri::command]
+async fn prewarm_recognizer() -> Result<PrewarmResult, AppError> {
+    log::info!("预热语音识别器");
+    
+    let result = AudioBridge::prewarm_recognizer()?;
+    log::info!("识别器预热结果: {:?}", result);
+    Ok(result)
+}
+
+/// 开始转录
+#[tauri::command]
+async fn start_transcription(app: AppHandle, state: State<'_, AppState>) -> Result<(), AppError> {
+    begin_capture(&app, &state)
+}
+
+/// 续写已有记录：载入其内容后开始转录，停止时（开启自动保存）更新该记录
+#[tauri::command]
+async fn start_transcription_append(app: AppHandle, state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    let record = {
+        let storage = state.storage.lock()
+            .map_err(|_| AppError::LockPoisoned)?;
+        
+        let storage = storage.as_ref()
+            .ok_or(AppError::StorageNotInitialized)?;
+        
+        storage.get_transcript(id)?
+    };
+    
+    log::info!("续写转录记录，ID: {}", id);
+    begin_capture_with(&app, &state, Some(AppendSource {
+        target: Some(record.id),
+        content: record.content,
+    }))
+}
+
+/// 按住说话：按下时开始捕获，之前按键的文本先载入，本次识别的文本追加在后面
+/// new_note 为 true 时开始一条新笔记；已按住时（按键重复）直接返回
+#[tauri::command]
+async fn begin_push_to_talk(app: AppHandle, state: State<'_, AppState>, new_note: Option<bool>) -> Result<(), AppError> {
+    let mut ptt = lock::lock_recover(&state.push_to_talk, "push_to_talk");
+    if ptt.active {
+        return Ok(());
+    }
+    if new_note.unwrap_or(false) {
+        *ptt = PushToTalkState::default();
+    }
+    
+    log::info!("按住说话：开始");
+    let append = (ptt.note_id.is_some() || !ptt.note_text.is_empty()).then(|| AppendSource {
+        target: ptt.note_id,
+        content: ptt.note_text.clone(),
+    });
+    begin_capture_with(&app, &state, append)?;
+    ptt.active = true;
+    Ok(())
+}
+
+/// 按住说话：松开时停止捕获，按自动保存设置保存（或更新）笔记，返回笔记的完整文本
+#[tauri::command]
+async fn end_push_to_talk(app: AppHandle, state: State<'_, AppState>) -> Result<TranscriptionStatus, AppError> {
+    let mut ptt = lock::lock_recover(&state.push_to_talk, "push_to_talk");
+    if !std::mem::take(&mut ptt.active) {
+        return Err(AppError::NotCapturing);
+    }
+    // 捕获已被其他方式结束（停止关键词、手动停止或取消），文本已按对应方式处理
+    if !AudioBridge::is_capturing() {
+        return Err(AppError::NotCapturing);
+    }
+    
+    log::info!("按住说话：结束");
+    let status = end_capture(&app, &state)?;
+    if status.saved_record_id.is_some() {
+        ptt.note_id = status.saved_record_id;
+    }
+    ptt.note_text = status.full_text.clone();
+    Ok(status)
+}
+
+/// 是否正在按住说话
+#[tauri::command]
+async fn is_push_to_talk_active(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(lock::lock_recover(&state.push_to_talk, "push_to_talk").active)
+}
+
+/// 取消捕获：停止录制并丢弃本次会话的全部文本，即使开启了自动保存也不保存
+fn cancel_capture(app: &AppHandle, state: &AppState) -> Result<(), AppError> {
+    log::info!("取消转录");
+    
+    let mut session = state.session();
+    if !AudioBridge::is_capturing() {
+        return Err(AppError::NotCapturing);
+    }
+    
+    AudioBridge::stop_transcription();
+    autosave::finish();
+    AudioBridge::clear_transcription();
+    
+    // 重置计时，续写模式下原记录保持不变
+    session.capture_start_time = None;
+    session.capture_started_at = None;
+    session.pause = PauseState::default();
+    session.append_target = None;
+    
+    emit_app_event(app, CAPTURE_CANCELLED_EVENT, CaptureCancelledPayload {
+        session_id: session.session_id.clone(),
+    });
+    
+    Ok(())
+}
+
+/// 停止转录
+#[tauri::command]
+async fn stop_transcription(app: AppHandle, state: State<'_, AppState>) -> Result<TranscriptionStatus, AppError> {
+    end_capture(&app, &state)
+}
+
+/// 取消转录：停止并丢弃本次会话的文本，不保存记录（用于误触开始）
+#[tauri::command]
+async fn cancel_transcription(app: AppHandle, state: State<'_, AppState>) -> Result<(), AppError> {
+    cancel_capture(&app, &state)
+}
+
+/// 转录已有的音频文件（WAV / M4A），进度通过 file-transcription-progress 事件发送
+/// 结果与实时会话一样按自动保存设置保存，时长取音频文件的时长
+#[tauri::command]
+async fn transcribe_file(state: State<'_, AppState>, path: String) -> Result<TranscriptionStatus, AppError> {
+    let path = std::path::PathBuf::from(path.trim());
+    let supported = path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| audio_bridge::SUPPORTED_AUDIO_FILE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+    if !supported {
+        return Err(AppError::InvalidInput(format!(
+            "不支持的音频文件格式（支持 {}）",
+            audio_bridge::SUPPORTED_AUDIO_FILE_EXTENSIONS.join(" / ")
+        )));
+    }
+    if !path.is_file() {
+        return Err(AppError::InvalidInput(format!("音频文件不存在: {}", path.display())));
+    }
+    
+    log::info!("转录音频文件: {}", path.display());
+    
+    let (auto_save, session_id) = {
+        let mut session = state.session();
+        // 实时转录进行中时直接拒绝，先于任何共享状态的修改：
+        // 不替换正在进行的会话的 ID，也不改动其识别器配置
+        if AudioBridge::is_capturing() {
+            return Err(AppError::AlreadyCapturing);
+        }
+        if session.on_device_recognition && !AudioBridge::supports_on_device() {
+            return Err(AppError::InvalidInput(format!(
+                "当前语言 {} 不支持端侧识别，请关闭强制端侧识别或更换语言",
+                session.current_language
+            )));
+        }
+        AudioBridge::set_contextual_strings(&session.custom_vocabulary);
+        AudioBridge::set_on_device_required(session.on_device_recognition);
+        AudioBridge::set_task_hint(session.task_hint);
+        (session.auto_save, new_session_id(&mut session))
+    };
+    
+    // 文件转录阻塞直到识别结束，不持有会话锁
+    let duration_seconds = AudioBridge::transcribe_file(&path)?.round() as i32;
+    let full_text = final_transcription();
+    let language = state.session().effective_language();
+    
+    let saved_record = if auto_save && !full_text.trim().is_empty() {
+        let storage = state.storage.lock()
+            .map_err(|_| AppError::LockPoisoned)?;
+        let storage = storage.as_ref()
+            .ok_or(AppError::StorageNotInitialized)?;
+        Some(storage.save_transcript(
+            &full_text,
+            &language,
+            duration_seconds,
+            &AudioBridge::get_segments(),
+            None,
+            Some(&session_id),
+        )?)
+    } else {
+        None
+    };
+    
+    Ok(TranscriptionStatus {
+        is_capturing: false,
+        is_paused: false,
+        latest_text: String::new(),
+        full_text,
+        duration_seconds,
+        active_duration_seconds: duration_seconds,
+        average_confidence: AudioBridge::get_average_confidence(),
+        saved_record_id: saved_record.as_ref().map(|r| r.id),
+        saved_record,
+        error: AudioBridge::get_error(),
+        is_clipping: AudioBridge::is_clipping(),
+        started_at: None,
+        session_id: Some(session_id),
+    })
+}
+
+/// 暂停转录（保留已确认的文本，暂停期间不计入时长）
+#[tauri::command]
+async fn pause_transcription(state: State<'_, AppState>) -> Result<(), AppError> {
+    log::info!("暂停转录");
+    
+    if !AudioBridge::is_capturing() {
+        return Err(AppError::NotCapturing);
+    }
+    
+    let mut session = state.session();
+    if session.pause.is_paused() {
+        return Err(AppError::InvalidInput("转录已处于暂停状态".to_string()));
+    }
+    
+    AudioBridge::pause_transcription();
+    session.pause.paused_at = Some(std::time::Instant::now());
+    
+    Ok(())
+}
+
+/// 恢复转录
+#[tauri::command]
+async fn resume_transcription(state: State<'_, AppState>) -> Result<(), AppError> {
+    log::info!("恢复转录");
+    
+    if !AudioBridge::is_capturing() {
+        return Err(AppError::NotCapturing);
+    }
+    
+    let mut session = state.session();
+    let Some(paused_at) = session.pause.paused_at.take() else {
+        return Err(AppError::InvalidInput("转录未处于暂停状态".to_string()));
+    };
+    
+    session.pause.paused_total += paused_at.elapsed();
+    AudioBridge::resume_transcription();
+    
+    Ok(())
+}
+
+/// 获取已确认文本的字符数，供进度显示轮询，不返回文本内容
+#[tauri::command]
+async fn get_transcription_length() -> Result<usize, AppError> {
+    Ok(AudioBridge::get_transcription_length())
+}
+
+/// 获取已确认片段的数量
+#[tauri::command]
+async fn get_confirmed_segment_count() -> Result<usize, AppError> {
+    Ok(AudioBridge::get_segment_count())
+}
+
+/// 获取转录状态
+/// 读取期间持有会话锁，开始/停止/暂停不会与读取交错，各字段来自同一时刻
+#[tauri::command]
+async fn get_transcription_status(state: State<'_, AppState>) -> Result<TranscriptionStatus, AppError> {
+    Ok(transcription_status(&state.session()))
+}
+
+/// 由会话状态和音频桥接层组装转录状态，调用方需持有会话锁
+fn transcription_status(session: &SessionState) -> TranscriptionStatus {
+    let is_capturing = AudioBridge::is_capturing();
+    
+    let latest_text = AudioBridge::get_latest_transcription();
+    let full_text = AudioBridge::get_full_transcription();
+    
+    let duration_seconds = session.duration_seconds();
+    let active_duration_seconds = session.active_duration_seconds();
+    
+    TranscriptionStatus {
+        is_capturing,
+        is_paused: session.pause.is_paused(),
+        latest_text,
+        full_text,
+        duration_seconds,
+        active_duration_seconds,
+        average_confidence: AudioBridge::get_average_confidence(),
+        saved_record_id: None,
+        saved_record: None,
+        error: AudioBridge::get_error(),
+        is_clipping: AudioBridge::is_clipping(),
+        started_at: session.started_at(),
+        session_id: session.session_id.clone(),
+    }
+}
+
+/// 获取当前（或最近一次）转录会话的 ID，尚未开始过转录时返回 None
+#[tauri::command]
+async fn get_current_session_id(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    Ok(state.session().session_id.clone())
+}
+
+/// 获取当前音频电平（0.0 ~ 1.0）
+#[tauri::command]
+async fn get_audio_level() -> Result<f32, AppError> {
+    Ok(AudioBridge::get_audio_level())
+}
+
+/// 获取并清除原生模块最近一次报告的错误
+#[tauri::command]
+async fn get_last_error() -> Result<Option<String>, AppError> {
+    Ok(AudioBridge::take_error())
+}
+
+/// 获取原生模块最近的错误历史（带时间戳，最旧的在前），不影响 get_last_error
+#[tauri::command]
+async fn get_error_history() -> Result<Vec<ErrorRecord>, AppError> {
+    Ok(AudioBridge::get_error_history())
+}
+
+/// 设置部分转录结果事件的最小发送间隔（毫秒，0 表示不限制），最终结果不受影响
+#[tauri::command]
+async fn set_partial_update_interval(interval_ms: u64) -> Result<(), AppError> {
+    log::info!("设置部分结果事件间隔: {} ms", interval_ms);
+    AudioBridge::set_partial_emit_interval(interval_ms);
+    Ok(())
+}
+
+/// 设置内存中转录缓冲区的最大字符数（0 表示不限制）
+/// 超出部分会写入会话文件，停止转录时会合并回完整文本
+#[tauri::command]
+async fn set_buffer_limit(chars: usize) -> Result<(), AppError> {
+    log::info!("设置转录缓冲区上限: {} 字符", chars);
+    AudioBridge::set_buffer_limit(chars);
+    Ok(())
+}
+
+/// 设置内存中保留的片段最大数量（0 表示不限制）
+/// 超出的最旧片段连同时间信息写入片段溢出文件，保存和字幕导出时会合并回完整列表
+#[tauri::command]
+async fn set_segment_limit(limit: usize) -> Result<(), AppError> {
+    log::info!("设置片段数量上限: {}", limit);
+    AudioBridge::set_segment_limit(limit);
+    Ok(())
+}
+
+/// 将当前会话的片段导出为字幕文件（srt / vtt），逐条从片段溢出文件和内存流式写入
+/// 返回写入的片段数量
+#[tauri::command]
+async fn export_session_subtitles(format: String, output_path: String) -> Result<usize, AppError> {
+    if format != "srt" && format != "vtt" {
+        return Err(AppError::InvalidInput(format!("字幕导出不支持的格式: {}（支持 srt / vtt）", format)));
+    }
+    
+    let file = std::fs::File::create(output_path.trim())
+        .map_err(|e| AppError::Io(format!("无法创建字幕文件: {}", e)))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let write_err = |e: std::io::Error| AppError::Io(format!("写入字幕文件失败: {}", e));
+    
+    writer.write_all(storage::subtitle_header(&format).as_bytes()).map_err(write_err)?;
+    let mut count = 0;
+    AudioBridge::for_each_segment(|seg| {
+        storage::write_cue(&mut writer, count, seg, &format).map_err(write_err)?;
+        count += 1;
+        Ok(())
+    })?;
+    writer.flush().map_err(write_err)?;
+    
+    log::info!("已导出 {} 条字幕到 {}", count, output_path);
+    Ok(count)
+}
+
+/// 设置实时输出文件，每个最终片段追加为一行，外部工具可 tail -f 读取
+/// 相对路径基于数据目录解析，传 None 关闭；返回实际使用的路径
+#[tauri::command]
+async fn set_live_output_file(state: State<'_, AppState>, path: Option<String>) -> Result<Option<String>, AppError> {
+    let resolved = match path.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(path) => {
+            let path = std::path::PathBuf::from(path);
+            if path.is_absolute() {
+                Some(path)
+            } else {
+                let storage = state.storage.lock()
+                    .map_err(|_| AppError::LockPoisoned)?;
+                let storage = storage.as_ref()
+                    .ok_or(AppError::StorageNotInitialized)?;
+                Some(storage.data_dir().join(path))
+            }
+        }
+    };
+    
+    AudioBridge::set_live_output_file(resolved.clone())?;
+    match &resolved {
+        Some(path) => log::info!("实时输出文件: {}", path.display()),
+        None => log::info!("已关闭实时输出文件"),
+    }
+    Ok(resolved.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// 设置自定义词汇（产品名、术语等），返回实际生效的列表
+/// 空字符串和重复项会被忽略，数量上限为 MAX_CONTEXTUAL_STRINGS
+#[tauri::command]
+async fn set_custom_vocabulary(state: State<'_, AppState>, phrases: Vec<String>) -> Result<Vec<String>, AppError> {
+    let mut vocabulary: Vec<String> = Vec::new();
+    for phrase in phrases {
+        let phrase = phrase.trim();
+        if !phrase.is_empty() && !vocabulary.iter().any(|p| p == phrase) {
+            vocabulary.push(phrase.to_string());
+        }
+    }
+    vocabulary.truncate(audio_bridge::MAX_CONTEXTUAL_STRINGS);
+    
+    log::info!("设置自定义词汇: {} 项", vocabulary.len());
+    AudioBridge::set_contextual_strings(&vocabulary);
+    
+    state.session().custom_vocabulary = vocabulary.clone();
+    persist_settings(&state)?;
+    
+    Ok(vocabulary)
+}
+
+/// 获取自定义词汇
+#[tauri::command]
+async fn get_custom_vocabulary(state: State<'_, AppState>) -> Result<Vec<String>, AppError> {
+    Ok(state.session().custom_vocabulary.clone())
+}
+
+/// 设置是否强制使用端侧识别（保护隐私，不回退到服务器识别）
+#[tauri::command]
+async fn set_on_device_recognition(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    log::info!("设置强制端侧识别: {}", enabled);
+    
+    AudioBridge::set_on_device_required(enabled);
+    state.session().on_device_recognition = enabled;
+    
+    persist_settings(&state)
+}
+
+/// 设置已确认片段之间的分隔符（如空格、空字符串）
+/// 传 None 恢复默认：中日韩语言不加分隔符，其他语言换行
+#[tauri::command]
+async fn set_segment_separator(state: State<'_, AppState>, sep: Option<String>) -> Result<(), AppError> {
+    log::info!("设置片段分隔符: {:?}", sep);
+    
+    AudioBridge::set_segment_separator(sep.clone());
+    state.session().segment_separator = sep;
+    
+    persist_settings(&state)
+}
+
+/// 显示或隐藏菜单栏（托盘）图标
+#[tauri::command]
+async fn set_tray_enabled(app: AppHandle, state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    log::info!("设置托盘图标: {}", enabled);
+    
+    if enabled {
+        tray::create(&app)
+            .map_err(|e| AppError::Native(format!("创建托盘图标失败: {}", e)))?;
+    } else {
+        tray::remove(&app);
+    }
+    state.session().tray_enabled = enabled;
+    
+    persist_settings(&state)
+}
+
+/// 强制音频直通（跳过混音与重采样），供确认输入已是 16kHz 单声道的用户使用
+/// 关闭时仍会自动检测格式，格式匹配时同样不做转换
+#[tauri::command]
+async fn set_audio_passthrough(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    log::info!("设置强制音频直通: {}", enabled);
+    
+    AudioBridge::set_audio_passthrough(enabled);
+    state.session().audio_passthrough = enabled;
+    
+    persist_settings(&state)
+}
+
+/// 设置静音分段：启用时连续静音达到 silence_ms 毫秒即强制分段，
+/// 识别器尚未确认的文本提前确认，下一段另起一行，长时间独白也能分成段落
+#[tauri::command]
+async fn set_silence_split(state: State<'_, AppState>, enabled: bool, silence_ms: u64) -> Result<(), AppError> {
+    if !audio_bridge::SILENCE_SPLIT_RANGE_MS.contains(&silence_ms) {
+        return Err(AppError::InvalidInput(format!(
+            "静音时长必须在 {} 到 {} 毫秒之间",
+            audio_bridge::SILENCE_SPLIT_RANGE_MS.start(),
+            audio_bridge::SILENCE_SPLIT_RANGE_MS.end()
+        )));
+    }
+    log::info!("设置静音分段: {}（{} 毫秒）", enabled, silence_ms);
+    
+    AudioBridge::set_silence_split(enabled, silence_ms);
+    {
+        let mut session = state.session();
+        session.silence_split_enabled = enabled;
+        session.silence_split_ms = silence_ms;
+    }
+    
+    persist_settings(&state)
+}
+
+/// 启用或关闭识别结果后处理（英文句首大写、片段末尾补标点）
+/// 原始识别文本保留在片段中，导出时可通过 raw 参数选择
+#[tauri::command]
+async fn set_post_processing(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    log::info!("设置识别结果后处理: {}", enabled);
+    
+    AudioBridge::set_post_processing(enabled);
+    state.session().post_processing = enabled;
+    
+    persist_settings(&state)
+}
+
+/// 设置停止关键词（如 "stop recording"），说出后自动停止转录；传 None 关闭
+#[tauri::command]
+async fn set_stop_keyword(state: State<'_, AppState>, phrase: Option<String>) -> Result<(), AppError> {
+    let phrase = match phrase {
+        Some(p) if p.trim().is_empty() => {
+            return Err(AppError::InvalidInput("停止关键词不能为空".to_string()));
+        }
+        Some(p) => Some(p.trim().to_string()),
+        None => None,
+    };
+    log::info!("设置停止关键词: {:?}", phrase);
+    
+    AudioBridge::set_stop_keyword(phrase.clone());
+    state.session().stop_keyword = phrase;
+    
+    persist_settings(&state)
+}
+
+/// 获取是否强制使用端侧识别
+#[tauri::command]
+async fn get_on_device_recognition(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.session().on_device_recognition)
+}
+
+/// 设置转录结束时是否发送系统通知
+#[tauri::command]
+async fn set_notifications_enabled(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    log::info!("设置系统通知: {}", enabled);
+    
+    notify::set_enabled(enabled);
+    state.session().notifications_enabled = enabled;
+    
+    persist_settings(&state)
+}
+
+/// 获取是否发送系统通知
+#[tauri::command]
+async fn get_notifications_enabled(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.session().notifications_enabled)
+}
+
+/// 设置发送通知的最短会话时长（秒），更短的会话不通知
+#[tauri::command]
+async fn set_notification_threshold(state: State<'_, AppState>, seconds: u64) -> Result<(), AppError> {
+    log::info!("设置通知最短会话时长: {} 秒", seconds);
+    
+    notify::set_min_session_seconds(seconds);
+    state.session().notification_min_seconds = seconds;
+    
+    persist_settings(&state)
+}
+
+/// 设置转录进行中自动保存会话的间隔（秒）
+#[tauri::command]
+async fn set_autosave_interval(state: State<'_, AppState>, seconds: u64) -> Result<(), AppError> {
+    if seconds == 0 {
+        return Err(AppError::InvalidInput("自动保存间隔必须大于 0 秒".to_string()));
+    }
+    log::info!("设置会话自动保存间隔: {} 秒", seconds);
+    
+    autosave::set_interval_seconds(seconds);
+    state.session().autosave_interval_seconds = seconds;
+    
+    persist_settings(&state)
+}
+
+/// 获取当前语言的识别方式，供界面在开始转录前提示是否会联网
+#[tauri::command]
+async fn get_recognition_mode(state: State<'_, AppState>) -> Result<RecognitionModeInfo, AppError> {
+    let (language, on_device_required) = {
+        let session = state.session();
+        (session.current_language.clone(), session.on_device_recognition)
+    };
+    
+    let mode = AudioBridge::recognition_mode(&language);
+    Ok(RecognitionModeInfo {
+        conflict: on_device_required && mode != RecognitionMode::OnDevice,
+        language,
+        mode,
+        on_device_required,
+    })
+}
+
+/// 获取后端整体状态快照，供"复制调试信息"使用
+#[tauri::command]
+async fn get_app_status(state: State<'_, AppState>) -> Result<AppStatus, AppError> {
+    let (audio_source, current_language) = {
+        let session = state.session();
+        (session.audio_source, session.current_language.clone())
+    };
+    let (audio_permission, speech_permission) = AudioBridge::check_permissions(audio_source);
+    
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    Ok(AppStatus {
+        native_available: AudioBridge::is_native_available(),
+        audio_permission,
+        speech_permission,
+        is_capturing: AudioBridge::is_capturing(),
+        current_language,
+        on_device_supported: AudioBridge::supports_on_device(),
+        storage_record_count: storage.count_transcripts()?,
+        data_dir: storage.data_dir().to_string_lossy().to_string(),
+    })
+}
+
+/// 获取本次转录的音频 / 转录回调计数与速率，用于排查卡顿发生在音频侧还是识别侧
+#[tauri::command]
+async fn get_bridge_metrics() -> Result<BridgeMetrics, AppError> {
+    Ok(AudioBridge::get_metrics())
+}
+
+/// 获取当前音频源的捕获状态
+#[tauri::command]
+async fn get_capture_status() -> Result<CaptureStatus, AppError> {
+    Ok(AudioBridge::get_capture_status())
+}
+
+/// 获取语音识别状态
+#[tauri::command]
+async fn get_recognition_status() -> Result<RecognitionStatus, AppError> {
+    Ok(AudioBridge::get_recognition_status())
+}
+
+/// 原生音频模块是否可用，不可用时前端应提示用户（转录命令会返回错误）
+#[tauri::command]
+async fn is_native_available() -> Result<bool, AppError> {
+    Ok(AudioBridge::is_native_available())
+}
+
+/// 设置低置信度阈值（0.0 ~ 1.0），md_annotated 导出中低于该值的片段会被标记
+#[tauri::command]
+async fn set_confidence_threshold(state: State<'_, AppState>, threshold: f32) -> Result<(), AppError> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(AppError::InvalidInput("置信度阈值必须在 0 到 1 之间".to_string()));
+    }
+    log::info!("设置低置信度阈值: {:.2}", threshold);
+    
+    storage::set_confidence_threshold(threshold);
+    state.session().confidence_threshold = threshold;
+    
+    persist_settings(&state)
+}
+
+/// 设置是否跳过重复保存（与上一条记录内容、语言相同且创建时间相近时返回已有记录）
+#[tauri::command]
+async fn set_skip_duplicate_saves(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    log::info!("设置跳过重复保存: {}", enabled);
+    
+    storage::set_skip_duplicates(enabled);
+    state.session().skip_duplicate_saves = enabled;
+    
+    persist_settings(&state)
+}
+
+/// 设置导出格式（txt / md）的自定义模板，模板为空时恢复内置布局
+/// 占位符：{created_at}、{language}、{duration}、{id}、{content}，模板必须包含 {content}
+#[tauri::command]
+async fn set_export_template(state: State<'_, AppState>, format: String, template: String) -> Result<(), AppError> {
+    log::info!("设置 {} 导出模板", format);
+    
+    storage::set_export_template(&format, &template)?;
+    {
+        let mut session = state.session();
+        if template.trim().is_empty() {
+            session.export_templates.remove(&format);
+        } else {
+            session.export_templates.insert(format, template);
+        }
+    }
+    
+    persist_settings(&state)
+}
+
+/// 恢复上次异常退出时自动保存的会话文本，没有可恢复内容时返回 None
+#[tauri::command]
+async fn recover_last_session() -> Result<Option<String>, AppError> {
+    let recovered = autosave::take_recovered();
+    if recovered.is_some() {
+        log::info!("已恢复上次会话的自动保存内容");
+    }
+    Ok(recovered)
+}
+
+/// 设置停止转录时是否自动保存
+#[tauri::command]
+async fn set_auto_save(state: State<'_, AppState>, enabled: bool) -> Result<(), AppError> {
+    log::info!("设置自动保存: {}", enabled);
+    
+    state.session().auto_save = enabled;
+    
+    persist_settings(&state)
+}
+
+/// 获取自动保存设置
+#[tauri::command]
+async fn get_auto_save(state: State<'_, AppState>) -> Result<bool, AppError> {
+    Ok(state.session().auto_save)
+}
+
+/// 获取当前会话的转录片段（含时间信息）
+#[tauri::command]
+async fn get_transcription_segments() -> Result<Vec<TranscriptSegment>, AppError> {
+    Ok(AudioBridge::get_segments())
+}
+
+/// 保存转录记录
+#[tauri::command]
+async fn save_transcript(
+    state: State<'_, AppState>,
+    content: String,
+    duration_seconds: i32,
+) -> Result<TranscriptRecord, AppError> {
+    let (language, started_at, session_id) = {
+        let session = state.session();
+        // 暂停中保存的内容可能不完整，要求先恢复或停止
+        if session.pause.is_paused() {
+            return Err(AppError::InvalidInput("转录已暂停，请先恢复或停止后再保存".to_string()));
+        }
+        (session.effective_language(), session.started_at_utc(), session.session_id.clone())
+    };
+    
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    // 附带当前会话的片段时间信息，用于字幕导出
+    let segments = AudioBridge::get_segments();
+    
+    storage.save_transcript(
+        &content,
+        &language,
+        duration_seconds,
+        &segments,
+        started_at.as_deref(),
+        session_id.as_deref(),
+    )
+}
+
+/// 获取转录历史（favorites_first 为 true 时收藏的记录排在前面）
+#[tauri::command]
+async fn get_transcript_history(
+    state: State<'_, AppState>,
+    favorites_first: Option<bool>,
+) -> Result<Vec<TranscriptRecord>, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    match favorites_first {
+        Some(true) => storage.load_history(true),
+        _ => storage.load_transcripts(),
+    }
+}
+
+/// 分页获取转录历史
+#[tauri::command]
+async fn get_transcript_history_page(
+    state: State<'_, AppState>,
+    offset: i64,
+    limit: i64,
+    favorites_first: Option<bool>,
+) -> Result<TranscriptPage, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.load_transcripts_page(offset, limit, favorites_first.unwrap_or(false))
+}
+
+/// 分块读取记录内容（按字节偏移，边界对齐到 UTF-8 字符），供前端按需加载超长记录
+#[tauri::command]
+async fn get_transcript_chunk(
+    state: State<'_, AppState>,
+    id: i64,
+    byte_offset: usize,
+    len: usize,
+) -> Result<TranscriptChunk, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.load_transcript_chunk(id, byte_offset, len)
+}
+
+/// 搜索转录记录
+#[tauri::command]
+async fn search_transcripts(
+    state: State<'_, AppState>,
+    query: String,
+) -> Result<Vec<TranscriptRecord>, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.search_transcripts(&query)
+}
+
+/// 按相关度搜索转录内容：匹配越靠前、出现次数越多排名越高，结果附带匹配处的上下文预览
+#[tauri::command]
+async fn search_transcripts_ranked(
+    state: State<'_, AppState>,
+    query: String,
+) -> Result<Vec<RankedSearchResult>, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    let results = storage.search_transcripts_ranked(&query)?
+        .into_iter()
+        .map(|(record, score)| RankedSearchResult {
+            snippet: storage::search_snippet(&record.content, &query).unwrap_or_default(),
+            record,
+            score,
+        })
+        .collect();
+    Ok(results)
+}
+
+/// 获取创建日期在指定范围内（含首尾，YYYY-MM-DD）的转录记录，最新的在前
+#[tauri::command]
+async fn get_transcripts_in_range(
+    state: State<'_, AppState>,
+    start: String,
+    end: String,
+) -> Result<Vec<TranscriptRecord>, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.load_transcripts_between(&start, &end)
+}
+
+/// 获取转录统计信息
+#[tauri::command]
+async fn get_statistics(state: State<'_, AppState>) -> Result<TranscriptStats, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.get_statistics()
+}
+
+/// 删除转录记录（移入回收站）
+#[tauri::command]
+async fn delete_transcript(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.delete_transcript(id)
+}
+
+/// 批量删除转录记录（移入回收站）
+#[tauri::command]
+async fn delete_transcripts(state: State<'_, AppState>, ids: Vec<i64>) -> Result<usize, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.delete_transcripts(&ids)
+}
+
+/// 清空全部历史记录（移入回收站），confirm 必须为 true，返回移除的数量
+#[tauri::command]
+async fn clear_all_transcripts(state: State<'_, AppState>, confirm: bool) -> Result<usize, AppError> {
+    if !confirm {
+        return Err(AppError::InvalidInput("清空历史记录需要确认".to_string()));
+    }
+    
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.clear_all()
+}
+
+/// 设置历史记录数量上限（0 表示不限制），超出时最旧的记录移入归档文件，返回本次归档的数量
+#[tauri::command]
+async fn set_max_history(state: State<'_, AppState>, n: usize) -> Result<usize, AppError> {
+    log::info!("设置历史记录上限: {}", n);
+    
+    let archived = {
+        let mut storage = state.storage.lock()
+            .map_err(|_| AppError::LockPoisoned)?;
+        let storage = storage.as_mut()
+            .ok_or(AppError::StorageNotInitialized)?;
+        storage.set_max_history(n)?
+    };
+    state.session().max_history_records = n;
+    
+    persist_settings(&state)?;
+    Ok(archived)
 }
 
-// ============= Tauri 命令 =============
+/// 设置单次会话的最长有效录制时长（秒，0 表示不限制），达到后自动停止并保存
+/// 对进行中的会话同样生效
+#[tauri::command]
+async fn set_max_session_duration(state: State<'_, AppState>, seconds: u64) -> Result<(), AppError> {
+    log::info!("设置会话时长上限: {} 秒", seconds);
+    
+    state.session().max_session_duration_seconds = seconds;
+    
+    persist_settings(&state)
+}
 
-/// 检查权限状态
+/// 读取已归档的历史记录
 #[tauri::command]
-async fn check_permissions() -> Result<PermissionStatus, String> {
-    log::info!("检查权限状态");
+async fn load_archive(state: State<'_, AppState>) -> Result<Vec<TranscriptRecord>, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    let (audio_ok, speech_ok) = AudioBridge::check_permissions();
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    Ok(PermissionStatus {
-        audio_capture: audio_ok,
-        speech_recognition: speech_ok,
-    })
+    storage.load_archive()
 }
 
-/// 请求权限（打开系统设置）
+/// 获取回收站中的记录
 #[tauri::command]
-async fn request_permissions() -> Result<(), String> {
-    log::info!("请求用户授权");
+async fn list_trash(state: State<'_, AppState>) -> Result<Vec<TrashedTranscript>, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    // 打开系统偏好设置 - 隐私与安全 - 屏幕录制
-    std::process::Command::new("open")
-        .args(["x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture"])
-        .spawn()
-        .map_err(|e| format!("无法打开系统设置: {}", e))?;
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    Ok(())
+    storage.list_trash()
 }
 
-/// 设置识别语言
+/// 从回收站恢复转录记录
 #[tauri::command]
-async fn set_language(state: State<'_, AppState>, language: String) -> Result<(), String> {
-    log::info!("设置识别语言: {}", language);
+async fn restore_transcript(state: State<'_, AppState>, id: i64) -> Result<TranscriptRecord, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    AudioBridge::set_language(&language);
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    let mut current = state.current_language.lock()
-        .map_err(|_| "无法获取状态锁")?;
-    *current = language;
+    storage.restore_transcript(id)
+}
+
+/// 清空回收站，返回永久删除的数量
+#[tauri::command]
+async fn empty_trash(state: State<'_, AppState>) -> Result<usize, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    Ok(())
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.empty_trash()
 }
 
-/// 获取当前语言
+/// 合并多条转录记录（至少两条），返回合并后的新记录
 #[tauri::command]
-async fn get_language(state: State<'_, AppState>) -> Result<String, String> {
-    let current = state.current_language.lock()
-        .map_err(|_| "无法获取状态锁")?;
-    Ok(current.clone())
+async fn merge_transcripts(state: State<'_, AppState>, ids: Vec<i64>) -> Result<TranscriptRecord, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.merge_transcripts(&ids)
 }
 
-/// 获取支持的语言列表
+/// 在指定行处拆分转录记录，返回拆分后的两条新记录
 #[tauri::command]
-async fn get_supported_languages() -> Result<Vec<(String, String)>, String> {
-    // 常用语言列表
-    Ok(vec![
-        ("zh-CN".to_string(), "简体中文".to_string()),
-        ("zh-TW".to_string(), "繁體中文".to_string()),
-        ("en-US".to_string(), "English (US)".to_string()),
-        ("en-GB".to_string(), "English (UK)".to_string()),
-        ("ja-JP".to_string(), "日本語".to_string()),
-        ("ko-KR".to_string(), "한국어".to_string()),
-        ("es-ES".to_string(), "Español".to_string()),
-        ("fr-FR".to_string(), "Français".to_string()),
-        ("de-DE".to_string(), "Deutsch".to_string()),
-    ])
+async fn split_transcript(
+    state: State<'_, AppState>,
+    id: i64,
+    line_index: usize,
+) -> Result<(TranscriptRecord, TranscriptRecord), AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.split_transcript(id, line_index)
 }
 
-/// 开始转录
+/// 修改转录记录内容
 #[tauri::command]
-async fn start_transcription(state: State<'_, AppState>) -> Result<(), String> {
-    log::info!("开始转录");
+async fn update_transcript(state: State<'_, AppState>, id: i64, content: String) -> Result<TranscriptRecord, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    // 检查是否已在捕获
-    if AudioBridge::is_capturing() {
-        return Err("转录已在进行中".to_string());
-    }
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    // 记录开始时间
-    {
-        let mut start_time = state.capture_start_time.lock()
-            .map_err(|_| "无法获取状态锁")?;
-        *start_time = Some(std::time::Instant::now());
-    }
+    storage.update_transcript_content(id, content)
+}
+
+/// 切换收藏状态，返回新的收藏状态
+#[tauri::command]
+async fn toggle_favorite(state: State<'_, AppState>, id: i64) -> Result<bool, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    // 启动音频捕获和语音识别
-    AudioBridge::start_transcription()?;
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    Ok(())
+    let favorite = !storage.get_transcript(id)?.is_favorite;
+    storage.set_favorite(id, favorite)?;
+    Ok(favorite)
 }
 
-/// 停止转录
+/// 设置转录记录的评分（1 ~ 5，None 表示清除）
 #[tauri::command]
-async fn stop_transcription(state: State<'_, AppState>) -> Result<TranscriptionStatus, String> {
-    log::info!("停止转录");
+async fn set_rating(state: State<'_, AppState>, id: i64, rating: Option<u8>) -> Result<TranscriptRecord, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    let duration_seconds;
-    {
-        let start_time = state.capture_start_time.lock()
-            .map_err(|_| "无法获取状态锁")?;
-        duration_seconds = start_time
-            .map(|t| t.elapsed().as_secs() as i32)
-            .unwrap_or(0);
-    }
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    // 先获取当前的转录内容（在停止之前）
-    let mut full_text = AudioBridge::get_full_transcription();
-    let latest_text = AudioBridge::get_latest_transcription();
+    storage.set_rating(id, rating)
+}
+
+/// 设置转录记录的备注（None 或空白表示清除）
+#[tauri::command]
+async fn set_note(state: State<'_, AppState>, id: i64, note: Option<String>) -> Result<TranscriptRecord, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    // 停止音频捕获
-    AudioBridge::stop_transcription();
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    // 如果有未确认的 latest_text，合并到 full_text
-    if !latest_text.is_empty() && !full_text.ends_with(&latest_text) {
-        if !full_text.is_empty() {
-            full_text.push('\n');
-        }
-        full_text.push_str(&latest_text);
-    }
+    storage.set_note(id, note)
+}
+
+/// 为转录记录添加标签
+#[tauri::command]
+async fn add_tag(state: State<'_, AppState>, id: i64, tag: String) -> Result<TranscriptRecord, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    log::info!("转录停止，最终文本长度: {} 字符", full_text.len());
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    Ok(TranscriptionStatus {
-        is_capturing: false,
-        latest_text: String::new(), // 停止后清空
-        full_text,
-        duration_seconds,
-    })
+    storage.add_tag(id, &tag)
 }
 
-/// 获取转录状态
+/// 移除转录记录的标签
 #[tauri::command]
-async fn get_transcription_status(state: State<'_, AppState>) -> Result<TranscriptionStatus, String> {
-    let is_capturing = AudioBridge::is_capturing();
+async fn remove_tag(state: State<'_, AppState>, id: i64, tag: String) -> Result<TranscriptRecord, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    let latest_text = AudioBridge::get_latest_transcription();
-    let full_text = AudioBridge::get_full_transcription();
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    let duration_seconds = state.capture_start_time.lock()
-        .map_err(|_| "无法获取状态锁")?
-        .map(|t| t.elapsed().as_secs() as i32)
-        .unwrap_or(0);
+    storage.remove_tag(id, &tag)
+}
+
+/// 按标签获取转录记录
+#[tauri::command]
+async fn get_transcripts_by_tag(state: State<'_, AppState>, tag: String) -> Result<Vec<TranscriptRecord>, AppError> {
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
     
-    Ok(TranscriptionStatus {
-        is_capturing,
-        latest_text,
-        full_text,
-        duration_seconds,
-    })
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.get_transcripts_by_tag(&tag)
 }
 
-/// 保存转录记录
+/// 导出转录记录，返回文件路径、格式和文件大小
 #[tauri::command]
-async fn save_transcript(
+async fn export_transcript(
     state: State<'_, AppState>,
-    content: String,
-    duration_seconds: i32,
-) -> Result<TranscriptRecord, String> {
+    id: i64,
+    format: String,
+    timestamps: Option<bool>,
+    raw: Option<bool>,
+) -> Result<ExportResult, AppError> {
     let storage = state.storage.lock()
-        .map_err(|_| "无法获取状态锁")?;
+        .map_err(|_| AppError::LockPoisoned)?;
     
     let storage = storage.as_ref()
-        .ok_or("存储未初始化")?;
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    let language = state.current_language.lock()
-        .map_err(|_| "无法获取状态锁")?
-        .clone();
-    
-    storage.save_transcript(&content, &language, duration_seconds)
+    storage.export_transcript(id, &format, timestamps, raw.unwrap_or(false))
+}
+
+/// 导出转录记录，只返回文件路径（兼容旧版前端）
+#[tauri::command]
+async fn export_transcript_path(
+    state: State<'_, AppState>,
+    id: i64,
+    format: String,
+    timestamps: Option<bool>,
+    raw: Option<bool>,
+) -> Result<String, AppError> {
+    export_transcript(state, id, format, timestamps, raw)
+        .await
+        .map(|result| result.path)
 }
 
-/// 获取转录历史
+/// 将选中的多条记录按 ID 顺序合并导出为一个文件（txt / md / json）
 #[tauri::command]
-async fn get_transcript_history(state: State<'_, AppState>) -> Result<Vec<TranscriptRecord>, String> {
+async fn export_combined(
+    state: State<'_, AppState>,
+    ids: Vec<i64>,
+    format: String,
+) -> Result<String, AppError> {
     let storage = state.storage.lock()
-        .map_err(|_| "无法获取状态锁")?;
+        .map_err(|_| AppError::LockPoisoned)?;
     
     let storage = storage.as_ref()
-        .ok_or("存储未初始化")?;
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    storage.load_transcripts()
+    storage.export_combined(&ids, &format)
 }
 
-/// 删除转录记录
+/// 导出所有转录记录到指定目录（csv 格式时写入单个 history.csv）
 #[tauri::command]
-async fn delete_transcript(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+async fn export_all_transcripts(
+    state: State<'_, AppState>,
+    format: String,
+    output_dir: String,
+) -> Result<ExportAllResult, AppError> {
     let storage = state.storage.lock()
-        .map_err(|_| "无法获取状态锁")?;
+        .map_err(|_| AppError::LockPoisoned)?;
     
     let storage = storage.as_ref()
-        .ok_or("存储未初始化")?;
+        .ok_or(AppError::StorageNotInitialized)?;
     
-    storage.delete_transcript(id)
+    storage.export_all_transcripts(&format, std::path::Path::new(&output_dir))
 }
 
-/// 导出转录记录
+/// 将整个数据目录（数据库、设置、导出文件）备份为 zip，返回备份文件大小（字节）
 #[tauri::command]
-async fn export_transcript(
+async fn backup_data(state: State<'_, AppState>, output_path: String) -> Result<u64, AppError> {
+    let output = std::path::PathBuf::from(output_path.trim());
+    if output.extension().and_then(|ext| ext.to_str()) != Some("zip") {
+        return Err(AppError::InvalidInput("备份文件须以 .zip 结尾".to_string()));
+    }
+    
+    // 备份期间持有存储锁，避免数据库被写入
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    backup::create_backup(storage.data_dir(), &output)
+}
+
+/// 从 zip 备份恢复数据目录，返回恢复前自动生成的安全备份路径
+/// 恢复后重新打开数据库并应用备份中的设置；全局快捷键保持不变，重启后生效
+#[tauri::command]
+async fn restore_data(app: AppHandle, state: State<'_, AppState>, zip_path: String) -> Result<String, AppError> {
+    if AudioBridge::is_capturing() {
+        return Err(AppError::AlreadyCapturing);
+    }
+    
+    let zip_path = std::path::PathBuf::from(zip_path.trim());
+    backup::validate_backup(&zip_path)?;
+    
+    let mut storage_lock = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    let data_dir = storage_lock.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?
+        .data_dir()
+        .to_path_buf();
+    
+    // 先备份当前数据，恢复出错时可手动还原
+    let safety_path = data_dir
+        .join(backup::SAFETY_BACKUP_DIR)
+        .join(format!("pre_restore_{}.zip", chrono::Local::now().format("%Y%m%d_%H%M%S")));
+    backup::create_backup(&data_dir, &safety_path)?;
+    
+    // 关闭数据库连接后解压，无论成功与否都重新打开
+    *storage_lock = None;
+    let restored = backup::restore_backup(&zip_path, &data_dir);
+    
+    let mut storage = StorageManager::new(&app)?;
+    let settings = storage.load_settings();
+    if let Err(e) = storage.set_max_history(settings.max_history_records) {
+        log::warn!("归档旧记录失败: {}", e);
+    }
+    *storage_lock = Some(storage);
+    drop(storage_lock);
+    restored?;
+    
+    apply_settings(&settings);
+    let mut session = state.session();
+    let global_shortcut = session.global_shortcut.clone();
+    *session = SessionState::new(settings);
+    session.global_shortcut = global_shortcut;
+    
+    log::info!("数据已从 {:?} 恢复，安全备份: {:?}", zip_path, safety_path);
+    Ok(safety_path.to_string_lossy().into_owned())
+}
+
+/// 复制当前会话已确认的转录文本到剪贴板
+#[tauri::command]
+async fn copy_transcription_to_clipboard(app: AppHandle) -> Result<(), AppError> {
+    let text = AudioBridge::get_full_transcription();
+    if text.trim().is_empty() {
+        return Err(AppError::InvalidInput("没有可复制的转录文本".to_string()));
+    }
+    
+    app.clipboard()
+        .write_text(text)
+        .map_err(|e| AppError::Native(format!("写入剪贴板失败: {}", e)))
+}
+
+/// 复制已保存的转录记录内容到剪贴板
+#[tauri::command]
+async fn copy_transcript_to_clipboard(
+    app: AppHandle,
     state: State<'_, AppState>,
     id: i64,
-    format: String,
-) -> Result<String, String> {
+) -> Result<(), AppError> {
+    let content = {
+        let storage = state.storage.lock()
+            .map_err(|_| AppError::LockPoisoned)?;
+        
+        let storage = storage.as_ref()
+            .ok_or(AppError::StorageNotInitialized)?;
+        
+        storage.get_transcript(id)?.content
+    };
+    
+    if content.trim().is_empty() {
+        return Err(AppError::InvalidInput("没有可复制的转录文本".to_string()));
+    }
+    
+    app.clipboard()
+        .write_text(content)
+        .map_err(|e| AppError::Native(format!("写入剪贴板失败: {}", e)))
+}
+
+/// 从 JSON 文件导入转录记录，返回成功导入的数量
+#[tauri::command]
+async fn import_transcripts(state: State<'_, AppState>, path: String) -> Result<usize, AppError> {
+    log::info!("导入转录记录: {}", path);
+    
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::Io(format!("读取导入文件失败: {}", e)))?;
+    
+    let storage = state.storage.lock()
+        .map_err(|_| AppError::LockPoisoned)?;
+    
+    let storage = storage.as_ref()
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    storage.import_transcripts(&json)
+}
+
+/// 获取当前使用的数据目录
+#[tauri::command]
+async fn get_data_dir(state: State<'_, AppState>) -> Result<String, AppError> {
     let storage = state.storage.lock()
-        .map_err(|_| "无法获取状态锁")?;
+        .map_err(|_| AppError::LockPoisoned)?;
     
     let storage = storage.as_ref()
-        .ok_or("存储未初始化")?;
+        .ok_or(AppError::StorageNotInitialized)?;
+    
+    Ok(storage.data_dir().to_string_lossy().to_string())
+}
+
+/// 在访达中打开数据目录
+#[tauri::command]
+async fn open_data_dir(state: State<'_, AppState>) -> Result<(), AppError> {
+    let data_dir = {
+        let storage = state.storage.lock()
+            .map_err(|_| AppError::LockPoisoned)?;
+        
+        let storage = storage.as_ref()
+            .ok_or(AppError::StorageNotInitialized)?;
+        
+        storage.data_dir().to_path_buf()
+    };
+    
+    std::process::Command::new("open")
+        .arg(&data_dir)
+        .spawn()
+        .map_err(|e| AppError::Io(format!("无法打开数据目录: {}", e)))?;
+    
+    Ok(())
+}
+
+/// 设置全局快捷键
+#[tauri::command]
+async fn set_global_shortcut(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    accelerator: String,
+) -> Result<(), AppError> {
+    log::info!("设置全局快捷键: {}", accelerator);
     
-    storage.export_transcript(id, &format)
+    let mut session = state.session();
+    
+    // 先校验新快捷键，避免注销旧快捷键后无法恢复
+    let new_shortcut: Shortcut = accelerator.parse()
+        .map_err(|e| AppError::InvalidInput(format!("无效的快捷键 {}: {}", accelerator, e)))?;
+    
+    if let Ok(old_shortcut) = session.global_shortcut.parse::<Shortcut>() {
+        if old_shortcut == new_shortcut {
+            return Ok(());
+        }
+        let _ = app.global_shortcut().unregister(old_shortcut);
+    }
+    
+    if let Err(e) = register_global_shortcut(&app, &accelerator) {
+        // 注册失败时恢复旧快捷键
+        let _ = register_global_shortcut(&app, &session.global_shortcut);
+        return Err(e);
+    }
+    session.global_shortcut = accelerator;
+    drop(session);
+    
+    persist_settings(&state)
+}
+
+/// 获取全局快捷键
+#[tauri::command]
+async fn get_global_shortcut(state: State<'_, AppState>) -> Result<String, AppError> {
+    Ok(state.session().global_shortcut.clone())
 }
 
 /// 模拟接收转录文本（用于演示和测试）
 #[tauri::command]
-async fn simulate_transcription(text: String) -> Result<(), String> {
+async fn simulate_transcription(text: String) -> Result<(), AppError> {
+    simulate_line(&text)
+}
+
+/// 将一行文本作为最终识别结果送入当前会话
+fn simulate_line(text: &str) -> Result<(), AppError> {
     if !AudioBridge::is_capturing() {
-        return Err("转录未在进行中".to_string());
+        return Err(AppError::NotCapturing);
     }
+    if AudioBridge::is_paused() {
+        return Err(AppError::InvalidInput("转录已暂停".to_string()));
+    }
+    
+    AudioBridge::simulate_text(text);
     
-    AudioBridge::simulate_text(&text);
+    Ok(())
+}
+
+// ============= 日志 =============
+
+/// 运行时调整日志级别（trace / debug / info / warn / error / off），重启后恢复默认级别
+/// 复现问题时可临时开启 debug / trace，无需重新编译
+#[tauri::command]
+async fn set_log_level(level: String) -> Result<(), AppError> {
+    let filter: log::LevelFilter = level.trim().parse().map_err(|_| {
+        AppError::InvalidInput(format!("不支持的日志级别: {}（可选 trace / debug / info / warn / error / off）", level))
+    })?;
     
+    log::set_max_level(filter);
+    log::info!("日志级别已设置为 {}", filter);
     Ok(())
 }
 
+/// 获取当前日志级别
+#[tauri::command]
+async fn get_log_level() -> Result<String, AppError> {
+    Ok(log::max_level().to_string().to_lowercase())
+}
+
+/// 获取日志文件路径，便于附加到问题报告
+#[tauri::command]
+async fn get_log_file_path(app: AppHandle) -> Result<String, AppError> {
+    let dir = app.path().app_log_dir()
+        .map_err(|e| AppError::Io(format!("无法获取日志目录: {}", e)))?;
+    Ok(dir.join(format!("{}.log", LOG_FILE_NAME)).to_string_lossy().into_owned())
+}
+
 // ============= 应用入口 =============
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
-            // 初始化日志
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+            // 初始化日志：插件本身不过滤，实际级别由 log::set_max_level 控制，可通过 set_log_level 调整
+            // 默认调试构建为 Info，发布构建关闭
+            app.handle().plugin(
+                tauri_plugin_log::Builder::default()
+                    .targets([
+                        Target::new(TargetKind::Stdout),
+                        Target::new(TargetKind::LogDir { file_name: Some(LOG_FILE_NAME.to_string()) }),
+                    ])
+                    .level(log::LevelFilter::Trace)
+                    .build(),
+            )?;
+            log::set_max_level(if cfg!(debug_assertions) {
+                log::LevelFilter::Info
+            } else {
+                log::LevelFilter::Off
+            });
             
             // 初始化音频桥接
-            AudioBridge::init();
+            AudioBridge::init(app.handle().clone());
             
             // 初始化存储
-            let storage = StorageManager::new(app.handle())
+            let mut storage = StorageManager::new(app.handle())
                 .map_err(|e| format!("初始化存储失败: {}", e))?;
             
+            let settings = storage.load_settings();
+            autosave::init(storage.data_dir());
+            if let Err(e) = storage.set_max_history(settings.max_history_records) {
+                log::warn!("归档旧记录失败: {}", e);
+            }
+            
             let state = app.state::<AppState>();
             let mut storage_lock = state.storage.lock()
                 .map_err(|_| "无法获取状态锁".to_string())?;
             *storage_lock = Some(storage);
+            drop(storage_lock);
+            
+            // 应用已保存的设置
+            apply_settings(&settings);
+            let global_shortcut = settings.global_shortcut.clone();
+            let tray_enabled = settings.tray_enabled;
+            *state.session() = SessionState::new(settings);
+            
+            // 菜单栏图标：创建失败不影响启动，状态随捕获事件更新
+            if tray_enabled {
+                if let Err(e) = tray::create(app.handle()) {
+                    log::error!("创建托盘图标失败: {}", e);
+                }
+            }
+            let handle = app.handle().clone();
+            app.listen(CAPTURE_STARTED_EVENT, move |_event| tray::update(&handle, true));
+            let handle = app.handle().clone();
+            app.listen(CAPTURE_STOPPED_EVENT, move |_event| tray::update(&handle, false));
+            let handle = app.handle().clone();
+            app.listen(CAPTURE_CANCELLED_EVENT, move |_event| tray::update(&handle, false));
+            
+            // 识别到停止关键词时执行停止流程
+            let handle = app.handle().clone();
+            app.listen(audio_bridge::STOP_KEYWORD_EVENT, move |_event| stop_by_keyword(&handle));
+            
+            // 注册全局快捷键，失败时仅记录日志，不影响启动
+            app.handle().plugin(
+                tauri_plugin_global_shortcut::Builder::new()
+                    .with_handler(|app, _shortcut, event| {
+                        if event.state() == ShortcutState::Pressed {
+                            let state = app.state::<AppState>();
+                            if let Err(e) = toggle_capture(app, &state) {
+                                log::error!("快捷键切换转录失败: {}", e);
+                            }
+                        }
+                    })
+                    .build(),
+            )?;
+            if let Err(e) = register_global_shortcut(app.handle(), &global_shortcut) {
+                log::error!("{}", e);
+                state.session().global_shortcut = AppSettings::default().global_shortcut;
+            }
             
             log::info!("Mac Voice to Text 应用已启动");
             
             // 打印模式信息
-            #[cfg(feature = "swift_audio")]
+            #[cfg(native_audio)]
             log::info!("运行模式: Swift 音频捕获");
-            #[cfg(not(feature = "swift_audio"))]
-            log::info!("运行模式: 模拟模式（Swift 库未链接）");
+            #[cfg(not(native_audio))]
+            {
+                if cfg!(feature = "swift_audio") {
+                    log::warn!("运行模式: 降级模式（Swift 库未链接，转录不可用）");
+                } else {
+                    log::info!("运行模式: 模拟模式（Swift 库未链接）");
+                }
+            }
             
             Ok(())
         })
@@ -311,15 +2420,300 @@ pub fn run() {
             set_language,
             get_language,
             get_supported_languages,
+            get_available_languages,
+            language_display_name,
+            set_audio_source,
+            set_task_hint,
+            get_audio_source,
+            list_audio_devices,
+            list_capture_targets,
+            set_capture_target,
+            get_capture_target,
+            set_audio_device,
+            get_audio_device,
+            prewarm_recognizer,
             start_transcription,
+            start_transcription_append,
             stop_transcription,
+            cancel_transcription,
+            begin_push_to_talk,
+            end_push_to_talk,
+            is_push_to_talk_active,
+            transcribe_file,
+            pause_transcription,
+            resume_transcription,
             get_transcription_status,
+            get_current_session_id,
+            get_transcription_length,
+            get_confirmed_segment_count,
+            get_transcription_segments,
+            get_audio_level,
+            set_buffer_limit,
+            set_segment_limit,
+            export_session_subtitles,
+            set_partial_update_interval,
+            get_last_error,
+            get_error_history,
+            get_bridge_metrics,
+            set_log_level,
+            get_log_level,
+            get_log_file_path,
+            set_custom_vocabulary,
+            get_custom_vocabulary,
+            set_on_device_recognition,
+            get_on_device_recognition,
+            get_recognition_mode,
+            set_segment_separator,
+            set_stop_keyword,
+            set_post_processing,
+            set_audio_passthrough,
+            set_silence_split,
+            set_tray_enabled,
+            set_notifications_enabled,
+            get_notifications_enabled,
+            set_notification_threshold,
+            set_autosave_interval,
+            recover_last_session,
+            set_confidence_threshold,
+            set_skip_duplicate_saves,
+            set_export_template,
+            is_native_available,
+            get_app_status,
+            get_capture_status,
+            get_recognition_status,
+            set_live_output_file,
+            set_auto_save,
+            get_auto_save,
             save_transcript,
             get_transcript_history,
+            get_transcript_history_page,
+            get_transcript_chunk,
+            search_transcripts,
+            search_transcripts_ranked,
+            get_transcripts_in_range,
+            get_statistics,
             delete_transcript,
+            delete_transcripts,
+            clear_all_transcripts,
+            set_max_history,
+            set_max_session_duration,
+            load_archive,
+            list_trash,
+            restore_transcript,
+            empty_trash,
+            merge_transcripts,
+            split_transcript,
+            update_transcript,
+            toggle_favorite,
+            set_rating,
+            set_note,
+            add_tag,
+            remove_tag,
+            get_transcripts_by_tag,
             export_transcript,
+            export_transcript_path,
+            export_combined,
+            export_all_transcripts,
+            backup_data,
+            restore_data,
+            copy_transcription_to_clipboard,
+            copy_transcript_to_clipboard,
+            import_transcripts,
+            get_data_dir,
+            open_data_dir,
+            set_global_shortcut,
+            get_global_shortcut,
             simulate_transcription,
         ])
-        .run(tauri::generate_context!())
-        .expect("运行 Tauri 应用时出错");
+        .on_window_event(|window, event| {
+            // 托盘开启时关闭主窗口只是隐藏，转录继续进行，可从托盘重新打开
+            // 托盘关闭时窗口正常关闭，应用随之退出，由 ExitRequested / Exit 停止转录
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" && window.state::<AppState>().session().tray_enabled {
+                    api.prevent_close();
+                    if let Err(e) = window.hide() {
+                        log::warn!("隐藏主窗口失败: {}", e);
+                    }
+                }
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("构建 Tauri 应用时出错")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { .. } | tauri::RunEvent::Exit = event {
+                shutdown_capture(&app.state::<AppState>());
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::serial_test;
+    use std::time::{Duration, Instant};
+    
+    /// 使用内存数据库的应用状态，默认设置（开启自动保存）
+    fn test_state() -> (tempfile::TempDir, AppState) {
+        let dir = tempfile::tempdir().unwrap();
+        let state = AppState::default();
+        *state.storage.lock().unwrap() = Some(StorageManager::open_in_memory(dir.path()).unwrap());
+        (dir, state)
+    }
+    
+    fn start(state: &AppState, append: Option<AppendSource>) {
+        start_capture(&mut state.session(), append).expect("开始转录失败");
+    }
+    
+    /// 将会话开始时间提前，停止时得到确定的时长
+    fn backdate(state: &AppState, seconds: u64) {
+        state.session().capture_start_time = Some(Instant::now() - Duration::from_secs(seconds));
+    }
+    
+    fn stop(state: &AppState) -> Result<TranscriptionStatus, AppError> {
+        stop_capture(state, &mut state.session())
+    }
+    
+    fn stored(state: &AppState, id: i64) -> TranscriptRecord {
+        state.storage.lock().unwrap().as_ref().unwrap().get_transcript(id).unwrap()
+    }
+    
+    #[test]
+    fn simulated_session_is_saved_with_content_and_duration() {
+        let _serial = serial_test();
+        let (_dir, state) = test_state();
+        
+        start(&state, None);
+        let lines = ["第一句话。", "第二句话。", "第三句话。"];
+        for line in lines {
+            simulate_line(line).unwrap();
+        }
+        backdate(&state, 3);
+        let status = stop(&state).unwrap();
+        
+        assert!(!status.is_capturing);
+        assert!(!AudioBridge::is_capturing());
+        let record = stored(&state, status.saved_record_id.expect("应自动保存"));
+        assert_eq!(record.content, status.full_text);
+        assert_eq!(record.duration_seconds, 3);
+        assert_eq!(record.language, language::DEFAULT_LANGUAGE);
+        assert_eq!(record.session_id, status.session_id);
+        
+        // 各行按顺序出现
+        let positions: Vec<usize> = lines.iter()
+            .map(|line| record.content.find(line).expect("缺少模拟的文本"))
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+    
+    #[test]
+    fn simulate_requires_active_capture() {
+        let _serial = serial_test();
+        let (_dir, state) = test_state();
+        
+        assert!(matches!(simulate_line("没有开始"), Err(AppError::NotCapturing)));
+        
+        start(&state, None);
+        let status = stop(&state).unwrap();
+        assert_eq!(status.saved_record_id, None, "空文本不保存");
+    }
+    
+    #[test]
+    fn second_stop_in_append_mode_does_not_save_again() {
+        let _serial = serial_test();
+        let (_dir, state) = test_state();
+        let original = state.storage.lock().unwrap().as_ref().unwrap()
+            .save_transcript("已有内容。", language::DEFAULT_LANGUAGE, 5, &[], None, None)
+            .unwrap();
+        
+        start(&state, Some(AppendSource {
+            target: Some(original.id),
+            content: original.content.clone(),
+        }));
+        simulate_line("续写内容。").unwrap();
+        let status = stop(&state).unwrap();
+        assert_eq!(status.saved_record_id, Some(original.id));
+        
+        // 自动停止后再次停止（托盘、快捷键）：不再通知和保存
+        assert!(matches!(stop(&state), Err(AppError::NotCapturing)));
+        
+        let storage = state.storage.lock().unwrap();
+        let storage = storage.as_ref().unwrap();
+        assert_eq!(storage.count_transcripts().unwrap(), 1);
+        let record = storage.get_transcript(original.id).unwrap();
+        assert!(record.content.starts_with("已有内容。"));
+        assert!(record.content.ends_with("续写内容。"));
+    }
+    
+    #[test]
+    fn status_reads_during_capture_are_consistent() {
+        const READERS: usize = 4;
+        const SESSIONS: usize = 20;
+        
+        let _serial = serial_test();
+        let (_dir, state) = test_state();
+        let done = std::sync::atomic::AtomicBool::new(false);
+        
+        std::thread::scope(|scope| {
+            for _ in 0..READERS {
+                scope.spawn(|| {
+                    // 同一会话内已确认文本只增不减
+                    let mut last: Option<(String, usize)> = None;
+                    while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                        let status = transcription_status(&state.session());
+                        assert!(status.duration_seconds >= 0);
+                        if !status.is_capturing {
+                            continue;
+                        }
+                        
+                        let session_id = status.session_id.clone().expect("捕获中缺少会话 ID");
+                        assert!(status.started_at.is_some(), "捕获中缺少开始时间");
+                        assert!(!status.is_paused);
+                        let len = status.full_text.len();
+                        if let Some((last_id, last_len)) = &last {
+                            if *last_id == session_id {
+                                assert!(len >= *last_len, "同一会话的文本变短: {} -> {}", last_len, len);
+                            }
+                        }
+                        last = Some((session_id, len));
+                    }
+                });
+            }
+            
+            for session in 0..SESSIONS {
+                start(&state, None);
+                for line in 0..5 {
+                    simulate_line(&format!("会话{}第{}行。", session, line)).unwrap();
+                }
+                stop(&state).unwrap();
+            }
+            done.store(true, std::sync::atomic::Ordering::SeqCst);
+        });
+        
+        let storage = state.storage.lock().unwrap();
+        assert_eq!(storage.as_ref().unwrap().count_transcripts().unwrap(), SESSIONS as i64);
+    }
+    
+    #[test]
+    fn failed_save_keeps_autosave_for_recovery() {
+        let _serial = serial_test();
+        let (dir, state) = test_state();
+        autosave::init(dir.path());
+        let original = state.storage.lock().unwrap().as_ref().unwrap()
+            .save_transcript("已有内容。", language::DEFAULT_LANGUAGE, 5, &[], None, None)
+            .unwrap();
+        
+        start(&state, Some(AppendSource {
+            target: Some(original.id),
+            content: original.content.clone(),
+        }));
+        simulate_line("续写内容。").unwrap();
+        // 续写目标在会话中被删除，停止时保存失败
+        state.storage.lock().unwrap().as_ref().unwrap().delete_transcript(original.id).unwrap();
+        assert!(matches!(stop(&state), Err(AppError::RecordNotFound(id)) if id == original.id));
+        
+        // 下次启动仍可恢复本次会话的文本
+        autosave::init(dir.path());
+        let recovered = autosave::take_recovered().expect("自动保存文件应保留");
+        assert!(recovered.contains("续写内容。"));
+    }
 }
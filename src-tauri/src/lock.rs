@@ -0,0 +1,23 @@
+// lock.rs
+// 锁中毒恢复
+// 持锁线程 panic 后锁会中毒，对可安全继续使用的数据取回内部值，避免整个会话不可用
+
+use std::sync::{Mutex, MutexGuard};
+
+/// 获取锁，锁已中毒时记录警告并恢复内部数据
+/// 仅用于转录缓冲区等非关键数据；存储锁中毒仍应返回错误
+pub fn lock_recover<'a, T>(mutex: &'a Mutex<T>, name: &str) -> MutexGuard<'a, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        log::warn!("{} 锁已中毒，恢复后继续使用", name);
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+/// 测试共用的串行锁：音频桥的捕获状态和缓冲区是进程级静态变量，
+/// 读写这些状态的测试持有该锁，避免并行执行的测试互相干扰
+#[cfg(test)]
+pub fn serial_test() -> MutexGuard<'static, ()> {
+    static SERIAL: Mutex<()> = Mutex::new(());
+    lock_recover(&SERIAL, "SERIAL")
+}
@@ -0,0 +1,44 @@
+// notify.rs
+// 系统通知
+// 转录结束（正常停止或因错误中断）时提醒用户，过短的会话不通知
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+/// 默认的最短通知会话时长（秒）
+pub const DEFAULT_MIN_SESSION_SECONDS: u64 = 60;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static MIN_SESSION_SECONDS: AtomicU64 = AtomicU64::new(DEFAULT_MIN_SESSION_SECONDS);
+
+/// 设置是否发送通知
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// 设置发送通知的最短会话时长（秒）
+pub fn set_min_session_seconds(seconds: u64) {
+    MIN_SESSION_SECONDS.store(seconds, Ordering::Relaxed);
+}
+
+/// 会话结束时发送通知，附带时长和字数
+pub fn notify_session_end(app: &AppHandle, title: &str, duration_seconds: i32, word_count: i64) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let duration = duration_seconds.max(0) as u64;
+    if duration < MIN_SESSION_SECONDS.load(Ordering::Relaxed) {
+        return;
+    }
+    
+    let body = format!(
+        "时长 {:02}:{:02}，共 {} 字",
+        duration / 60,
+        duration % 60,
+        word_count
+    );
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("发送系统通知失败: {}", e);
+    }
+}
@@ -0,0 +1,50 @@
+// postprocess.rs
+// 识别结果后处理
+// 端侧识别常省略标点、英文句首小写，确认片段进入缓冲区前做简单修正
+
+use crate::language;
+
+/// 句末标点（中英文）
+const SENTENCE_END: &[char] = &['.', '!', '?', '。', '！', '？', '…'];
+
+/// 片段末尾已有的标点，有则不再补句号
+const TRAILING_PUNCTUATION: &[char] = &[
+    '.', '!', '?', ',', ';', ':', '。', '！', '？', '，', '；', '：', '…', '、', '"', '\'', '”', '」', ')', '）',
+];
+
+/// 处理单个确认片段：拉丁字母句首大写，片段末尾缺少标点时补句号
+pub fn process_segment(text: &str, language: &str) -> String {
+    let text = text.trim();
+    if text.is_empty() {
+        return String::new();
+    }
+    
+    let cjk = language::is_cjk(language);
+    let mut output = if cjk { text.to_string() } else { capitalize_sentences(text) };
+    
+    if !output.ends_with(TRAILING_PUNCTUATION) {
+        output.push(if cjk { '。' } else { '.' });
+    }
+    output
+}
+
+/// 将片段开头和句末标点之后的第一个字母大写
+fn capitalize_sentences(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut at_sentence_start = true;
+    
+    for c in text.chars() {
+        if at_sentence_start && c.is_alphabetic() {
+            output.extend(c.to_uppercase());
+            at_sentence_start = false;
+            continue;
+        }
+        if SENTENCE_END.contains(&c) {
+            at_sentence_start = true;
+        } else if !c.is_whitespace() {
+            at_sentence_start = false;
+        }
+        output.push(c);
+    }
+    output
+}
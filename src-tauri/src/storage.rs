@@ -2,135 +2,2298 @@
 // 转录数据存储模块
 // 使用 SQLite 持久化存储转录记录
 
+use crate::atomic_file::write_atomic;
+use crate::audio_bridge::{AudioSource, TaskHint, TranscriptSegment};
+use crate::error::AppError;
+use crate::language;
+use crate::lock::lock_recover;
+use docx_rs::{Docx, Paragraph, Run, RunFonts};
+use printpdf::{IndirectFontRef, Mm, PdfDocument, PdfDocumentReference};
+use rusqlite::{params, Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
 use tauri::{AppHandle, Manager};
 
+/// 默认低置信度阈值，md_annotated 导出中低于该值的片段会被标记
+pub const DEFAULT_CONFIDENCE_THRESHOLD: f32 = 0.5;
+
+// 低置信度阈值（f32 的位表示）
+static CONFIDENCE_THRESHOLD: AtomicU32 = AtomicU32::new(0x3F00_0000); // 0.5
+
+/// 设置低置信度阈值（0.0 ~ 1.0）
+pub fn set_confidence_threshold(threshold: f32) {
+    CONFIDENCE_THRESHOLD.store(threshold.to_bits(), Ordering::Relaxed);
+}
+
+/// 重复保存判定窗口（秒）：与上一条记录内容、语言相同且创建时间相差不超过该值时视为重复
+const DUPLICATE_WINDOW_SECONDS: i64 = 5;
+
+// 是否跳过重复保存
+static SKIP_DUPLICATES: AtomicBool = AtomicBool::new(true);
+
+/// 设置是否跳过重复保存
+pub fn set_skip_duplicates(enabled: bool) {
+    SKIP_DUPLICATES.store(enabled, Ordering::Relaxed);
+}
+
+/// 支持自定义模板的导出格式
+pub const TEMPLATE_FORMATS: &[&str] = &["txt", "md"];
+
+/// 模板中必须包含的占位符
+pub const TEMPLATE_CONTENT_PLACEHOLDER: &str = "{content}";
+
+// 自定义导出模板（格式 -> 模板），未设置的格式使用内置布局
+static EXPORT_TEMPLATES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// 设置全部自定义导出模板（启动时从设置载入）
+pub fn set_export_templates(templates: BTreeMap<String, String>) {
+    *lock_recover(&EXPORT_TEMPLATES, "EXPORT_TEMPLATES") = templates;
+}
+
+/// 校验并设置单个格式的导出模板，模板为空时恢复内置布局
+/// 模板支持 {created_at}、{language}、{duration}、{id}、{content} 占位符，且必须包含 {content}
+pub fn set_export_template(format: &str, template: &str) -> Result<(), AppError> {
+    if !TEMPLATE_FORMATS.contains(&format) {
+        return Err(AppError::InvalidInput(format!(
+            "格式 {} 不支持自定义模板（支持 {}）",
+            format,
+            TEMPLATE_FORMATS.join(" / ")
+        )));
+    }
+    
+    let mut templates = lock_recover(&EXPORT_TEMPLATES, "EXPORT_TEMPLATES");
+    if template.trim().is_empty() {
+        templates.remove(format);
+        return Ok(());
+    }
+    if !template.contains(TEMPLATE_CONTENT_PLACEHOLDER) {
+        return Err(AppError::InvalidInput(format!("模板必须包含 {} 占位符", TEMPLATE_CONTENT_PLACEHOLDER)));
+    }
+    templates.insert(format.to_string(), template.to_string());
+    Ok(())
+}
+
+/// 按模板渲染记录，{content} 最后替换，避免正文中的花括号文本被当作占位符
+fn render_template(template: &str, record: &TranscriptRecord) -> String {
+    template
+        .replace("{created_at}", &record.created_at_local)
+        .replace("{language}", &record.language)
+        .replace("{duration}", &record.duration_seconds.to_string())
+        .replace("{id}", &record.id.to_string())
+        .replace(TEMPLATE_CONTENT_PLACEHOLDER, &record.content)
+}
+
+/// 旧版时间戳及本地显示使用的格式
+const LOCAL_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 格式化为 RFC 3339 UTC 时间戳（精确到秒，以 Z 结尾），长度固定，可直接按字符串排序
+pub fn utc_timestamp(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// 当前时间的 UTC 时间戳
+pub fn now_timestamp() -> String {
+    utc_timestamp(chrono::Utc::now())
+}
+
+/// 将存储的时间戳转换为本地时间显示字符串（YYYY-MM-DD HH:MM:SS），无法解析时原样返回
+pub fn local_display(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&chrono::Local).format(LOCAL_TIME_FORMAT).to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// 本地日期零点对应的 UTC 时间戳
+fn local_midnight_utc(date: chrono::NaiveDate) -> Option<String> {
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .map(|t| utc_timestamp(t.with_timezone(&chrono::Utc)))
+}
+
+/// 将旧版无时区的本地时间字符串按本地时区解释，转换为 UTC 时间戳
+fn legacy_local_to_utc(local: &str) -> Option<String> {
+    chrono::NaiveDateTime::parse_from_str(local.trim(), LOCAL_TIME_FORMAT).ok()?
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .map(|t| utc_timestamp(t.with_timezone(&chrono::Utc)))
+}
+
+/// 查询转录记录时使用的列
+const RECORD_COLUMNS: &str = "id, content, language, created_at, duration_seconds, segments, tags, is_favorite, rating, note, session_id";
+
+/// 评分的有效范围
+pub const RATING_RANGE: std::ops::RangeInclusive<u8> = 1..=5;
+
+/// 数据库结构迁移脚本，按顺序执行，PRAGMA user_version 记录已执行的数量
+const SCHEMA_MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS transcripts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        content TEXT NOT NULL,
+        language TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        duration_seconds INTEGER NOT NULL DEFAULT 0
+    );",
+    "ALTER TABLE transcripts ADD COLUMN segments TEXT NOT NULL DEFAULT '[]';",
+    "ALTER TABLE transcripts ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';",
+    "ALTER TABLE transcripts ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0;",
+    "CREATE TABLE IF NOT EXISTS deleted_transcripts (
+        id INTEGER PRIMARY KEY,
+        content TEXT NOT NULL,
+        language TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        duration_seconds INTEGER NOT NULL DEFAULT 0,
+        segments TEXT NOT NULL DEFAULT '[]',
+        tags TEXT NOT NULL DEFAULT '[]',
+        is_favorite INTEGER NOT NULL DEFAULT 0,
+        deleted_at TEXT NOT NULL
+    );",
+    "ALTER TABLE transcripts ADD COLUMN rating INTEGER;
+     ALTER TABLE transcripts ADD COLUMN note TEXT;
+     ALTER TABLE deleted_transcripts ADD COLUMN rating INTEGER;
+     ALTER TABLE deleted_transcripts ADD COLUMN note TEXT;",
+    "ALTER TABLE transcripts ADD COLUMN session_id TEXT;
+     ALTER TABLE deleted_transcripts ADD COLUMN session_id TEXT;",
+];
+
+/// 回收站记录的保留天数，启动时自动清理更早删除的记录
+const TRASH_RETENTION_DAYS: i64 = 30;
+
+/// 覆盖数据目录的环境变量（便携模式 / 外置磁盘）
+const DATA_DIR_ENV: &str = "MAC_VTT_DATA_DIR";
+
+/// 分页查询的单页最大记录数
+const MAX_PAGE_LIMIT: i64 = 200;
+
+/// 分块读取内容时单块的最大字节数
+const MAX_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// 分块读取内容时单块的最小字节数（一个 UTF-8 字符最长 4 字节，保证每块至少前进一个字符）
+const MIN_CHUNK_BYTES: usize = 4;
+
 /// 转录记录结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptRecord {
     pub id: i64,
     pub content: String,
     pub language: String,
+    /// 创建时间，RFC 3339 UTC 格式
     pub created_at: String,
+    /// 创建时间的本地显示字符串（YYYY-MM-DD HH:MM:SS），由 created_at 计算，不入库
+    #[serde(default)]
+    pub created_at_local: String,
     pub duration_seconds: i32,
+    /// 带时间信息的片段，旧记录可能为空
+    #[serde(default)]
+    pub segments: Vec<TranscriptSegment>,
+    /// 标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 是否已收藏
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// 用户评分（1 ~ 5）
+    #[serde(default)]
+    pub rating: Option<u8>,
+    /// 用户备注
+    #[serde(default)]
+    pub note: Option<String>,
+    /// 生成该记录的转录会话 ID，旧记录为空
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+/// 回收站中的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedTranscript {
+    #[serde(flatten)]
+    pub record: TranscriptRecord,
+    pub deleted_at: String,
+}
+
+impl TranscriptRecord {
+    /// 从查询结果行构造记录
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let segments: String = row.get("segments")?;
+        let tags: String = row.get("tags")?;
+        let created_at: String = row.get("created_at")?;
+        Ok(Self {
+            id: row.get("id")?,
+            content: row.get("content")?,
+            language: row.get("language")?,
+            created_at_local: local_display(&created_at),
+            created_at,
+            duration_seconds: row.get("duration_seconds")?,
+            segments: serde_json::from_str(&segments).unwrap_or_default(),
+            tags: serde_json::from_str(&tags).unwrap_or_default(),
+            is_favorite: row.get("is_favorite")?,
+            rating: row.get("rating")?,
+            note: row.get("note")?,
+            session_id: row.get("session_id")?,
+        })
+    }
+    
+    /// 还原后处理前的原始识别文本：片段文本换回 raw_text，内容中对应的文本依次替换
+    fn to_raw(&self) -> Self {
+        let mut record = self.clone();
+        let mut content = String::with_capacity(self.content.len());
+        let mut rest = self.content.as_str();
+        
+        for seg in &mut record.segments {
+            let Some(raw) = seg.raw_text.take() else {
+                continue;
+            };
+            if let Some(pos) = rest.find(&seg.text) {
+                content.push_str(&rest[..pos]);
+                content.push_str(&raw);
+                rest = &rest[pos + seg.text.len()..];
+            }
+            seg.text = raw;
+        }
+        content.push_str(rest);
+        record.content = content;
+        record
+    }
+    
+    /// 获取用于字幕导出的带时间片段
+    /// 没有片段数据时按行拆分内容，并将总时长平均分配到每一行
+    fn timed_segments(&self) -> Vec<TranscriptSegment> {
+        if !self.segments.is_empty() {
+            return self.segments.clone();
+        }
+        
+        let lines: Vec<&str> = self.content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        if lines.is_empty() {
+            return Vec::new();
+        }
+        
+        let total_ms = self.duration_seconds.max(0) as u64 * 1000;
+        let step_ms = total_ms / lines.len() as u64;
+        lines.iter()
+            .enumerate()
+            .map(|(i, line)| TranscriptSegment {
+                text: line.to_string(),
+                start_ms: step_ms * i as u64,
+                end_ms: step_ms * (i as u64 + 1),
+                confidence: 0.0,
+                raw_text: None,
+            })
+            .collect()
+    }
+}
+
+/// 历史记录排序子句
+fn history_order(favorites_first: bool) -> &'static str {
+    if favorites_first {
+        "is_favorite DESC, id DESC"
+    } else {
+        "id DESC"
+    }
+}
+
+/// 搜索结果预览中匹配位置前后各保留的字符数
+const SNIPPET_CONTEXT_CHARS: usize = 30;
+
+/// 逐字符转为小写，每个字符只取小写形式的第一个字符，保持与原文字符一一对应
+fn fold_chars(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// 查找 needle 在 haystack 中所有不重叠的出现位置（字符下标）
+fn find_matches(haystack: &[char], needle: &[char]) -> Vec<usize> {
+    let mut matches = Vec::new();
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return matches;
+    }
+    
+    let mut i = 0;
+    while i + needle.len() <= haystack.len() {
+        if haystack[i..i + needle.len()] == *needle {
+            matches.push(i);
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    matches
+}
+
+/// 相关度评分：出现次数越多、首次出现的位置越靠前分数越高
+/// 次数按对数增长，避免长文本单靠重复次数压过靠前的匹配；位置因子在 0.5 ~ 1 之间
+fn relevance_score(content_chars: usize, matches: &[usize]) -> f32 {
+    let Some(&first) = matches.first() else {
+        return 0.0;
+    };
+    let occurrence = 1.0 + (matches.len() as f32).ln();
+    let position = 1.0 - first as f32 / content_chars.max(1) as f32;
+    occurrence * (0.5 + 0.5 * position)
+}
+
+/// 首个匹配位置前后的上下文预览（不区分大小写），换行替换为空格，截断处加省略号
+/// 没有匹配时返回 None
+pub fn search_snippet(content: &str, query: &str) -> Option<String> {
+    let chars: Vec<char> = content.chars().collect();
+    let needle = fold_chars(query.trim());
+    let first = *find_matches(&fold_chars(content), &needle).first()?;
+    
+    let start = first.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let end = (first + needle.len() + SNIPPET_CONTEXT_CHARS).min(chars.len());
+    let body: String = chars[start..end].iter()
+        .map(|&c| if c == '\n' || c == '\r' { ' ' } else { c })
+        .collect();
+    
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    Some(format!("{}{}{}", prefix, body.trim(), suffix))
+}
+
+/// 构建 docx 文档：标题行包含时间和语言，正文按换行拆分为段落
+fn render_docx(record: &TranscriptRecord) -> Result<Vec<u8>, AppError> {
+    // 指定东亚字体，保证中日韩文本在 Word / Pages 中正常显示
+    let fonts = || RunFonts::new().east_asia("PingFang SC");
+    
+    let title = format!("转录记录 - {} ({})", record.created_at_local, record.language);
+    let mut docx = Docx::new().add_paragraph(
+        Paragraph::new().add_run(Run::new().add_text(title).bold().size(32).fonts(fonts())),
+    );
+    for line in record.content.lines() {
+        docx = docx.add_paragraph(
+            Paragraph::new().add_run(Run::new().add_text(line).fonts(fonts())),
+        );
+    }
+    
+    let mut buffer = Vec::new();
+    docx.build()
+        .pack(std::io::Cursor::new(&mut buffer))
+        .map_err(|e| AppError::Io(format!("生成 docx 失败: {}", e)))?;
+    Ok(buffer)
+}
+
+/// 可用于 PDF 导出的中日韩字体（需为单一 TTF/OTF，printpdf 不支持 TTC）
+const PDF_CJK_FONT_CANDIDATES: &[&str] = &[
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/System/Library/Fonts/Supplemental/AppleGothic.ttf",
+];
+
+/// PDF 页面尺寸（A4）与排版参数
+const PDF_PAGE_WIDTH_MM: f32 = 210.0;
+const PDF_PAGE_HEIGHT_MM: f32 = 297.0;
+const PDF_MARGIN_MM: f32 = 20.0;
+const PDF_FONT_SIZE: f32 = 11.0;
+const PDF_LINE_HEIGHT_MM: f32 = 6.0;
+/// 每行最大宽度单位：全角字符计 2，半角字符计 1
+const PDF_LINE_UNITS: usize = 86;
+
+/// 按显示宽度折行，全角字符占两个单位
+fn wrap_line(line: &str, max_units: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut units = 0;
+    
+    for c in line.chars() {
+        let width = if c.is_ascii() { 1 } else { 2 };
+        if units + width > max_units && !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+            units = 0;
+        }
+        current.push(c);
+        units += width;
+    }
+    lines.push(current);
+    lines
+}
+
+/// 加载 PDF 字体：优先使用系统中的中日韩字体，找不到时退回内置 Helvetica
+fn load_pdf_font(doc: &PdfDocumentReference) -> Result<IndirectFontRef, AppError> {
+    for path in PDF_CJK_FONT_CANDIDATES {
+        if let Ok(file) = fs::File::open(path) {
+            match doc.add_external_font(file) {
+                Ok(font) => return Ok(font),
+                Err(e) => log::warn!("加载字体 {} 失败: {}", path, e),
+            }
+        }
+    }
+    
+    log::warn!("未找到中日韩字体，PDF 中的中日韩文字可能无法显示");
+    doc.add_builtin_font(printpdf::BuiltinFont::Helvetica)
+        .map_err(|e| AppError::Io(format!("加载 PDF 字体失败: {}", e)))
+}
+
+/// 构建 PDF 文档：元数据头 + 自动折行的正文，超出页面时自动分页
+fn render_pdf(record: &TranscriptRecord) -> Result<Vec<u8>, AppError> {
+    let (doc, page, layer) = PdfDocument::new(
+        format!("转录记录 {}", record.id),
+        Mm(PDF_PAGE_WIDTH_MM),
+        Mm(PDF_PAGE_HEIGHT_MM),
+        "content",
+    );
+    let font = load_pdf_font(&doc)?;
+    
+    let mut lines = vec![
+        "转录记录".to_string(),
+        format!("时间: {}", record.created_at_local),
+        format!("语言: {}", record.language),
+        format!("时长: {} 秒", record.duration_seconds),
+        String::new(),
+    ];
+    for line in record.content.lines() {
+        lines.extend(wrap_line(line, PDF_LINE_UNITS));
+    }
+    
+    let mut current = doc.get_page(page).get_layer(layer);
+    let mut y = PDF_PAGE_HEIGHT_MM - PDF_MARGIN_MM;
+    for line in lines {
+        if y < PDF_MARGIN_MM {
+            let (page, layer) = doc.add_page(Mm(PDF_PAGE_WIDTH_MM), Mm(PDF_PAGE_HEIGHT_MM), "content");
+            current = doc.get_page(page).get_layer(layer);
+            y = PDF_PAGE_HEIGHT_MM - PDF_MARGIN_MM;
+        }
+        if !line.is_empty() {
+            current.use_text(line, PDF_FONT_SIZE, Mm(PDF_MARGIN_MM), Mm(y), &font);
+        }
+        y -= PDF_LINE_HEIGHT_MM;
+    }
+    
+    doc.save_to_bytes()
+        .map_err(|e| AppError::Io(format!("生成 PDF 失败: {}", e)))
+}
+
+/// 渲染 Markdown 正文，timestamps 为 true 时每段前加 [MM:SS] 时间戳
+fn render_md_body(record: &TranscriptRecord, timestamps: bool) -> String {
+    if !timestamps || record.segments.is_empty() {
+        return record.content.clone();
+    }
+    
+    record.segments.iter()
+        .map(|seg| {
+            let seconds = seg.start_ms / 1000;
+            format!("[{:02}:{:02}] {}", seconds / 60, seconds % 60, seg.text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 渲染带置信度标注的 Markdown 正文，低于阈值的片段标记为 ⟨文本?⟩
+/// 置信度为 0 的片段（导入或估算的时间信息）视为没有置信度数据，不做标记
+fn render_md_annotated_body(record: &TranscriptRecord, threshold: f32) -> String {
+    if record.segments.is_empty() {
+        return record.content.clone();
+    }
+    
+    record.segments.iter()
+        .map(|seg| {
+            if seg.confidence > 0.0 && seg.confidence < threshold {
+                format!("⟨{}?⟩", seg.text)
+            } else {
+                seg.text.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// 导出格式对应的文件扩展名
+fn export_extension(format: &str) -> &str {
+    match format {
+        "md_annotated" => "md",
+        _ => format,
+    }
+}
+
+/// 按格式渲染导出内容（txt / md / md_annotated / json / ndjson / srt / vtt / docx / pdf）
+/// timestamps 仅对 md 生效，未指定时有片段时间信息则默认开启
+fn render_export(record: &TranscriptRecord, format: &str, timestamps: Option<bool>) -> Result<Vec<u8>, AppError> {
+    match format {
+        "docx" => return render_docx(record),
+        "pdf" => return render_pdf(record),
+        _ => {}
+    }
+    
+    let template = lock_recover(&EXPORT_TEMPLATES, "EXPORT_TEMPLATES").get(format).cloned();
+    if let Some(template) = template {
+        return Ok(render_template(&template, record).into_bytes());
+    }
+    
+    let content = match format {
+        "md" => format!(
+            "# 转录记录\n\n- **时间**: {}\n- **语言**: {}\n- **时长**: {} 秒\n\n---\n\n{}",
+            record.created_at_local,
+            record.language,
+            record.duration_seconds,
+            render_md_body(record, timestamps.unwrap_or(!record.segments.is_empty()))
+        ),
+        "md_annotated" => {
+            let threshold = f32::from_bits(CONFIDENCE_THRESHOLD.load(Ordering::Relaxed));
+            format!(
+                "# 转录记录\n\n- **时间**: {}\n- **语言**: {}\n- **时长**: {} 秒\n- **置信度阈值**: {:.2}（⟨文本?⟩ 为低置信度片段）\n\n---\n\n{}",
+                record.created_at_local,
+                record.language,
+                record.duration_seconds,
+                threshold,
+                render_md_annotated_body(record, threshold)
+            )
+        }
+        "json" => serde_json::to_string_pretty(record)
+            .map_err(|e| AppError::Serde(format!("JSON 序列化失败: {}", e)))?,
+        "ndjson" => render_ndjson(record)?,
+        "srt" => render_srt(&record.timed_segments()),
+        "vtt" => render_vtt(&record.timed_segments()),
+        _ => record.content.clone(), // txt 格式
+    };
+    Ok(content.into_bytes())
+}
+
+/// 将记录导出到目录，返回写入的文件路径
+fn write_export(
+    record: &TranscriptRecord,
+    format: &str,
+    timestamps: Option<bool>,
+    dir: &Path,
+) -> Result<PathBuf, AppError> {
+    let stem = sanitize_filename(&format!("transcript_{}_{}", record.id, record.created_at_local));
+    let extension = sanitize_filename(export_extension(format));
+    
+    let content = render_export(record, format, timestamps)?;
+    let (file_path, mut file) = create_unique_file(dir, &stem, &extension)?;
+    file.write_all(&content)
+        .map_err(|e| AppError::Io(format!("写入导出文件失败: {}", e)))?;
+    
+    Ok(file_path)
+}
+
+/// 将多条记录渲染为一个文档（txt / md / json），每条记录带时间、语言、时长的标题
+fn render_combined(records: &[TranscriptRecord], format: &str) -> Result<String, AppError> {
+    match format {
+        "json" => serde_json::to_string_pretty(records)
+            .map_err(|e| AppError::Serde(format!("JSON 序列化失败: {}", e))),
+        "md" => {
+            let sections: Vec<String> = records.iter()
+                .map(|r| format!(
+                    "## {}\n\n- **语言**: {}\n- **时长**: {} 秒\n\n{}",
+                    r.created_at_local, r.language, r.duration_seconds, r.content
+                ))
+                .collect();
+            Ok(format!("# 转录记录汇总\n\n{}\n", sections.join("\n\n---\n\n")))
+        }
+        "txt" => {
+            let sections: Vec<String> = records.iter()
+                .map(|r| format!(
+                    "[{}] 语言: {}  时长: {} 秒\n\n{}",
+                    r.created_at_local, r.language, r.duration_seconds, r.content
+                ))
+                .collect();
+            Ok(format!("{}\n", sections.join("\n\n==========\n\n")))
+        }
+        other => Err(AppError::InvalidInput(format!("合并导出不支持的格式: {}（支持 txt / md / json）", other))),
+    }
+}
+
+/// 将文件名中的路径分隔符、控制字符和各平台的非法字符替换为下划线
+fn sanitize_filename(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() || c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect();
+    // 连续的点合并为一个，并去掉首尾的点，避免生成隐藏文件或 "." / ".." 这样的特殊名称
+    let mut collapsed = String::with_capacity(sanitized.len());
+    for c in sanitized.chars() {
+        if c == '.' && collapsed.ends_with('.') {
+            continue;
+        }
+        collapsed.push(c);
+    }
+    let trimmed = collapsed.trim_matches('.');
+    if trimmed.is_empty() {
+        "export".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// 在目录中创建新文件，同名文件已存在时依次尝试 stem-1、stem-2……，不覆盖已有文件
+fn create_unique_file(dir: &Path, stem: &str, extension: &str) -> Result<(PathBuf, fs::File), AppError> {
+    for n in 0u32.. {
+        let filename = if n == 0 {
+            format!("{}.{}", stem, extension)
+        } else {
+            format!("{}-{}.{}", stem, n, extension)
+        };
+        let path = dir.join(filename);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(AppError::Io(format!("创建导出文件失败: {}", e))),
+        }
+    }
+    unreachable!("导出文件名序号耗尽")
+}
+
+/// 创建目录并写入探测文件，确认目录可写
+fn ensure_writable_dir(dir: &Path) -> Result<(), AppError> {
+    fs::create_dir_all(dir)
+        .map_err(|e| AppError::Io(format!("无法创建目录: {}", e)))?;
+    
+    let probe = dir.join(".write_test");
+    fs::write(&probe, b"")
+        .map_err(|e| AppError::Io(format!("目录不可写: {}", e)))?;
+    let _ = fs::remove_file(&probe);
+    
+    Ok(())
+}
+
+/// 插入一条完整记录（忽略 record.id），返回新记录的 ID
+fn insert_record(conn: &Connection, record: &TranscriptRecord) -> Result<i64, AppError> {
+    let segments_json = serde_json::to_string(&record.segments)
+        .map_err(|e| AppError::Serde(format!("序列化片段数据失败: {}", e)))?;
+    let tags_json = serde_json::to_string(&record.tags)
+        .map_err(|e| AppError::Serde(format!("序列化标签失败: {}", e)))?;
+    
+    conn.execute(
+        "INSERT INTO transcripts (content, language, created_at, duration_seconds, segments, tags, is_favorite, rating, note, session_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            record.content,
+            record.language,
+            record.created_at,
+            record.duration_seconds,
+            segments_json,
+            tags_json,
+            record.is_favorite,
+            record.rating,
+            record.note,
+            record.session_id
+        ],
+    )
+    .map_err(|e| AppError::Database(format!("写入转录记录失败: {}", e)))?;
+    
+    Ok(conn.last_insert_rowid())
+}
+
+/// 格式化字幕时间戳 HH:MM:SS{sep}mmm
+fn format_cue_timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+/// 渲染 NDJSON：每行一个片段对象；没有片段时间信息时按内容行输出，时间和置信度为 null
+fn render_ndjson(record: &TranscriptRecord) -> Result<String, AppError> {
+    let lines: Vec<serde_json::Value> = if record.segments.is_empty() {
+        record.content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::json!({
+                "text": line,
+                "start_ms": null,
+                "end_ms": null,
+                "confidence": null,
+            }))
+            .collect()
+    } else {
+        record.segments.iter()
+            .map(|seg| serde_json::json!({
+                "text": seg.text,
+                "start_ms": seg.start_ms,
+                "end_ms": seg.end_ms,
+                "confidence": seg.confidence,
+            }))
+            .collect()
+    };
+    
+    let mut output = String::new();
+    for line in lines {
+        let json = serde_json::to_string(&line)
+            .map_err(|e| AppError::Serde(format!("JSON 序列化失败: {}", e)))?;
+        output.push_str(&json);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// 渲染 SRT 字幕
+fn render_srt(segments: &[TranscriptSegment]) -> String {
+    render_subtitles(segments, "srt")
+}
+
+/// 渲染 WebVTT 字幕
+fn render_vtt(segments: &[TranscriptSegment]) -> String {
+    render_subtitles(segments, "vtt")
+}
+
+/// 将片段渲染为完整的字幕文本
+fn render_subtitles(segments: &[TranscriptSegment], format: &str) -> String {
+    let mut output = subtitle_header(format).as_bytes().to_vec();
+    for (i, seg) in segments.iter().enumerate() {
+        // 写入 Vec 不会失败
+        let _ = write_cue(&mut output, i, seg, format);
+    }
+    String::from_utf8(output).unwrap_or_default()
+}
+
+/// 字幕文件头（srt 没有文件头）
+pub fn subtitle_header(format: &str) -> &'static str {
+    if format == "vtt" { "WEBVTT\n" } else { "" }
+}
+
+/// 写入一条字幕（index 从 0 开始），供逐条流式写入：srt 条目之间空一行，vtt 每条之前空一行
+pub fn write_cue<W: Write>(out: &mut W, index: usize, seg: &TranscriptSegment, format: &str) -> std::io::Result<()> {
+    if format == "vtt" {
+        return write!(
+            out,
+            "\n{} --> {}\n{}\n",
+            format_cue_timestamp(seg.start_ms, '.'),
+            format_cue_timestamp(seg.end_ms, '.'),
+            seg.text
+        );
+    }
+    
+    if index > 0 {
+        out.write_all(b"\n")?;
+    }
+    write!(
+        out,
+        "{}\n{} --> {}\n{}\n",
+        index + 1,
+        format_cue_timestamp(seg.start_ms, ','),
+        format_cue_timestamp(seg.end_ms, ','),
+        seg.text
+    )
+}
+
+/// 默认全局快捷键
+const DEFAULT_GLOBAL_SHORTCUT: &str = "CmdOrCtrl+Shift+V";
+
+/// 持久化的应用设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub language: String,
+    pub audio_source: AudioSource,
+    pub auto_save: bool,
+    pub global_shortcut: String,
+    /// 自定义词汇，提高专有名词的识别准确率
+    pub custom_vocabulary: Vec<String>,
+    /// 强制使用端侧识别（不回退到服务器识别）
+    pub on_device_recognition: bool,
+    /// 转录结束时发送系统通知
+    pub notifications_enabled: bool,
+    /// 发送通知的最短会话时长（秒）
+    pub notification_min_seconds: u64,
+    /// 转录进行中自动保存会话的间隔（秒）
+    pub autosave_interval_seconds: u64,
+    /// 低置信度阈值，用于 md_annotated 导出
+    pub confidence_threshold: f32,
+    /// 自定义片段分隔符，None 时按语言使用默认值
+    pub segment_separator: Option<String>,
+    /// 停止关键词，最终片段中出现时自动停止转录
+    pub stop_keyword: Option<String>,
+    /// 历史记录数量上限，0 表示不限制
+    pub max_history_records: usize,
+    /// 对确认片段做标点、大小写后处理
+    pub post_processing: bool,
+    /// 强制音频直通，跳过混音与重采样
+    pub audio_passthrough: bool,
+    /// 是否显示菜单栏（托盘）图标
+    pub tray_enabled: bool,
+    /// 自定义导出模板（格式 -> 模板）
+    pub export_templates: BTreeMap<String, String>,
+    /// 持续静音时强制分段
+    pub silence_split_enabled: bool,
+    /// 触发静音分段的静音时长（毫秒）
+    pub silence_split_ms: u64,
+    /// 识别任务类型提示
+    pub task_hint: TaskHint,
+    /// 跳过与上一条记录重复的保存
+    pub skip_duplicate_saves: bool,
+    /// 单次会话的最长有效录制时长（秒），0 表示不限制
+    pub max_session_duration_seconds: u64,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            language: language::DEFAULT_LANGUAGE.to_string(),
+            audio_source: AudioSource::default(),
+            auto_save: true,
+            global_shortcut: DEFAULT_GLOBAL_SHORTCUT.to_string(),
+            custom_vocabulary: Vec::new(),
+            on_device_recognition: false,
+            notifications_enabled: true,
+            notification_min_seconds: crate::notify::DEFAULT_MIN_SESSION_SECONDS,
+            autosave_interval_seconds: crate::autosave::DEFAULT_INTERVAL_SECONDS,
+            confidence_threshold: DEFAULT_CONFIDENCE_THRESHOLD,
+            segment_separator: None,
+            stop_keyword: None,
+            max_history_records: 0,
+            post_processing: false,
+            audio_passthrough: false,
+            tray_enabled: true,
+            export_templates: BTreeMap::new(),
+            silence_split_enabled: false,
+            silence_split_ms: crate::audio_bridge::DEFAULT_SILENCE_SPLIT_MS,
+            task_hint: TaskHint::default(),
+            skip_duplicate_saves: true,
+            max_session_duration_seconds: 0,
+        }
+    }
+}
+
+/// 分页查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptPage {
+    pub records: Vec<TranscriptRecord>,
+    pub total_count: i64,
+}
+
+/// 记录内容的一块（按字节偏移读取，边界对齐到 UTF-8 字符）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptChunk {
+    pub id: i64,
+    /// 本块的实际起始字节偏移（请求偏移落在字符中间时向后对齐）
+    pub byte_offset: usize,
+    pub content: String,
+    /// 下一块的起始字节偏移
+    pub next_offset: usize,
+    /// 内容的总字节数
+    pub total_bytes: usize,
+    /// 之后是否还有内容
+    pub has_more: bool,
+}
+
+/// 转录统计信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptStats {
+    pub total_records: i64,
+    pub total_duration_seconds: i64,
+    pub total_words: i64,
+    pub records_by_language: Vec<(String, i64)>,
+}
+
+/// 单条记录的导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportResult {
+    /// 导出文件路径
+    pub path: String,
+    /// 导出格式
+    pub format: String,
+    /// 导出文件大小（字节），取自写入后的文件
+    pub bytes: u64,
+}
+
+/// 批量导出中单条记录的失败信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportFailure {
+    pub id: i64,
+    pub error: String,
+}
+
+/// 批量导出结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportAllResult {
+    pub written: Vec<String>,
+    pub failures: Vec<ExportFailure>,
 }
 
 /// 存储管理器
 pub struct StorageManager {
     data_dir: PathBuf,
+    conn: Connection,
+    /// 历史记录数量上限，超出时最旧的记录移入归档文件，0 表示不限制
+    max_history: usize,
 }
 
 impl StorageManager {
     /// 创建新的存储管理器
-    pub fn new(app: &AppHandle) -> Result<Self, String> {
-        let data_dir = app
+    /// 数据目录默认为应用数据目录，可通过 MAC_VTT_DATA_DIR 环境变量覆盖
+    pub fn new(app: &AppHandle) -> Result<Self, AppError> {
+        let default_dir = app
             .path()
             .app_data_dir()
-            .map_err(|e| format!("无法获取应用数据目录: {}", e))?;
+            .map_err(|e| AppError::Io(format!("无法获取应用数据目录: {}", e)))?;
+        
+        let data_dir = match std::env::var_os(DATA_DIR_ENV).filter(|v| !v.is_empty()) {
+            Some(dir) => {
+                let dir = PathBuf::from(dir);
+                match ensure_writable_dir(&dir) {
+                    Ok(()) => {
+                        log::info!("使用自定义数据目录: {:?}", dir);
+                        dir
+                    }
+                    Err(e) => {
+                        log::warn!("自定义数据目录 {:?} 不可用，使用默认目录: {}", dir, e);
+                        default_dir
+                    }
+                }
+            }
+            None => default_dir,
+        };
         
         // 确保目录存在
         fs::create_dir_all(&data_dir)
-            .map_err(|e| format!("无法创建数据目录: {}", e))?;
+            .map_err(|e| AppError::Io(format!("无法创建数据目录: {}", e)))?;
+        
+        let conn = Connection::open(data_dir.join("transcripts.db"))
+            .map_err(|e| AppError::Database(format!("无法打开数据库: {}", e)))?;
+        
+        let storage = Self { data_dir, conn, max_history: 0 };
+        storage.init_schema()?;
+        storage.migrate_from_json()?;
+        storage.migrate_timestamps_to_utc()?;
         
-        Ok(Self { data_dir })
+        if let Err(e) = storage.purge_trash_older_than(TRASH_RETENTION_DAYS) {
+            log::warn!("清理回收站失败: {}", e);
+        }
+        
+        Ok(storage)
+    }
+    
+    /// 使用内存数据库创建存储管理器（测试用），设置、归档和导出文件写入 data_dir
+    #[cfg(test)]
+    pub fn open_in_memory(data_dir: &Path) -> Result<Self, AppError> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| AppError::Database(format!("无法打开数据库: {}", e)))?;
+        
+        let storage = Self { data_dir: data_dir.to_path_buf(), conn, max_history: 0 };
+        storage.init_schema()?;
+        Ok(storage)
+    }
+    
+    /// 当前使用的数据目录
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
     }
     
-    /// 获取转录文件路径
-    fn transcripts_file(&self) -> PathBuf {
+    /// 初始化数据库表结构，执行尚未应用的迁移脚本
+    fn init_schema(&self) -> Result<(), AppError> {
+        let version: usize = self.conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| AppError::Database(format!("读取数据库版本失败: {}", e)))?;
+        
+        for (i, migration) in SCHEMA_MIGRATIONS.iter().enumerate().skip(version) {
+            self.conn
+                .execute_batch(&format!("BEGIN; {} PRAGMA user_version = {}; COMMIT;", migration, i + 1))
+                .map_err(|e| AppError::Database(format!("初始化数据库失败: {}", e)))?;
+        }
+        
+        Ok(())
+    }
+    
+    /// 旧版 JSON 转录文件路径
+    fn legacy_json_file(&self) -> PathBuf {
         self.data_dir.join("transcripts.json")
     }
     
+    /// 一次性迁移：导入旧版 transcripts.json 并重命名为 .bak
+    fn migrate_from_json(&self) -> Result<(), AppError> {
+        let json_path = self.legacy_json_file();
+        if !json_path.exists() {
+            return Ok(());
+        }
+        
+        let content = fs::read_to_string(&json_path)
+            .map_err(|e| AppError::Io(format!("读取旧版转录文件失败: {}", e)))?;
+        let records: Vec<TranscriptRecord> = serde_json::from_str(&content)
+            .map_err(|e| AppError::Serde(format!("解析旧版转录数据失败: {}", e)))?;
+        
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {}", e)))?;
+        for record in &records {
+            // 保留原有 ID，已存在则跳过，保证迁移可重复执行
+            tx.execute(
+                "INSERT OR IGNORE INTO transcripts (id, content, language, created_at, duration_seconds)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![record.id, record.content, record.language, record.created_at, record.duration_seconds],
+            )
+            .map_err(|e| AppError::Database(format!("导入转录记录失败: {}", e)))?;
+        }
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {}", e)))?;
+        
+        fs::rename(&json_path, self.data_dir.join("transcripts.json.bak"))
+            .map_err(|e| AppError::Io(format!("重命名旧版转录文件失败: {}", e)))?;
+        
+        log::info!("已从 transcripts.json 迁移 {} 条记录", records.len());
+        Ok(())
+    }
+    
+    /// 一次性迁移：将旧版本地时间字符串（YYYY-MM-DD HH:MM:SS）按本地时区解释并转换为 UTC 时间戳
+    /// 只处理无法按 RFC 3339 解析的值，可重复执行
+    fn migrate_timestamps_to_utc(&self) -> Result<(), AppError> {
+        const COLUMNS: &[(&str, &str)] = &[
+            ("transcripts", "created_at"),
+            ("deleted_transcripts", "created_at"),
+            ("deleted_transcripts", "deleted_at"),
+        ];
+        
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {}", e)))?;
+        let mut converted = 0;
+        for (table, column) in COLUMNS {
+            let mut stmt = tx
+                .prepare(&format!("SELECT id, {column} FROM {table} WHERE {column} NOT LIKE '%Z'"))
+                .map_err(|e| AppError::Database(format!("读取旧版时间戳失败: {}", e)))?;
+            let legacy = stmt
+                .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+                .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+                .map_err(|e| AppError::Database(format!("读取旧版时间戳失败: {}", e)))?;
+            
+            for (id, value) in legacy {
+                let Some(utc) = legacy_local_to_utc(&value) else {
+                    log::warn!("无法解析 {}.{} 的时间戳（ID: {}）: {}", table, column, id, value);
+                    continue;
+                };
+                tx.execute(
+                    &format!("UPDATE {table} SET {column} = ?1 WHERE id = ?2"),
+                    params![utc, id],
+                )
+                .map_err(|e| AppError::Database(format!("转换时间戳失败: {}", e)))?;
+                converted += 1;
+            }
+        }
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {}", e)))?;
+        
+        if converted > 0 {
+            log::info!("已将 {} 个本地时间戳转换为 UTC", converted);
+        }
+        Ok(())
+    }
+    
+    /// 设置文件路径
+    fn settings_file(&self) -> PathBuf {
+        self.data_dir.join("settings.json")
+    }
+    
+    /// 加载应用设置，文件缺失或损坏时使用默认值
+    pub fn load_settings(&self) -> AppSettings {
+        let content = match fs::read_to_string(self.settings_file()) {
+            Ok(content) => content,
+            Err(_) => return AppSettings::default(),
+        };
+        
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("设置文件损坏，使用默认设置: {}", e);
+            AppSettings::default()
+        })
+    }
+    
+    /// 保存应用设置
+    pub fn save_settings(&self, settings: &AppSettings) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(settings)
+            .map_err(|e| AppError::Serde(format!("序列化设置失败: {}", e)))?;
+        
+        write_atomic(&self.settings_file(), json)
+            .map_err(|e| AppError::Io(format!("写入设置文件失败: {}", e)))
+    }
+    
+    /// 按 ID 查询单条记录
+    fn find_transcript(&self, id: i64) -> Result<Option<TranscriptRecord>, AppError> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM transcripts WHERE id = ?1", RECORD_COLUMNS),
+                params![id],
+                TranscriptRecord::from_row,
+            )
+            .optional()
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))
+    }
+    
+    /// 获取单条转录记录
+    pub fn get_transcript(&self, id: i64) -> Result<TranscriptRecord, AppError> {
+        self.find_transcript(id)?
+            .ok_or(AppError::RecordNotFound(id))
+    }
+    
+    /// 分块读取记录内容：从 byte_offset 起最多 len 字节，供前端滚动时按需加载超长记录
+    /// 只从数据库读取所需的字节；起点落在字符中间时向后跳到下一个字符，
+    /// 终点截断到最后一个完整字符，下一块从 next_offset 继续即可无缝衔接
+    pub fn load_transcript_chunk(&self, id: i64, byte_offset: usize, len: usize) -> Result<TranscriptChunk, AppError> {
+        if !(MIN_CHUNK_BYTES..=MAX_CHUNK_BYTES).contains(&len) {
+            return Err(AppError::InvalidInput(format!(
+                "分块大小必须在 {} 到 {} 字节之间",
+                MIN_CHUNK_BYTES, MAX_CHUNK_BYTES
+            )));
+        }
+        
+        // SQLite 的 substr 对 BLOB 按字节计数，下标从 1 开始
+        let (total_bytes, bytes): (i64, Vec<u8>) = self.conn
+            .query_row(
+                "SELECT length(CAST(content AS BLOB)), substr(CAST(content AS BLOB), ?2, ?3)
+                 FROM transcripts WHERE id = ?1",
+                params![id, byte_offset as i64 + 1, len as i64],
+                |row| Ok((row.get(0)?, row.get::<_, Option<Vec<u8>>>(1)?.unwrap_or_default())),
+            )
+            .optional()
+            .map_err(|e| AppError::Database(format!("读取记录内容失败: {}", e)))?
+            .ok_or(AppError::RecordNotFound(id))?;
+        let total_bytes = total_bytes.max(0) as usize;
+        
+        // 跳过开头的 UTF-8 续字节（10xxxxxx）
+        let skipped = bytes.iter().take_while(|&&b| b & 0xC0 == 0x80).count();
+        let bytes = &bytes[skipped..];
+        let content = match std::str::from_utf8(bytes) {
+            Ok(text) => text,
+            // 末尾是不完整的字符：截断到最后一个完整字符
+            Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()])
+                .map_err(|e| AppError::Serde(format!("记录内容不是有效的 UTF-8: {}", e)))?,
+            Err(e) => return Err(AppError::Serde(format!("记录内容不是有效的 UTF-8: {}", e))),
+        };
+        
+        let start = (byte_offset + skipped).min(total_bytes);
+        let next_offset = start + content.len();
+        Ok(TranscriptChunk {
+            id,
+            byte_offset: start,
+            content: content.to_string(),
+            next_offset,
+            total_bytes,
+            has_more: next_offset < total_bytes,
+        })
+    }
+    
     /// 加载所有转录记录
-    pub fn load_transcripts(&self) -> Result<Vec<TranscriptRecord>, String> {
-        let file_path = self.transcripts_file();
+    pub fn load_transcripts(&self) -> Result<Vec<TranscriptRecord>, AppError> {
+        let mut stmt = self.conn
+            .prepare(&format!("SELECT {} FROM transcripts ORDER BY id ASC", RECORD_COLUMNS))
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
+        
+        let rows = stmt
+            .query_map([], TranscriptRecord::from_row)
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
+        
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(format!("解析转录数据失败: {}", e)))
+    }
+    
+    /// 加载历史记录（最新的在前），favorites_first 时收藏的记录排在前面
+    pub fn load_history(&self, favorites_first: bool) -> Result<Vec<TranscriptRecord>, AppError> {
+        let mut stmt = self.conn
+            .prepare(&format!(
+                "SELECT {} FROM transcripts ORDER BY {}",
+                RECORD_COLUMNS,
+                history_order(favorites_first)
+            ))
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
+        
+        let records = stmt
+            .query_map([], TranscriptRecord::from_row)
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(format!("解析转录数据失败: {}", e)))?;
+        
+        Ok(records)
+    }
+    
+    /// 转录记录总数（不含回收站）
+    pub fn count_transcripts(&self) -> Result<i64, AppError> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM transcripts", [], |row| row.get(0))
+            .map_err(|e| AppError::Database(format!("统计转录记录失败: {}", e)))
+    }
+    
+    /// 分页加载转录记录（按 ID 倒序，最新的在前），favorites_first 时收藏的记录排在前面
+    pub fn load_transcripts_page(
+        &self,
+        offset: i64,
+        limit: i64,
+        favorites_first: bool,
+    ) -> Result<TranscriptPage, AppError> {
+        let offset = offset.max(0);
+        let limit = limit.clamp(0, MAX_PAGE_LIMIT);
+        
+        let total_count = self.count_transcripts()?;
         
-        if !file_path.exists() {
+        let mut stmt = self.conn
+            .prepare(&format!(
+                "SELECT {} FROM transcripts ORDER BY {} LIMIT ?1 OFFSET ?2",
+                RECORD_COLUMNS,
+                history_order(favorites_first)
+            ))
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
+        
+        let records = stmt
+            .query_map(params![limit, offset], TranscriptRecord::from_row)
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(format!("解析转录数据失败: {}", e)))?;
+        
+        Ok(TranscriptPage { records, total_count })
+    }
+    
+    /// 搜索转录记录（不区分大小写，匹配内容、语言和创建时间，最新的在前）
+    pub fn search_transcripts(&self, query: &str) -> Result<Vec<TranscriptRecord>, AppError> {
+        let query = query.trim();
+        
+        // 转义 LIKE 通配符，按字面子串匹配
+        let pattern = format!(
+            "%{}%",
+            query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+        
+        let mut stmt = self.conn
+            .prepare(&format!(
+                "SELECT {} FROM transcripts
+                 WHERE content LIKE ?1 ESCAPE '\\'
+                    OR language LIKE ?1 ESCAPE '\\'
+                 ORDER BY id DESC",
+                RECORD_COLUMNS
+            ))
+            .map_err(|e| AppError::Database(format!("搜索转录记录失败: {}", e)))?;
+        
+        let mut records = stmt
+            .query_map(params![pattern], TranscriptRecord::from_row)
+            .map_err(|e| AppError::Database(format!("搜索转录记录失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(format!("解析转录数据失败: {}", e)))?;
+        
+        // 创建时间以 UTC 存储，日期 / 时间查询按本地时间的显示字符串匹配，与界面上看到的一致
+        let looks_like_time = !query.is_empty()
+            && query.chars().all(|c| c.is_ascii_digit() || matches!(c, '-' | ':' | ' '));
+        if looks_like_time {
+            let found: HashSet<i64> = records.iter().map(|r| r.id).collect();
+            records.extend(
+                self.load_transcripts()?
+                    .into_iter()
+                    .filter(|r| !found.contains(&r.id) && r.created_at_local.contains(query)),
+            );
+            records.sort_by(|a, b| b.id.cmp(&a.id));
+        }
+        
+        Ok(records)
+    }
+    
+    /// 按相关度搜索转录内容（不区分大小写），返回记录与评分，评分高的在前，同分时最新的在前
+    /// 评分见 relevance_score；与 search_transcripts 不同，只匹配内容
+    pub fn search_transcripts_ranked(&self, query: &str) -> Result<Vec<(TranscriptRecord, f32)>, AppError> {
+        let needle = fold_chars(query.trim());
+        if needle.is_empty() {
             return Ok(Vec::new());
         }
         
-        let content = fs::read_to_string(&file_path)
-            .map_err(|e| format!("读取转录文件失败: {}", e))?;
+        // ASCII 查询先用 SQL LIKE 筛选候选再在内存中评分；LIKE 只对 ASCII 不区分大小写，
+        // 非 ASCII 查询（如 "ärger" 与 "Ärger"）跳过预筛选，全部记录按 Unicode 小写统一比较
+        let candidates = if query.trim().is_ascii() {
+            self.search_transcripts(query)?
+        } else {
+            self.load_transcripts()?
+        };
+        let mut ranked: Vec<(TranscriptRecord, f32)> = candidates.into_iter()
+            .filter_map(|record| {
+                let content = fold_chars(&record.content);
+                let matches = find_matches(&content, &needle);
+                let score = relevance_score(content.len(), &matches);
+                (score > 0.0).then_some((record, score))
+            })
+            .collect();
         
-        serde_json::from_str(&content)
-            .map_err(|e| format!("解析转录数据失败: {}", e))
+        ranked.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.total_cmp(a_score).then_with(|| b.id.cmp(&a.id))
+        });
+        Ok(ranked)
     }
     
-    /// 保存转录记录
-    pub fn save_transcript(&self, content: &str, language: &str, duration_seconds: i32) -> Result<TranscriptRecord, String> {
-        let mut transcripts = self.load_transcripts()?;
+    /// 加载创建日期在 [start, end] 闭区间内的转录记录（最新的在前），日期格式为 YYYY-MM-DD
+    pub fn load_transcripts_between(&self, start: &str, end: &str) -> Result<Vec<TranscriptRecord>, AppError> {
+        let parse = |date: &str| {
+            chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| AppError::InvalidInput(format!("无效的日期: {}（格式应为 YYYY-MM-DD）", date)))
+        };
+        let start_date = parse(start)?;
+        let end_date = parse(end)?;
+        if start_date > end_date {
+            return Err(AppError::InvalidInput(format!("开始日期 {} 晚于结束日期 {}", start, end)));
+        }
+        
+        // 日期按本地时区解释，换算为 UTC 时间戳后按字符串比较；结束日期取次日零点作为开区间上界
+        let lower = local_midnight_utc(start_date)
+            .unwrap_or_else(|| "0000-01-01T00:00:00Z".to_string());
+        let upper = end_date.succ_opt()
+            .and_then(local_midnight_utc)
+            .unwrap_or_else(|| "9999-12-31T23:59:59Z".to_string());
+        
+        let mut stmt = self.conn
+            .prepare(&format!(
+                "SELECT {} FROM transcripts
+                 WHERE created_at >= ?1 AND created_at < ?2
+                 ORDER BY created_at DESC, id DESC",
+                RECORD_COLUMNS
+            ))
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
+        
+        let records = stmt
+            .query_map(params![lower, upper], TranscriptRecord::from_row)
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(format!("解析转录数据失败: {}", e)))?;
+        
+        Ok(records)
+    }
+    
+    /// 获取转录统计信息
+    pub fn get_statistics(&self) -> Result<TranscriptStats, AppError> {
+        let (total_records, total_duration_seconds): (i64, i64) = self.conn
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(duration_seconds), 0) FROM transcripts",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| AppError::Database(format!("统计转录记录失败: {}", e)))?;
+        
+        let mut stmt = self.conn
+            .prepare("SELECT language, COUNT(*) FROM transcripts GROUP BY language ORDER BY COUNT(*) DESC")
+            .map_err(|e| AppError::Database(format!("统计转录记录失败: {}", e)))?;
+        let records_by_language = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| AppError::Database(format!("统计转录记录失败: {}", e)))?
+            .collect::<Result<Vec<(String, i64)>, _>>()
+            .map_err(|e| AppError::Database(format!("解析统计数据失败: {}", e)))?;
+        
+        let mut stmt = self.conn
+            .prepare("SELECT content, language FROM transcripts")
+            .map_err(|e| AppError::Database(format!("统计转录记录失败: {}", e)))?;
+        let mut rows = stmt
+            .query([])
+            .map_err(|e| AppError::Database(format!("统计转录记录失败: {}", e)))?;
+        
+        let mut total_words = 0;
+        while let Some(row) = rows.next().map_err(|e| AppError::Database(format!("统计转录记录失败: {}", e)))? {
+            let content: String = row.get(0)
+                .map_err(|e| AppError::Database(format!("解析统计数据失败: {}", e)))?;
+            let language: String = row.get(1)
+                .map_err(|e| AppError::Database(format!("解析统计数据失败: {}", e)))?;
+            total_words += language::count_words(&content, &language);
+        }
+        
+        Ok(TranscriptStats {
+            total_records,
+            total_duration_seconds,
+            total_words,
+            records_by_language,
+        })
+    }
+    
+    /// 保存转录记录，started_at 为会话开始时间的 UTC 时间戳（作为记录的创建时间），
+    /// session_id 为生成该记录的转录会话
+    pub fn save_transcript(
+        &self,
+        content: &str,
+        language: &str,
+        duration_seconds: i32,
+        segments: &[TranscriptSegment],
+        started_at: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<TranscriptRecord, AppError> {
+        // 创建时间取会话开始时间，与时长对应；没有时使用当前时间
+        let created_at = started_at
+            .map(str::to_string)
+            .unwrap_or_else(now_timestamp);
+        
+        // 停止与生命周期处理可能先后保存同一会话，重复时返回已有记录
+        if SKIP_DUPLICATES.load(Ordering::Relaxed) {
+            if let Some(existing) = self.find_duplicate(content, language, &created_at)? {
+                log::warn!("跳过重复保存，返回已有记录 ID: {}", existing.id);
+                return Ok(existing);
+            }
+        }
         
-        // 生成新 ID
-        let new_id = transcripts.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        let segments_json = serde_json::to_string(segments)
+            .map_err(|e| AppError::Serde(format!("序列化片段数据失败: {}", e)))?;
         
-        // 获取当前时间
-        let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.conn
+            .execute(
+                "INSERT INTO transcripts (content, language, created_at, duration_seconds, segments, session_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![content, language, created_at, duration_seconds, segments_json, session_id],
+            )
+            .map_err(|e| AppError::Database(format!("写入转录记录失败: {}", e)))?;
         
         let record = TranscriptRecord {
-            id: new_id,
+            id: self.conn.last_insert_rowid(),
             content: content.to_string(),
             language: language.to_string(),
+            created_at_local: local_display(&created_at),
             created_at,
             duration_seconds,
+            segments: segments.to_vec(),
+            tags: Vec::new(),
+            is_favorite: false,
+            rating: None,
+            note: None,
+            session_id: session_id.map(str::to_string),
+        };
+        
+        log::info!("已保存转录记录，ID: {}", record.id);
+        
+        // 归档失败不影响本次保存
+        if let Err(e) = self.prune_to_limit() {
+            log::warn!("归档旧记录失败: {}", e);
+        }
+        Ok(record)
+    }
+    
+    /// 查找与待保存内容重复的上一条记录：内容、语言相同，创建时间相差不超过 DUPLICATE_WINDOW_SECONDS
+    fn find_duplicate(&self, content: &str, language: &str, created_at: &str) -> Result<Option<TranscriptRecord>, AppError> {
+        let previous = self.conn
+            .query_row(
+                &format!("SELECT {} FROM transcripts ORDER BY id DESC LIMIT 1", RECORD_COLUMNS),
+                [],
+                TranscriptRecord::from_row,
+            )
+            .optional()
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
+        
+        let Some(previous) = previous else {
+            return Ok(None);
+        };
+        if previous.content != content || previous.language != language {
+            return Ok(None);
+        }
+        
+        let within_window = match (
+            chrono::DateTime::parse_from_rfc3339(&previous.created_at),
+            chrono::DateTime::parse_from_rfc3339(created_at),
+        ) {
+            (Ok(a), Ok(b)) => (a - b).num_seconds().abs() <= DUPLICATE_WINDOW_SECONDS,
+            _ => previous.created_at == created_at,
+        };
+        Ok(within_window.then_some(previous))
+    }
+    
+    /// 设置历史记录数量上限（0 表示不限制），立即归档超出的记录，返回归档的数量
+    pub fn set_max_history(&mut self, max: usize) -> Result<usize, AppError> {
+        self.max_history = max;
+        self.prune_to_limit()
+    }
+    
+    /// 归档文件路径
+    fn archive_file(&self) -> PathBuf {
+        self.data_dir.join("transcripts_archive.json")
+    }
+    
+    /// 读取归档文件中的记录，文件不存在时返回空列表
+    pub fn load_archive(&self) -> Result<Vec<TranscriptRecord>, AppError> {
+        let content = match fs::read_to_string(self.archive_file()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AppError::Io(format!("读取归档文件失败: {}", e))),
+        };
+        
+        serde_json::from_str(&content)
+            .map_err(|e| AppError::Serde(format!("解析归档文件失败: {}", e)))
+    }
+    
+    /// 记录数超过上限时，将最旧的记录追加到归档文件并从数据库中删除，返回归档的数量
+    /// 先写归档文件再删除，写入失败时不会丢失记录
+    fn prune_to_limit(&self) -> Result<usize, AppError> {
+        if self.max_history == 0 {
+            return Ok(0);
+        }
+        
+        let excess = self.count_transcripts()? - self.max_history as i64;
+        if excess <= 0 {
+            return Ok(0);
+        }
+        
+        let oldest = {
+            let mut stmt = self.conn
+                .prepare(&format!("SELECT {} FROM transcripts ORDER BY id ASC LIMIT ?1", RECORD_COLUMNS))
+                .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
+            let records = stmt
+                .query_map(params![excess], TranscriptRecord::from_row)
+                .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| AppError::Database(format!("解析转录数据失败: {}", e)))?;
+            records
         };
         
-        transcripts.push(record.clone());
+        let mut archive = self.load_archive()?;
+        archive.extend(oldest.iter().cloned());
+        let json = serde_json::to_string_pretty(&archive)
+            .map_err(|e| AppError::Serde(format!("序列化归档数据失败: {}", e)))?;
+        write_atomic(&self.archive_file(), json)
+            .map_err(|e| AppError::Io(format!("写入归档文件失败: {}", e)))?;
         
-        // 写入文件
-        let json = serde_json::to_string_pretty(&transcripts)
-            .map_err(|e| format!("序列化数据失败: {}", e))?;
+        let ids: Vec<i64> = oldest.iter().map(|r| r.id).collect();
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let archived = self.conn
+            .execute(
+                &format!("DELETE FROM transcripts WHERE id IN ({})", placeholders),
+                rusqlite::params_from_iter(&ids),
+            )
+            .map_err(|e| AppError::Database(format!("删除已归档记录失败: {}", e)))?;
         
-        fs::write(self.transcripts_file(), json)
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        log::info!("历史记录超过上限 {}，已归档 {} 条最旧的记录", self.max_history, archived);
+        Ok(archived)
+    }
+    
+    /// 更新转录记录内容，保留创建时间、语言和时长
+    pub fn update_transcript_content(&self, id: i64, content: String) -> Result<TranscriptRecord, AppError> {
+        let updated = self.conn
+            .execute("UPDATE transcripts SET content = ?1 WHERE id = ?2", params![content, id])
+            .map_err(|e| AppError::Database(format!("更新转录记录失败: {}", e)))?;
         
-        log::info!("已保存转录记录，ID: {}", new_id);
+        if updated == 0 {
+            return Err(AppError::RecordNotFound(id));
+        }
+        
+        log::info!("已更新转录记录内容，ID: {}", id);
+        self.get_transcript(id)
+    }
+    
+    /// 写入续写会话：替换记录内容、累加时长，并将新片段按原时长顺延后追加
+    /// 三项在同一事务中更新，任一失败时记录保持不变
+    pub fn append_session_to_transcript(
+        &self,
+        id: i64,
+        content: &str,
+        added_seconds: i32,
+        segments: &[TranscriptSegment],
+    ) -> Result<TranscriptRecord, AppError> {
+        let record = self.get_transcript(id)?;
+        
+        let offset_ms = record.duration_seconds.max(0) as u64 * 1000;
+        let mut all_segments = record.segments;
+        all_segments.extend(segments.iter().map(|seg| TranscriptSegment {
+            start_ms: seg.start_ms + offset_ms,
+            end_ms: seg.end_ms + offset_ms,
+            ..seg.clone()
+        }));
+        let segments_json = serde_json::to_string(&all_segments)
+            .map_err(|e| AppError::Serde(format!("序列化片段数据失败: {}", e)))?;
+        
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {}", e)))?;
+        let updated = tx
+            .execute(
+                "UPDATE transcripts SET content = ?1, duration_seconds = duration_seconds + ?2, segments = ?3 WHERE id = ?4",
+                params![content, added_seconds, segments_json, id],
+            )
+            .map_err(|e| AppError::Database(format!("更新转录记录失败: {}", e)))?;
+        if updated == 0 {
+            return Err(AppError::RecordNotFound(id));
+        }
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {}", e)))?;
+        
+        log::info!("已将续写内容写入转录记录，ID: {}", id);
+        self.get_transcript(id)
+    }
+    
+    /// 设置记录的收藏状态
+    pub fn set_favorite(&self, id: i64, favorite: bool) -> Result<(), AppError> {
+        let updated = self.conn
+            .execute("UPDATE transcripts SET is_favorite = ?1 WHERE id = ?2", params![favorite, id])
+            .map_err(|e| AppError::Database(format!("更新收藏状态失败: {}", e)))?;
+        
+        if updated == 0 {
+            return Err(AppError::RecordNotFound(id));
+        }
+        
+        log::info!("已更新记录 {} 的收藏状态: {}", id, favorite);
+        Ok(())
+    }
+    
+    /// 设置记录的评分（None 表示清除），评分须在 1 ~ 5 之间
+    pub fn set_rating(&self, id: i64, rating: Option<u8>) -> Result<TranscriptRecord, AppError> {
+        if let Some(value) = rating {
+            if !RATING_RANGE.contains(&value) {
+                return Err(AppError::InvalidInput(format!(
+                    "评分须在 {} ~ {} 之间: {}",
+                    RATING_RANGE.start(), RATING_RANGE.end(), value
+                )));
+            }
+        }
+        
+        let updated = self.conn
+            .execute("UPDATE transcripts SET rating = ?1 WHERE id = ?2", params![rating, id])
+            .map_err(|e| AppError::Database(format!("更新评分失败: {}", e)))?;
+        if updated == 0 {
+            return Err(AppError::RecordNotFound(id));
+        }
+        
+        log::info!("已更新记录 {} 的评分: {:?}", id, rating);
+        self.get_transcript(id)
+    }
+    
+    /// 设置记录的备注（None 或空白表示清除）
+    pub fn set_note(&self, id: i64, note: Option<String>) -> Result<TranscriptRecord, AppError> {
+        let note = note
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty());
+        
+        let updated = self.conn
+            .execute("UPDATE transcripts SET note = ?1 WHERE id = ?2", params![note, id])
+            .map_err(|e| AppError::Database(format!("更新备注失败: {}", e)))?;
+        if updated == 0 {
+            return Err(AppError::RecordNotFound(id));
+        }
+        
+        log::info!("已更新记录 {} 的备注", id);
+        self.get_transcript(id)
+    }
+    
+    /// 写入记录的标签列表
+    fn write_tags(&self, id: i64, tags: &[String]) -> Result<(), AppError> {
+        let tags_json = serde_json::to_string(tags)
+            .map_err(|e| AppError::Serde(format!("序列化标签失败: {}", e)))?;
+        
+        self.conn
+            .execute("UPDATE transcripts SET tags = ?1 WHERE id = ?2", params![tags_json, id])
+            .map_err(|e| AppError::Database(format!("更新标签失败: {}", e)))?;
+        
+        Ok(())
+    }
+    
+    /// 为记录添加标签
+    pub fn add_tag(&self, id: i64, tag: &str) -> Result<TranscriptRecord, AppError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(AppError::InvalidInput("标签不能为空".to_string()));
+        }
+        
+        let mut record = self.get_transcript(id)?;
+        if record.tags.iter().any(|t| t == tag) {
+            return Err(AppError::InvalidInput(format!("标签已存在: {}", tag)));
+        }
+        
+        record.tags.push(tag.to_string());
+        self.write_tags(id, &record.tags)?;
+        
+        log::info!("已为记录 {} 添加标签: {}", id, tag);
         Ok(record)
     }
     
-    /// 删除转录记录
-    pub fn delete_transcript(&self, id: i64) -> Result<(), String> {
-        let mut transcripts = self.load_transcripts()?;
-        transcripts.retain(|t| t.id != id);
+    /// 移除记录的标签
+    pub fn remove_tag(&self, id: i64, tag: &str) -> Result<TranscriptRecord, AppError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(AppError::InvalidInput("标签不能为空".to_string()));
+        }
+        
+        let mut record = self.get_transcript(id)?;
+        record.tags.retain(|t| t != tag);
+        self.write_tags(id, &record.tags)?;
+        
+        log::info!("已移除记录 {} 的标签: {}", id, tag);
+        Ok(record)
+    }
+    
+    /// 按标签获取转录记录（最新的在前）
+    pub fn get_transcripts_by_tag(&self, tag: &str) -> Result<Vec<TranscriptRecord>, AppError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(AppError::InvalidInput("标签不能为空".to_string()));
+        }
+        
+        let mut stmt = self.conn
+            .prepare(&format!(
+                "SELECT {} FROM transcripts
+                 WHERE EXISTS (SELECT 1 FROM json_each(transcripts.tags) WHERE value = ?1)
+                 ORDER BY id DESC",
+                RECORD_COLUMNS
+            ))
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
         
-        let json = serde_json::to_string_pretty(&transcripts)
-            .map_err(|e| format!("序列化数据失败: {}", e))?;
+        let records = stmt
+            .query_map(params![tag], TranscriptRecord::from_row)
+            .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(format!("解析转录数据失败: {}", e)))?;
         
-        fs::write(self.transcripts_file(), json)
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        Ok(records)
+    }
+    
+    /// 删除转录记录
+    /// 删除的记录移入回收站，可通过 restore_transcript 恢复
+    pub fn delete_transcript(&self, id: i64) -> Result<(), AppError> {
+        self.move_to_trash(&[id])?;
         
-        log::info!("已删除转录记录，ID: {}", id);
+        log::info!("已将转录记录移入回收站，ID: {}", id);
         Ok(())
     }
     
+    /// 批量删除转录记录（移入回收站），返回实际删除的数量（不存在的 ID 会被忽略）
+    pub fn delete_transcripts(&self, ids: &[i64]) -> Result<usize, AppError> {
+        let deleted = self.move_to_trash(ids)?;
+        
+        log::info!("已将 {} 条转录记录移入回收站", deleted);
+        Ok(deleted)
+    }
+    
+    /// 将所有转录记录移入回收站，返回移动的数量
+    pub fn clear_all(&self) -> Result<usize, AppError> {
+        let ids = {
+            let mut stmt = self.conn
+                .prepare("SELECT id FROM transcripts")
+                .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?;
+            let ids = stmt
+                .query_map([], |row| row.get::<_, i64>(0))
+                .map_err(|e| AppError::Database(format!("查询转录记录失败: {}", e)))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| AppError::Database(format!("解析转录数据失败: {}", e)))?;
+            ids
+        };
+        let cleared = self.move_to_trash(&ids)?;
+        
+        log::info!("已清空历史记录，{} 条记录移入回收站", cleared);
+        Ok(cleared)
+    }
+    
+    /// 将记录移入回收站并记录删除时间，返回移动的数量
+    fn move_to_trash(&self, ids: &[i64]) -> Result<usize, AppError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        
+        let deleted_at = now_timestamp();
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {}", e)))?;
+        tx.execute(
+            &format!(
+                "INSERT OR REPLACE INTO deleted_transcripts ({columns}, deleted_at)
+                 SELECT {columns}, ? FROM transcripts WHERE id IN ({placeholders})",
+                columns = RECORD_COLUMNS,
+                placeholders = placeholders
+            ),
+            rusqlite::params_from_iter(
+                std::iter::once(rusqlite::types::Value::from(deleted_at))
+                    .chain(ids.iter().map(|id| rusqlite::types::Value::from(*id))),
+            ),
+        )
+        .map_err(|e| AppError::Database(format!("移入回收站失败: {}", e)))?;
+        let deleted = tx
+            .execute(
+                &format!("DELETE FROM transcripts WHERE id IN ({})", placeholders),
+                rusqlite::params_from_iter(ids),
+            )
+            .map_err(|e| AppError::Database(format!("删除转录记录失败: {}", e)))?;
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {}", e)))?;
+        
+        Ok(deleted)
+    }
+    
+    /// 列出回收站中的记录（最近删除的在前）
+    pub fn list_trash(&self) -> Result<Vec<TrashedTranscript>, AppError> {
+        let mut stmt = self.conn
+            .prepare(&format!(
+                "SELECT {}, deleted_at FROM deleted_transcripts ORDER BY deleted_at DESC, id DESC",
+                RECORD_COLUMNS
+            ))
+            .map_err(|e| AppError::Database(format!("查询回收站失败: {}", e)))?;
+        
+        let records = stmt
+            .query_map([], |row| {
+                Ok(TrashedTranscript {
+                    record: TranscriptRecord::from_row(row)?,
+                    deleted_at: row.get("deleted_at")?,
+                })
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| AppError::Database(format!("读取回收站失败: {}", e)))?;
+        
+        Ok(records)
+    }
+    
+    /// 从回收站恢复记录（保留原 ID）
+    pub fn restore_transcript(&self, id: i64) -> Result<TranscriptRecord, AppError> {
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {}", e)))?;
+        let restored = tx
+            .execute(
+                &format!(
+                    "INSERT INTO transcripts ({columns}) SELECT {columns} FROM deleted_transcripts WHERE id = ?1",
+                    columns = RECORD_COLUMNS
+                ),
+                params![id],
+            )
+            .map_err(|e| AppError::Database(format!("恢复转录记录失败: {}", e)))?;
+        if restored == 0 {
+            return Err(AppError::RecordNotFound(id));
+        }
+        tx.execute("DELETE FROM deleted_transcripts WHERE id = ?1", params![id])
+            .map_err(|e| AppError::Database(format!("清除回收站记录失败: {}", e)))?;
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {}", e)))?;
+        
+        log::info!("已从回收站恢复转录记录，ID: {}", id);
+        self.get_transcript(id)
+    }
+    
+    /// 清空回收站，返回永久删除的数量
+    pub fn empty_trash(&self) -> Result<usize, AppError> {
+        let purged = self.conn
+            .execute("DELETE FROM deleted_transcripts", [])
+            .map_err(|e| AppError::Database(format!("清空回收站失败: {}", e)))?;
+        
+        log::info!("已清空回收站，永久删除 {} 条记录", purged);
+        Ok(purged)
+    }
+    
+    /// 永久删除回收站中超过指定天数的记录，返回删除的数量
+    pub fn purge_trash_older_than(&self, days: i64) -> Result<usize, AppError> {
+        let cutoff = utc_timestamp(chrono::Utc::now() - chrono::Duration::days(days));
+        
+        let purged = self.conn
+            .execute("DELETE FROM deleted_transcripts WHERE deleted_at < ?1", params![cutoff])
+            .map_err(|e| AppError::Database(format!("清理回收站失败: {}", e)))?;
+        
+        if purged > 0 {
+            log::info!("已清理回收站中 {} 天前删除的 {} 条记录", days, purged);
+        }
+        Ok(purged)
+    }
+    
+    /// 从 JSON 数组导入转录记录，重新分配 ID 后追加，返回成功导入的数量
+    /// 格式不正确的条目会被跳过，不会中断整个导入
+    pub fn import_transcripts(&self, json: &str) -> Result<usize, AppError> {
+        let entries: Vec<serde_json::Value> = serde_json::from_str(json)
+            .map_err(|e| AppError::Serde(format!("导入文件不是有效的 JSON 数组: {}", e)))?;
+        
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {}", e)))?;
+        let mut imported = 0;
+        for (index, entry) in entries.into_iter().enumerate() {
+            let record: TranscriptRecord = match serde_json::from_value(entry) {
+                Ok(record) => record,
+                Err(e) => {
+                    log::warn!("跳过第 {} 条格式不正确的记录: {}", index + 1, e);
+                    continue;
+                }
+            };
+            insert_record(&tx, &record)?;
+            imported += 1;
+        }
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {}", e)))?;
+        
+        log::info!("已导入 {} 条转录记录", imported);
+        Ok(imported)
+    }
+    
+    /// 合并多条转录记录为一条新记录，并删除原记录
+    /// 内容按 ID 顺序以空行拼接，时长求和，保留最早的创建时间，语言取第一条记录
+    pub fn merge_transcripts(&self, ids: &[i64]) -> Result<TranscriptRecord, AppError> {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() < 2 {
+            return Err(AppError::InvalidInput("至少需要两条记录才能合并".to_string()));
+        }
+        
+        let records = ids.iter()
+            .map(|id| self.get_transcript(*id))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let content = records.iter()
+            .map(|r| r.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let language = records[0].language.clone();
+        let created_at = records.iter()
+            .map(|r| r.created_at.clone())
+            .min()
+            .unwrap_or_default();
+        let duration_seconds: i32 = records.iter().map(|r| r.duration_seconds).sum();
+        
+        // 片段时间按前面记录的累计时长顺延
+        let mut segments = Vec::new();
+        let mut offset_ms = 0u64;
+        for record in &records {
+            segments.extend(record.segments.iter().map(|seg| TranscriptSegment {
+                start_ms: seg.start_ms + offset_ms,
+                end_ms: seg.end_ms + offset_ms,
+                ..seg.clone()
+            }));
+            offset_ms += record.duration_seconds.max(0) as u64 * 1000;
+        }
+        
+        let mut tags: Vec<String> = Vec::new();
+        for tag in records.iter().flat_map(|r| r.tags.iter()) {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        let is_favorite = records.iter().any(|r| r.is_favorite);
+        let rating = records.iter().filter_map(|r| r.rating).max();
+        let notes: Vec<&str> = records.iter().filter_map(|r| r.note.as_deref()).collect();
+        let note = (!notes.is_empty()).then(|| notes.join("\n"));
+        // 合并后的记录沿用最早一条的会话 ID
+        let session_id = records.iter().find_map(|r| r.session_id.clone());
+        
+        let mut merged = TranscriptRecord {
+            id: 0,
+            content,
+            language,
+            created_at_local: local_display(&created_at),
+            created_at,
+            duration_seconds,
+            segments,
+            tags,
+            is_favorite,
+            rating,
+            note,
+            session_id,
+        };
+        
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {}", e)))?;
+        merged.id = insert_record(&tx, &merged)?;
+        
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        tx.execute(
+            &format!("DELETE FROM transcripts WHERE id IN ({})", placeholders),
+            rusqlite::params_from_iter(&ids),
+        )
+        .map_err(|e| AppError::Database(format!("删除原记录失败: {}", e)))?;
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {}", e)))?;
+        
+        log::info!("已合并记录 {:?}，新记录 ID: {}", ids, merged.id);
+        Ok(merged)
+    }
+    
+    /// 在指定行处将记录拆分为两条新记录，并删除原记录
+    /// 第 line_index 行（从 0 开始）成为第二条记录的首行，时长按字符数比例分配
+    pub fn split_transcript(&self, id: i64, line_index: usize) -> Result<(TranscriptRecord, TranscriptRecord), AppError> {
+        let record = self.get_transcript(id)?;
+        
+        let lines: Vec<&str> = record.content.split('\n').collect();
+        if line_index == 0 || line_index >= lines.len() {
+            return Err(AppError::InvalidInput(format!(
+                "拆分位置超出范围: {}（有效范围 1 ~ {}）",
+                line_index,
+                lines.len().saturating_sub(1)
+            )));
+        }
+        
+        let first_content = lines[..line_index].join("\n");
+        let second_content = lines[line_index..].join("\n");
+        
+        let first_chars = first_content.chars().count();
+        let total_chars = (first_chars + second_content.chars().count()).max(1);
+        let first_duration = (record.duration_seconds as f64 * first_chars as f64 / total_chars as f64).round() as i32;
+        let second_duration = record.duration_seconds - first_duration;
+        
+        // 片段与行一一对应时按同一位置拆分，第二部分的时间从 0 开始；否则丢弃片段信息
+        let (first_segments, second_segments) = if record.segments.len() == lines.len() {
+            let offset = record.segments[line_index].start_ms;
+            let second = record.segments[line_index..].iter()
+                .map(|seg| TranscriptSegment {
+                    start_ms: seg.start_ms.saturating_sub(offset),
+                    end_ms: seg.end_ms.saturating_sub(offset),
+                    ..seg.clone()
+                })
+                .collect();
+            (record.segments[..line_index].to_vec(), second)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        
+        let mut first = TranscriptRecord {
+            content: first_content,
+            duration_seconds: first_duration,
+            segments: first_segments,
+            ..record.clone()
+        };
+        let mut second = TranscriptRecord {
+            content: second_content,
+            duration_seconds: second_duration,
+            segments: second_segments,
+            ..record
+        };
+        
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::Database(format!("开启事务失败: {}", e)))?;
+        first.id = insert_record(&tx, &first)?;
+        second.id = insert_record(&tx, &second)?;
+        tx.execute("DELETE FROM transcripts WHERE id = ?1", params![id])
+            .map_err(|e| AppError::Database(format!("删除原记录失败: {}", e)))?;
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("提交事务失败: {}", e)))?;
+        
+        log::info!("已拆分记录 {}，新记录 ID: {} / {}", id, first.id, second.id);
+        Ok((first, second))
+    }
+    
     /// 导出转录到文件
-    pub fn export_transcript(&self, id: i64, format: &str) -> Result<String, String> {
-        let transcripts = self.load_transcripts()?;
-        let record = transcripts.iter()
-            .find(|t| t.id == id)
-            .ok_or_else(|| format!("未找到 ID 为 {} 的记录", id))?;
+    /// timestamps 控制 md 格式是否为每段添加时间戳，None 时有片段时间信息则添加
+    /// raw 为 true 时导出后处理前的原始识别文本
+    pub fn export_transcript(
+        &self,
+        id: i64,
+        format: &str,
+        timestamps: Option<bool>,
+        raw: bool,
+    ) -> Result<ExportResult, AppError> {
+        let record = self.get_transcript(id)?;
+        let record = if raw { record.to_raw() } else { record };
         
         let export_dir = self.data_dir.join("exports");
         fs::create_dir_all(&export_dir)
-            .map_err(|e| format!("无法创建导出目录: {}", e))?;
+            .map_err(|e| AppError::Io(format!("无法创建导出目录: {}", e)))?;
         
-        let filename = format!("transcript_{}_{}.{}", id, record.created_at.replace([':', ' '], "_"), format);
-        let file_path = export_dir.join(&filename);
+        let file_path = write_export(&record, format, timestamps, &export_dir)?;
+        let bytes = fs::metadata(&file_path)
+            .map_err(|e| AppError::Io(format!("读取导出文件失败: {}", e)))?
+            .len();
         
-        let content = match format {
-            "md" => format!(
-                "# 转录记录\n\n- **时间**: {}\n- **语言**: {}\n- **时长**: {} 秒\n\n---\n\n{}",
-                record.created_at, record.language, record.duration_seconds, record.content
-            ),
-            "json" => serde_json::to_string_pretty(record)
-                .map_err(|e| format!("JSON 序列化失败: {}", e))?,
-            _ => record.content.clone(), // txt 格式
-        };
+        log::info!("已导出转录记录到: {:?}（{} 字节）", file_path, bytes);
+        Ok(ExportResult {
+            path: file_path.to_string_lossy().to_string(),
+            format: format.to_string(),
+            bytes,
+        })
+    }
+    
+    /// 将多条记录按 ID 顺序合并导出为一个文件（txt / md / json），返回文件路径
+    pub fn export_combined(&self, ids: &[i64], format: &str) -> Result<String, AppError> {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.is_empty() {
+            return Err(AppError::InvalidInput("请至少选择一条记录".to_string()));
+        }
+        
+        let records = ids.iter()
+            .map(|id| self.get_transcript(*id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let content = render_combined(&records, format)?;
+        
+        let export_dir = self.data_dir.join("exports");
+        fs::create_dir_all(&export_dir)
+            .map_err(|e| AppError::Io(format!("无法创建导出目录: {}", e)))?;
         
-        fs::write(&file_path, &content)
-            .map_err(|e| format!("写入导出文件失败: {}", e))?;
+        let stem = format!("transcripts_combined_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        let (file_path, mut file) = create_unique_file(&export_dir, &stem, format)?;
+        file.write_all(content.as_bytes())
+            .map_err(|e| AppError::Io(format!("写入导出文件失败: {}", e)))?;
         
-        log::info!("已导出转录记录到: {:?}", file_path);
+        log::info!("已将 {} 条记录合并导出到: {:?}", records.len(), file_path);
         Ok(file_path.to_string_lossy().to_string())
     }
+    
+    /// 导出所有转录记录到指定目录（每条记录一个文件）
+    /// 单个文件写入失败不会中断，失败信息记录在返回结果中
+    /// csv 格式例外：所有记录写入同一个 history.csv
+    pub fn export_all_transcripts(&self, format: &str, output_dir: &Path) -> Result<ExportAllResult, AppError> {
+        fs::create_dir_all(output_dir)
+            .map_err(|e| AppError::Io(format!("无法创建导出目录: {}", e)))?;
+        
+        if format == "csv" {
+            let path = self.write_history_csv(output_dir)?;
+            return Ok(ExportAllResult {
+                written: vec![path.to_string_lossy().to_string()],
+                failures: Vec::new(),
+            });
+        }
+        
+        let mut result = ExportAllResult::default();
+        for record in self.load_transcripts()? {
+            match write_export(&record, format, None, output_dir) {
+                Ok(path) => result.written.push(path.to_string_lossy().to_string()),
+                Err(e) => {
+                    log::warn!("导出记录 {} 失败: {}", record.id, e);
+                    result.failures.push(ExportFailure {
+                        id: record.id,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+        
+        log::info!(
+            "批量导出完成，成功 {} 个，失败 {} 个",
+            result.written.len(),
+            result.failures.len()
+        );
+        Ok(result)
+    }
+    
+    /// 将全部记录写入 output_dir/history.csv，返回文件路径
+    fn write_history_csv(&self, output_dir: &Path) -> Result<PathBuf, AppError> {
+        let file_path = output_dir.join("history.csv");
+        let csv_error = |e: csv::Error| AppError::Io(format!("写入 CSV 失败: {}", e));
+        
+        let mut writer = csv::Writer::from_path(&file_path).map_err(csv_error)?;
+        writer.write_record(["id", "created_at", "language", "duration_seconds", "content"])
+            .map_err(csv_error)?;
+        
+        let records = self.load_transcripts()?;
+        for record in &records {
+            writer.write_record([
+                record.id.to_string(),
+                record.created_at.clone(),
+                record.language.clone(),
+                record.duration_seconds.to_string(),
+                record.content.clone(),
+            ]).map_err(csv_error)?;
+        }
+        writer.flush()
+            .map_err(|e| AppError::Io(format!("写入 CSV 失败: {}", e)))?;
+        
+        log::info!("已导出 {} 条记录到: {:?}", records.len(), file_path);
+        Ok(file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn open_storage() -> (tempfile::TempDir, StorageManager) {
+        let dir = tempfile::tempdir().expect("创建临时目录失败");
+        let storage = StorageManager::open_in_memory(dir.path()).expect("打开内存数据库失败");
+        (dir, storage)
+    }
+    
+    #[test]
+    fn duplicate_save_returns_existing_record() {
+        let (_dir, storage) = open_storage();
+        let started_at = now_timestamp();
+        
+        let first = storage
+            .save_transcript("你好，世界", "zh-CN", 12, &[], Some(&started_at), Some("session-a"))
+            .unwrap();
+        let second = storage
+            .save_transcript("你好，世界", "zh-CN", 12, &[], Some(&started_at), Some("session-a"))
+            .unwrap();
+        
+        assert_eq!(first.id, second.id);
+        assert_eq!(storage.count_transcripts().unwrap(), 1);
+    }
+    
+    #[test]
+    fn sanitize_filename_strips_separators_and_dot_runs() {
+        let name = sanitize_filename("../a/b\\c:d..e");
+        for forbidden in ["/", "\\", ":", ".."] {
+            assert!(!name.contains(forbidden), "{} 中包含 {}", name, forbidden);
+        }
+        assert_eq!(name, "_a_b_c_d.e");
+        assert_eq!(sanitize_filename(".."), "export");
+        assert_eq!(sanitize_filename("transcript_1_2024-01-02 10:20:30"), "transcript_1_2024-01-02_10_20_30");
+    }
+    
+    #[test]
+    fn exporting_twice_creates_distinct_files() {
+        let (_dir, storage) = open_storage();
+        let record = storage
+            .save_transcript("导出测试", "zh-CN", 3, &[], None, None)
+            .unwrap();
+        
+        let first = storage.export_transcript(record.id, "txt", None, false).unwrap();
+        let second = storage.export_transcript(record.id, "txt", None, false).unwrap();
+        
+        assert_ne!(first.path, second.path);
+        assert!(Path::new(&first.path).is_file());
+        assert!(Path::new(&second.path).is_file());
+        assert_eq!(fs::read(&first.path).unwrap(), fs::read(&second.path).unwrap());
+    }
+    
+    #[test]
+    fn export_result_reports_file_size() {
+        let (_dir, storage) = open_storage();
+        let record = storage
+            .save_transcript("Export size check with 中文 content", "en-US", 5, &[], None, None)
+            .unwrap();
+        
+        for format in ["txt", "md", "json"] {
+            let result = storage.export_transcript(record.id, format, None, false).unwrap();
+            assert_eq!(result.format, format);
+            assert!(result.bytes > 0);
+            assert_eq!(result.bytes, fs::metadata(&result.path).unwrap().len());
+        }
+    }
+    
+    #[test]
+    fn ranked_search_folds_non_ascii_case() {
+        let (_dir, storage) = open_storage();
+        let record = storage
+            .save_transcript("Ärger im Büro", "de-DE", 4, &[], None, None)
+            .unwrap();
+        storage.save_transcript("Alles gut", "de-DE", 2, &[], None, None).unwrap();
+        
+        for query in ["ärger", "ÄRGER", "büro", "Ärger"] {
+            let results = storage.search_transcripts_ranked(query).unwrap();
+            assert_eq!(results.len(), 1, "查询 {}", query);
+            assert_eq!(results[0].0.id, record.id);
+        }
+        assert_eq!(storage.search_transcripts_ranked("ALLES").unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn search_matches_local_creation_time() {
+        let (_dir, storage) = open_storage();
+        let local = chrono::NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(23, 30, 0)
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .earliest()
+            .unwrap();
+        let started_at = utc_timestamp(local.with_timezone(&chrono::Utc));
+        let record = storage
+            .save_transcript("深夜记录", "zh-CN", 6, &[], Some(&started_at), None)
+            .unwrap();
+        storage.save_transcript("其他记录", "zh-CN", 2, &[], None, None).unwrap();
+        
+        for query in ["2024-03-01", "2024-03-01 23:30", "23:30:00"] {
+            let results = storage.search_transcripts(query).unwrap();
+            assert_eq!(results.len(), 1, "查询 {}", query);
+            assert_eq!(results[0].id, record.id);
+        }
+        assert_eq!(storage.search_transcripts("深夜").unwrap().len(), 1);
+        assert!(storage.search_transcripts("2024-03-02").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn appending_session_updates_content_and_timing_together() {
+        let (_dir, storage) = open_storage();
+        let segment = |text: &str, start_ms, end_ms| TranscriptSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: 0.9,
+            raw_text: None,
+        };
+        let original = storage
+            .save_transcript("第一段。", "zh-CN", 4, &[segment("第一段。", 0, 3000)], None, None)
+            .unwrap();
+        
+        let record = storage
+            .append_session_to_transcript(original.id, "第一段。第二段。", 2, &[segment("第二段。", 500, 1500)])
+            .unwrap();
+        assert_eq!(record.content, "第一段。第二段。");
+        assert_eq!(record.duration_seconds, 6);
+        assert_eq!(record.segments.len(), 2);
+        assert_eq!((record.segments[1].start_ms, record.segments[1].end_ms), (4500, 5500));
+        
+        assert!(matches!(
+            storage.append_session_to_transcript(original.id + 1, "不存在", 1, &[]),
+            Err(AppError::RecordNotFound(_))
+        ));
+    }
 }
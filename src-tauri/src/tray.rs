@@ -0,0 +1,115 @@
+// tray.rs
+// 菜单栏（托盘）快捷控制
+// 提供开始、停止转录和打开历史记录的菜单，标题和菜单状态随捕获状态更新
+
+use crate::audio_bridge::AudioBridge;
+use crate::{begin_capture, end_capture, AppState};
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+/// 托盘图标 ID
+const TRAY_ID: &str = "main-tray";
+
+/// 前端打开历史记录面板的事件名（由托盘菜单触发）
+pub const OPEN_HISTORY_EVENT: &str = "open-history";
+
+const MENU_START: &str = "tray-start";
+const MENU_STOP: &str = "tray-stop";
+const MENU_HISTORY: &str = "tray-history";
+
+/// 转录中显示在菜单栏的标题
+const CAPTURING_TITLE: &str = "● REC";
+
+/// 托盘菜单项，保存在应用状态中以便按捕获状态启用或禁用
+struct TrayMenu {
+    start: MenuItem<Wry>,
+    stop: MenuItem<Wry>,
+}
+
+/// 创建托盘图标，已存在时不重复创建
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    if app.tray_by_id(TRAY_ID).is_some() {
+        return Ok(());
+    }
+    
+    let start = MenuItem::with_id(app, MENU_START, "开始转录", true, None::<&str>)?;
+    let stop = MenuItem::with_id(app, MENU_STOP, "停止转录", false, None::<&str>)?;
+    let history = MenuItem::with_id(app, MENU_HISTORY, "打开历史记录", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&start, &stop, &history])?;
+    
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip("Mac Voice to Text")
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    
+    // manage 只在首次调用时生效，重新创建托盘时替换菜单项
+    if let Some(existing) = app.try_state::<Mutex<TrayMenu>>() {
+        if let Ok(mut items) = existing.lock() {
+            *items = TrayMenu { start, stop };
+        }
+    } else {
+        app.manage(Mutex::new(TrayMenu { start, stop }));
+    }
+    
+    update(app, AudioBridge::is_capturing());
+    log::info!("托盘图标已创建");
+    Ok(())
+}
+
+/// 移除托盘图标
+pub fn remove(app: &AppHandle) {
+    if app.remove_tray_by_id(TRAY_ID).is_some() {
+        log::info!("托盘图标已移除");
+    }
+}
+
+/// 按捕获状态更新托盘标题、提示和菜单项，托盘未创建时不做任何事
+pub fn update(app: &AppHandle, is_capturing: bool) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    
+    let title = is_capturing.then_some(CAPTURING_TITLE);
+    let tooltip = if is_capturing { "Mac Voice to Text - 转录中" } else { "Mac Voice to Text" };
+    if let Err(e) = tray.set_title(title).and_then(|_| tray.set_tooltip(Some(tooltip))) {
+        log::warn!("更新托盘状态失败: {}", e);
+    }
+    
+    if let Some(menu) = app.try_state::<Mutex<TrayMenu>>() {
+        if let Ok(items) = menu.lock() {
+            let _ = items.start.set_enabled(!is_capturing);
+            let _ = items.stop.set_enabled(is_capturing);
+        }
+    }
+}
+
+/// 托盘菜单点击：开始 / 停止与命令走同一代码路径，状态更新由捕获事件驱动
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    let state = app.state::<AppState>();
+    let result = match id {
+        MENU_START => begin_capture(app, &state),
+        MENU_STOP => end_capture(app, &state).map(|_| ()),
+        MENU_HISTORY => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+            if let Err(e) = app.emit(OPEN_HISTORY_EVENT, ()) {
+                log::warn!("发送事件 {} 失败: {}", OPEN_HISTORY_EVENT, e);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+    
+    if let Err(e) = result {
+        log::error!("托盘操作失败: {}", e);
+    }
+}
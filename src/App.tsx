@@ -13,6 +13,7 @@ import {
   usePermissions,
   useLanguage,
   useTranscriptHistory,
+  formatBytes,
 } from './hooks/useTranscription';
 import type { TranscriptRecord } from './hooks/useTranscription';
 
@@ -110,6 +111,13 @@ function App() {
 
     console.log('停止转录，文本内容:', textToSave?.substring(0, 100), '长度:', textToSave?.length);
 
+    // 后端已自动保存，只需刷新历史记录
+    if (result?.saved_record_id != null) {
+      console.log('转录已由后端自动保存，ID:', result.saved_record_id);
+      await history.loadHistory();
+      return;
+    }
+
     // 如果有内容，自动保存
     if (textToSave && textToSave.trim()) {
       try {
@@ -154,9 +162,9 @@ function App() {
   // 处理导出历史记录
   const handleExportRecord = useCallback(async (id: number) => {
     try {
-      const path = await history.exportRecord(id, 'txt');
-      console.log('已导出到:', path);
-      alert(`已导出到: ${path}`);
+      const result = await history.exportRecord(id, 'txt');
+      console.log('已导出到:', result.path);
+      alert(`已导出到: ${result.path}（${formatBytes(result.bytes)}）`);
     } catch (e) {
       console.error('导出失败:', e);
     }
@@ -230,6 +238,7 @@ function App() {
           <div className="history-tab-content">
             <HistoryPanel
               records={history.records}
+              stats={history.stats}
               isLoading={history.isLoading}
               selectedId={selectedRecord?.id ?? null}
               onSelect={handleSelectRecord}
@@ -240,7 +249,7 @@ function App() {
               <div className="history-detail">
                 <div className="history-detail-header">
                   <h3>转录详情</h3>
-                  <span className="history-detail-date">{selectedRecord.created_at}</span>
+                  <span className="history-detail-date">{selectedRecord.created_at_local}</span>
                 </div>
                 <div className="history-detail-content">
                   <p>{selectedRecord.content}</p>
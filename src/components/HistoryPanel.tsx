@@ -2,7 +2,7 @@
 // 历史记录侧边栏组件
 
 import { formatDuration } from '../hooks/useTranscription';
-import type { TranscriptRecord } from '../hooks/useTranscription';
+import type { TranscriptRecord, TranscriptStats } from '../hooks/useTranscription';
 
 // 删除图标
 function TrashIcon() {
@@ -24,6 +24,7 @@ function ExportIcon() {
 
 interface HistoryPanelProps {
     records: TranscriptRecord[];
+    stats?: TranscriptStats | null;
     isLoading: boolean;
     selectedId: number | null;
     onSelect: (record: TranscriptRecord) => void;
@@ -33,6 +34,7 @@ interface HistoryPanelProps {
 
 export function HistoryPanel({
     records,
+    stats,
     isLoading,
     selectedId,
     onSelect,
@@ -45,6 +47,7 @@ export function HistoryPanel({
                 <h2>历史记录</h2>
                 <span style={{ fontSize: 12, color: 'var(--color-text-muted)' }}>
                     {records.length} 条
+                    {stats && ` · ${stats.total_words} 字 · ${formatDuration(stats.total_duration_seconds)}`}
                 </span>
             </div>
 
@@ -65,7 +68,7 @@ export function HistoryPanel({
                             onClick={() => onSelect(record)}
                         >
                             <div className="history-item-header">
-                                <span className="history-item-date">{record.created_at}</span>
+                                <span className="history-item-date">{record.created_at_local}</span>
                                 <span className="history-item-duration">
                                     {formatDuration(record.duration_seconds)}
                                 </span>
@@ -58,7 +58,7 @@ interface SettingsProps {
     onLanguageChange: (code: string) => void;
     hasAllPermissions: boolean;
     permissions: PermissionStatus | null;
-    onRequestPermissions: () => void;
+    onRequestPermissions: (permission?: 'screen' | 'microphone' | 'speech') => void;
     onCheckPermissions: () => Promise<PermissionStatus>;
     isCheckingPermissions: boolean;
 }
@@ -171,7 +171,11 @@ export function Settings({
                             </button>
                             <button
                                 className="btn btn-primary"
-                                onClick={onRequestPermissions}
+                                onClick={() => onRequestPermissions(
+                                    permissions && permissions.audio_capture && !permissions.speech_recognition
+                                        ? 'speech'
+                                        : 'screen'
+                                )}
                             >
                                 打开系统设置
                             </button>
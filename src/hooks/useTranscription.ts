@@ -4,6 +4,7 @@
 
 import { useState, useCallback, useEffect, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 
 // 检测是否在 Tauri 环境中运行
 const isTauri = (): boolean => {
@@ -14,6 +15,29 @@ const isTauri = (): boolean => {
   }
 };
 
+// 后端错误（对应 Rust 端 AppError 的序列化结果）
+export interface AppErrorPayload {
+  kind: string;
+  message: string;
+}
+
+export class BackendError extends Error {
+  kind: string;
+
+  constructor(payload: AppErrorPayload) {
+    super(payload.message);
+    this.name = 'BackendError';
+    this.kind = payload.kind;
+  }
+
+  toString(): string {
+    return this.message;
+  }
+}
+
+const isAppErrorPayload = (e: unknown): e is AppErrorPayload =>
+  typeof e === 'object' && e !== null && 'kind' in e && 'message' in e;
+
 // 安全调用 invoke，在非 Tauri 环境返回默认值
 async function safeInvoke<T>(cmd: string, args?: Record<string, unknown>, defaultValue?: T): Promise<T> {
   if (!isTauri()) {
@@ -23,7 +47,11 @@ async function safeInvoke<T>(cmd: string, args?: Record<string, unknown>, defaul
     }
     throw new Error('Not in Tauri environment');
   }
-  return invoke<T>(cmd, args);
+  try {
+    return await invoke<T>(cmd, args);
+  } catch (e) {
+    throw isAppErrorPayload(e) ? new BackendError(e) : e;
+  }
 }
 
 // 类型定义
@@ -37,14 +65,106 @@ export interface TranscriptionStatus {
   latest_text: string;
   full_text: string;
   duration_seconds: number;
+  // 实际有音频输入的时长（不含暂停和音频中断）
+  active_duration_seconds?: number;
+  // 后端自动保存生成的记录 ID
+  saved_record_id?: number | null;
+  // 后端自动保存生成的完整记录
+  saved_record?: TranscriptRecord;
+  // 原生模块最近一次报告的错误
+  error?: string | null;
+  // 本次捕获开始的本地时间（YYYY-MM-DD HH:MM:SS）
+  started_at?: string | null;
+  // 本次会话是否检测到持续削波（音源音量过大）
+  is_clipping?: boolean;
+  // 当前（或最近一次）转录会话的 ID
+  session_id?: string | null;
+}
+
+// capture-started 事件负载
+export interface CaptureStartedPayload {
+  session_id: string;
+  started_at: string | null;
+  append_target: number | null;
+}
+
+// capture-cancelled 事件负载
+export interface CaptureCancelledPayload {
+  session_id: string | null;
+}
+
+// capture-stopped 事件负载
+export interface CaptureStoppedPayload {
+  session_id: string | null;
+  // 停止原因：手动、停止关键词或达到会话时长上限
+  reason: 'manual' | 'stop_keyword' | 'max_duration';
+  duration_seconds: number;
+  active_duration_seconds: number;
+  record_id: number | null;
+}
+
+export interface TranscriptionUpdate {
+  latest_text: string;
+  full_text: string;
+  is_final: boolean;
+  // latest_text 与上一次事件相同的前缀长度，只需对之后的部分做动画
+  stable_prefix_len: number;
+  // latest_text 中新增或被修订的尾部
+  revised_suffix: string;
+  // 产生该更新的转录会话 ID
+  session_id: string | null;
+}
+
+// 带会话 ID 的事件负载（transcription-error、audio-level 等）
+export interface SessionEvent<T> {
+  session_id: string | null;
+  payload: T;
 }
 
 export interface TranscriptRecord {
   id: number;
   content: string;
   language: string;
+  // 创建时间（RFC 3339 UTC）
   created_at: string;
+  // 创建时间的本地显示字符串（YYYY-MM-DD HH:MM:SS）
+  created_at_local: string;
   duration_seconds: number;
+  tags?: string[];
+  is_favorite?: boolean;
+  // 用户评分（1 ~ 5）
+  rating?: number | null;
+  // 用户备注
+  note?: string | null;
+  // 生成该记录的转录会话 ID
+  session_id?: string | null;
+}
+
+// get_transcript_chunk 返回的一块内容（字节偏移，已对齐到 UTF-8 字符）
+export interface TranscriptChunk {
+  id: number;
+  byte_offset: number;
+  content: string;
+  // 下一块的起始字节偏移
+  next_offset: number;
+  total_bytes: number;
+  has_more: boolean;
+}
+
+// search_transcripts_ranked 的单条结果
+export interface RankedSearchResult {
+  record: TranscriptRecord;
+  // 相关度评分，越高越相关
+  score: number;
+  // 首个匹配位置前后的上下文预览
+  snippet: string;
+}
+
+export interface TranscriptStats {
+  total_records: number;
+  total_duration_seconds: number;
+  total_words: number;
+  records_by_language: [string, number][];
 }
 
 export interface LanguageOption {
@@ -52,6 +172,30 @@ export interface LanguageOption {
   name: string;
 }
 
+// 当前语言的识别方式（get_recognition_mode）
+export interface RecognitionModeInfo {
+  language: string;
+  mode: 'on_device' | 'requires_network' | 'unsupported';
+  on_device_required: boolean;
+  // 强制端侧识别但当前语言需要联网，开始转录会失败
+  conflict: boolean;
+}
+
+// 单条记录的导出结果
+export interface ExportResult {
+  path: string;
+  format: string;
+  // 导出文件大小（字节）
+  bytes: number;
+}
+
+// 格式化文件大小
+export function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+}
+
 // 格式化持续时间
 export function formatDuration(seconds: number): string {
   const mins = Math.floor(seconds / 60);
@@ -73,7 +217,8 @@ export function useTranscription() {
 
   // 计时器
   const timerRef = useRef<ReturnType<typeof setInterval> | null>(null);
-  const pollRef = useRef<ReturnType<typeof setInterval> | null>(null);
+  const unlistenRef = useRef<UnlistenFn | null>(null);
+  const unlistenErrorRef = useRef<UnlistenFn | null>(null);
   // 保存上一次的 fullText，用于检测变化
   const prevFullTextRef = useRef('');
 
@@ -97,29 +242,44 @@ export function useTranscription() {
         setDuration(prev => prev + 1);
       }, 1000);
 
-      // 开始轮询状态（每 300ms，更频繁以获取实时更新）
-      pollRef.current = setInterval(async () => {
-        try {
-          const status = await safeInvoke<TranscriptionStatus>('get_transcription_status', undefined, {
-            is_capturing: true,
-            latest_text: '',
-            full_text: '',
-            duration_seconds: 0,
-          });
-
-          // 更新最新转录文本
-          setLatestText(status.latest_text);
-
-          // 如果 full_text 有变化，说明有新的确认文本
-          if (status.full_text !== prevFullTextRef.current) {
-            setFullText(status.full_text);
-            setAccumulatedText(status.full_text);
-            prevFullTextRef.current = status.full_text;
-          }
-        } catch (e) {
-          console.error('轮询状态失败:', e);
+      // 应用转录更新（来自事件或初始状态）
+      const applyUpdate = (latest: string, full: string) => {
+        setLatestText(latest);
+
+        // 如果 full_text 有变化，说明有新的确认文本
+        if (full !== prevFullTextRef.current) {
+          setFullText(full);
+          setAccumulatedText(full);
+          prevFullTextRef.current = full;
+        }
+      };
+
+      // 订阅后端推送的转录更新事件
+      if (isTauri()) {
+        unlistenRef.current = await listen<TranscriptionUpdate>('transcription-update', (event) => {
+          applyUpdate(event.payload.latest_text, event.payload.full_text);
+        });
+        // 订阅识别错误事件，避免捕获静默中断
+        unlistenErrorRef.current = await listen<SessionEvent<string>>('transcription-error', (event) => {
+          setError(event.payload.payload);
+        });
+      }
+
+      // 获取初始状态，避免遗漏订阅前的更新
+      try {
+        const status = await safeInvoke<TranscriptionStatus>('get_transcription_status', undefined, {
+          is_capturing: true,
+          latest_text: '',
+          full_text: '',
+          duration_seconds: 0,
+        });
+        applyUpdate(status.latest_text, status.full_text);
+        if (status.error) {
+          setError(status.error);
         }
-      }, 300);
+      } catch (e) {
+        console.error('获取转录状态失败:', e);
+      }
 
     } catch (e) {
       setError(String(e));
@@ -140,10 +300,14 @@ export function useTranscription() {
         timerRef.current = null;
       }
 
-      // 清除轮询
-      if (pollRef.current) {
-        clearInterval(pollRef.current);
-        pollRef.current = null;
+      // 取消事件订阅
+      if (unlistenRef.current) {
+        unlistenRef.current();
+        unlistenRef.current = null;
+      }
+      if (unlistenErrorRef.current) {
+        unlistenErrorRef.current();
+        unlistenErrorRef.current = null;
       }
 
       const result = await safeInvoke<TranscriptionStatus>('stop_transcription', undefined, {
@@ -191,6 +355,39 @@ export function useTranscription() {
     }
   }, [latestText, fullText, duration, accumulatedText]);
 
+  // 取消转录：丢弃本次会话的文本，后端不保存记录
+  const cancelTranscription = useCallback(async () => {
+    try {
+      setIsLoading(true);
+
+      if (timerRef.current) {
+        clearInterval(timerRef.current);
+        timerRef.current = null;
+      }
+      if (unlistenRef.current) {
+        unlistenRef.current();
+        unlistenRef.current = null;
+      }
+      if (unlistenErrorRef.current) {
+        unlistenErrorRef.current();
+        unlistenErrorRef.current = null;
+      }
+
+      await safeInvoke('cancel_transcription');
+    } catch (e) {
+      setError(String(e));
+      console.error('取消转录失败:', e);
+    } finally {
+      setIsCapturing(false);
+      setLatestText('');
+      setFullText('');
+      setAccumulatedText('');
+      setDuration(0);
+      prevFullTextRef.current = '';
+      setIsLoading(false);
+    }
+  }, []);
+
   // 获取当前累积的完整文本（用于外部获取）
   const getCurrentText = useCallback(() => {
     let text = accumulatedText || fullText;
@@ -213,7 +410,8 @@ export function useTranscription() {
   useEffect(() => {
     return () => {
       if (timerRef.current) clearInterval(timerRef.current);
-      if (pollRef.current) clearInterval(pollRef.current);
+      if (unlistenRef.current) unlistenRef.current();
+      if (unlistenErrorRef.current) unlistenErrorRef.current();
     };
   }, []);
 
@@ -227,6 +425,7 @@ export function useTranscription() {
     isLoading,
     startTranscription,
     stopTranscription,
+    cancelTranscription,
     simulateTranscription,
     getCurrentText,
     formattedDuration: formatDuration(duration),
@@ -258,9 +457,9 @@ export function usePermissions() {
     }
   }, []);
 
-  const requestPermissions = useCallback(async () => {
+  const requestPermissions = useCallback(async (permission: 'screen' | 'microphone' | 'speech' = 'screen') => {
     try {
-      await safeInvoke('request_permissions');
+      await safeInvoke('request_permissions', { permission });
     } catch (e) {
       console.error('请求权限失败:', e);
     }
@@ -348,6 +547,7 @@ export function useLanguage() {
 // 历史记录 Hook
 export function useTranscriptHistory() {
   const [records, setRecords] = useState<TranscriptRecord[]>([]);
+  const [stats, setStats] = useState<TranscriptStats | null>(null);
   const [isLoading, setIsLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
@@ -359,6 +559,8 @@ export function useTranscriptHistory() {
       const result = await safeInvoke<TranscriptRecord[]>('get_transcript_history', undefined, []);
       // 按时间倒序排列
       setRecords(result.sort((a, b) => b.id - a.id));
+      const statistics = await safeInvoke<TranscriptStats | null>('get_statistics', undefined, null);
+      setStats(statistics);
     } catch (e) {
       setError(String(e));
       console.error('加载历史记录失败:', e);
@@ -398,8 +600,7 @@ export function useTranscriptHistory() {
   // 导出记录
   const exportRecord = useCallback(async (id: number, format: string = 'txt') => {
     try {
-      const path = await safeInvoke<string>('export_transcript', { id, format });
-      return path;
+      return await safeInvoke<ExportResult>('export_transcript', { id, format });
     } catch (e) {
       console.error('导出记录失败:', e);
       throw e;
@@ -412,6 +613,7 @@ export function useTranscriptHistory() {
 
   return {
     records,
+    stats,
     isLoading,
     error,
     loadHistory,
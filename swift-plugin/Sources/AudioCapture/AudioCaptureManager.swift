@@ -7,11 +7,11 @@ import ScreenCaptureKit
 import AVFoundation
 import CoreMedia
 
-/// 音频样本回调类型
-public typealias AudioSampleCallback = @convention(c) (UnsafePointer<Float>, Int32, Double) -> Void
+/// 音频样本回调类型（样本、样本总数、时间戳、采样率、声道数）
+public typealias AudioSampleCallback = @convention(c) (UnsafePointer<Float>, Int32, Double, Double, Int32) -> Void
 
 /// 转录结果回调类型
-public typealias TranscriptionCallback = @convention(c) (UnsafePointer<CChar>, Bool) -> Void
+public typealias TranscriptionCallback = @convention(c) (UnsafePointer<CChar>, Bool, Float) -> Void
 
 /// 错误回调类型
 public typealias ErrorCallback = @convention(c) (UnsafePointer<CChar>) -> Void
@@ -43,6 +43,9 @@ public typealias ErrorCallback = @convention(c) (UnsafePointer<CChar>) -> Void
     // 状态
     private(set) var status: CaptureStatus = .idle
     
+    // 只捕获该应用的音频（bundle ID），nil 表示捕获整个系统的音频
+    private var targetBundleID: String?
+    
     // 音频格式设置
     private let sampleRate: Double = 16000.0  // 16kHz 适合语音识别
     private let channelCount: Int = 1          // 单声道
@@ -64,6 +67,39 @@ public typealias ErrorCallback = @convention(c) (UnsafePointer<CChar>) -> Void
         self.errorCallback = callback
     }
     
+    /// 设置捕获目标应用（bundle ID），nil 表示整个系统，下次开始捕获时生效
+    @objc public func setTarget(_ bundleID: String?) {
+        targetBundleID = bundleID
+    }
+    
+    /// 列出可单独捕获音频的应用，返回 JSON 数组 [{ name, bundle_id }]，按名称排序
+    /// 不包含本应用和没有 bundle ID 的进程
+    @objc public func listTargetsJSON() async -> String {
+        guard let content = try? await SCShareableContent.excludingDesktopWindows(false, onScreenWindowsOnly: false) else {
+            return "[]"
+        }
+        
+        let ownBundleID = Bundle.main.bundleIdentifier
+        var seen = Set<String>()
+        let apps = content.applications
+            .filter { !$0.bundleIdentifier.isEmpty && $0.bundleIdentifier != ownBundleID }
+            .filter { seen.insert($0.bundleIdentifier).inserted }
+            .sorted { $0.applicationName.localizedCaseInsensitiveCompare($1.applicationName) == .orderedAscending }
+        
+        let list: [[String: Any]] = apps.map { app in
+            [
+                "name": app.applicationName.isEmpty ? app.bundleIdentifier : app.applicationName,
+                "bundle_id": app.bundleIdentifier,
+            ]
+        }
+        
+        guard let data = try? JSONSerialization.data(withJSONObject: list),
+              let json = String(data: data, encoding: .utf8) else {
+            return "[]"
+        }
+        return json
+    }
+    
     /// 检查屏幕录制权限
     @objc public func checkPermission() async -> Bool {
         do {
@@ -94,8 +130,19 @@ public typealias ErrorCallback = @convention(c) (UnsafePointer<CChar>) -> Void
                 return false
             }
             
-            // 创建内容过滤器 - 捕获整个显示器的音频
-            let filter = SCContentFilter(display: display, excludingApplications: [], exceptingWindows: [])
+            // 创建内容过滤器 - 设置了目标应用时只捕获该应用的音频，否则捕获整个显示器的音频
+            let filter: SCContentFilter
+            if let bundleID = targetBundleID {
+                guard let app = content.applications.first(where: { $0.bundleIdentifier == bundleID }) else {
+                    reportError("未找到要捕获的应用（可能未运行）: \(bundleID)")
+                    status = .idle
+                    return false
+                }
+                filter = SCContentFilter(display: display, including: [app], exceptingWindows: [])
+                print("[AudioCapture] 只捕获应用音频: \(app.applicationName) (\(bundleID))")
+            } else {
+                filter = SCContentFilter(display: display, excludingApplications: [], exceptingWindows: [])
+            }
             self.contentFilter = filter
             
             // 配置流 - 只捕获音频
@@ -191,12 +238,29 @@ public typealias ErrorCallback = @convention(c) (UnsafePointer<CChar>) -> Void
         let presentationTime = CMSampleBufferGetPresentationTimeStamp(sampleBuffer)
         let timestamp = CMTimeGetSeconds(presentationTime)
         
+        // 读取实际的采样率和声道数，系统可能不按配置输出
+        var actualSampleRate = sampleRate
+        var channels = Int32(channelCount)
+        var nonInterleaved = false
+        if let formatDescription = CMSampleBufferGetFormatDescription(sampleBuffer),
+           let asbd = CMAudioFormatDescriptionGetStreamBasicDescription(formatDescription)?.pointee {
+            actualSampleRate = asbd.mSampleRate
+            channels = Int32(max(asbd.mChannelsPerFrame, 1))
+            nonInterleaved = asbd.mFormatFlags & kAudioFormatFlagIsNonInterleaved != 0
+        }
+        
         // 将数据转换为 Float 数组（假设是 Float32 格式）
-        let floatCount = length / MemoryLayout<Float>.size
+        var floatCount = length / MemoryLayout<Float>.size
         let floatPointer = UnsafeRawPointer(data).bindMemory(to: Float.self, capacity: floatCount)
         
+        // 非交错的多声道数据按声道依次排列，只取第一个声道
+        if nonInterleaved && channels > 1 {
+            floatCount /= Int(channels)
+            channels = 1
+        }
+        
         // 调用回调
-        audioCallback?(floatPointer, Int32(floatCount), timestamp)
+        audioCallback?(floatPointer, Int32(floatCount), timestamp, actualSampleRate, channels)
     }
     
     /// 报告错误
@@ -210,6 +274,17 @@ public typealias ErrorCallback = @convention(c) (UnsafePointer<CChar>) -> Void
 
 // MARK: - C 接口（供 Rust FFI 调用）
 
+/// 原生接口版本，接口签名变化时递增；Rust 侧初始化时比对，不一致说明链接了过期的库
+private let audioBridgeABIVersion: Int32 = 1
+
+/// 健康检查：调用 Swift 运行时和 Foundation，返回接口版本
+/// Rust 侧初始化时首先调用，返回值不符时不再调用其他接口
+@_cdecl("audio_bridge_health_check")
+public func audioBridge_healthCheck() -> Int32 {
+    _ = ProcessInfo.processInfo.operatingSystemVersion
+    return audioBridgeABIVersion
+}
+
 /// 检查屏幕录制权限
 @_cdecl("audio_capture_check_permission")
 public func audioCapture_checkPermission() -> Bool {
@@ -259,6 +334,27 @@ public func audioCapture_getStatus() -> Int32 {
     return AudioCaptureManager.shared.getStatus().rawValue
 }
 
+/// 设置捕获目标应用（bundle ID），NULL 表示整个系统
+@_cdecl("audio_capture_set_target")
+public func audioCapture_setTarget(_ bundleID: UnsafePointer<CChar>?) {
+    AudioCaptureManager.shared.setTarget(bundleID.map { String(cString: $0) })
+}
+
+/// 列出可捕获的应用，返回 JSON 字符串，调用方需使用 audio_free_string 释放
+@_cdecl("audio_list_capture_targets")
+public func audioCapture_listTargets() -> UnsafeMutablePointer<CChar>? {
+    let semaphore = DispatchSemaphore(value: 0)
+    var json = "[]"
+    
+    Task {
+        json = await AudioCaptureManager.shared.listTargetsJSON()
+        semaphore.signal()
+    }
+    
+    semaphore.wait()
+    return strdup(json)
+}
+
 /// 设置音频回调
 @_cdecl("audio_capture_set_callback")
 public func audioCapture_setCallback(_ callback: @escaping AudioSampleCallback) {
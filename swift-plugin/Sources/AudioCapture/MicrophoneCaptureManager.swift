@@ -0,0 +1,323 @@
+// MicrophoneCaptureManager.swift
+// 麦克风音频捕获管理器
+// 使用 AVAudioEngine 捕获麦克风输入并转换为 16kHz 单声道
+
+import Foundation
+import AVFoundation
+import CoreAudio
+import AudioToolbox
+
+/// 麦克风捕获管理器 - 负责从默认输入设备捕获音频
+@objc public class MicrophoneCaptureManager: NSObject {
+
+    // MARK: - 单例
+    @objc public static let shared = MicrophoneCaptureManager()
+    
+    // MARK: - 属性
+    private let audioEngine = AVAudioEngine()
+    private var converter: AVAudioConverter?
+    
+    // 回调
+    private var audioCallback: AudioSampleCallback?
+    private var errorCallback: ErrorCallback?
+    
+    // 状态
+    private(set) var status: CaptureStatus = .idle
+    
+    // 选定的输入设备 UID，nil 表示使用系统默认设备
+    private var deviceUID: String?
+    
+    // 输出格式：16kHz, 单声道, Float32（与语音识别一致）
+    private let outputFormat = AVAudioFormat(
+        commonFormat: .pcmFormatFloat32,
+        sampleRate: 16000.0,
+        channels: 1,
+        interleaved: false
+    )
+    
+    // MARK: - 初始化
+    private override init() {
+        super.init()
+    }
+    
+    // MARK: - 公共方法
+    
+    /// 设置音频样本回调
+    @objc public func setAudioCallback(_ callback: @escaping AudioSampleCallback) {
+        self.audioCallback = callback
+    }
+    
+    /// 设置错误回调
+    @objc public func setErrorCallback(_ callback: @escaping ErrorCallback) {
+        self.errorCallback = callback
+    }
+    
+    /// 设置输入设备，nil 表示使用系统默认设备，下次开始捕获时生效
+    @objc public func setDevice(_ uid: String?) {
+        deviceUID = uid
+    }
+    
+    /// 列出可用的音频输入设备，返回 JSON 数组 [{ id, name, is_default }]
+    @objc public func listDevicesJSON() -> String {
+        let devices: [AVCaptureDevice]
+        if #available(macOS 14.0, *) {
+            devices = AVCaptureDevice.DiscoverySession(
+                deviceTypes: [.microphone, .external],
+                mediaType: .audio,
+                position: .unspecified
+            ).devices
+        } else {
+            devices = AVCaptureDevice.DiscoverySession(
+                deviceTypes: [.builtInMicrophone, .externalUnknown],
+                mediaType: .audio,
+                position: .unspecified
+            ).devices
+        }
+        let defaultUID = AVCaptureDevice.default(for: .audio)?.uniqueID
+        
+        let list: [[String: Any]] = devices.map { device in
+            [
+                "id": device.uniqueID,
+                "name": device.localizedName,
+                "is_default": device.uniqueID == defaultUID,
+            ]
+        }
+        
+        guard let data = try? JSONSerialization.data(withJSONObject: list),
+              let json = String(data: data, encoding: .utf8) else {
+            return "[]"
+        }
+        return json
+    }
+    
+    /// 检查麦克风权限
+    @objc public func checkPermission(completion: @escaping (Bool) -> Void) {
+        switch AVCaptureDevice.authorizationStatus(for: .audio) {
+        case .authorized:
+            completion(true)
+        case .notDetermined:
+            AVCaptureDevice.requestAccess(for: .audio) { granted in
+                completion(granted)
+            }
+        default:
+            completion(false)
+        }
+    }
+    
+    /// 开始捕获麦克风音频
+    @objc public func startCapture() -> Bool {
+        guard status == .idle else {
+            reportError("麦克风捕获已在进行中")
+            return false
+        }
+        guard let outputFormat = outputFormat else {
+            reportError("无法创建输出音频格式")
+            return false
+        }
+        
+        status = .starting
+        
+        let inputNode = audioEngine.inputNode
+        
+        // 切换到选定的输入设备（需在读取输入格式之前）
+        if let uid = deviceUID {
+            guard let deviceID = Self.audioDeviceID(forUID: uid), let audioUnit = inputNode.audioUnit else {
+                reportError("未找到音频输入设备: \(uid)")
+                status = .idle
+                return false
+            }
+            var id = deviceID
+            let err = AudioUnitSetProperty(
+                audioUnit,
+                kAudioOutputUnitProperty_CurrentDevice,
+                kAudioUnitScope_Global,
+                0,
+                &id,
+                UInt32(MemoryLayout<AudioDeviceID>.size)
+            )
+            if err != noErr {
+                reportError("切换音频输入设备失败: \(err)")
+                status = .idle
+                return false
+            }
+        }
+        
+        let inputFormat = inputNode.outputFormat(forBus: 0)
+        
+        guard let converter = AVAudioConverter(from: inputFormat, to: outputFormat) else {
+            reportError("无法创建音频格式转换器")
+            status = .idle
+            return false
+        }
+        self.converter = converter
+        
+        inputNode.installTap(onBus: 0, bufferSize: 4096, format: inputFormat) { [weak self] buffer, time in
+            self?.processBuffer(buffer, time: time)
+        }
+        
+        do {
+            audioEngine.prepare()
+            try audioEngine.start()
+        } catch {
+            inputNode.removeTap(onBus: 0)
+            self.converter = nil
+            reportError("启动麦克风捕获失败: \(error.localizedDescription)")
+            status = .idle
+            return false
+        }
+        
+        status = .capturing
+        print("[MicrophoneCapture] 麦克风捕获已开始")
+        return true
+    }
+    
+    /// 停止捕获
+    @objc public func stopCapture() {
+        guard status == .capturing else { return }
+        
+        status = .stopping
+        audioEngine.inputNode.removeTap(onBus: 0)
+        audioEngine.stop()
+        converter = nil
+        status = .idle
+        print("[MicrophoneCapture] 麦克风捕获已停止")
+    }
+    
+    /// 获取当前状态
+    @objc public func getStatus() -> CaptureStatus {
+        return status
+    }
+    
+    // MARK: - 私有方法
+    
+    /// 将设备 UID 转换为 CoreAudio 设备 ID
+    private static func audioDeviceID(forUID uid: String) -> AudioDeviceID? {
+        var address = AudioObjectPropertyAddress(
+            mSelector: kAudioHardwarePropertyTranslateUIDToDevice,
+            mScope: kAudioObjectPropertyScopeGlobal,
+            mElement: kAudioObjectPropertyElementMain
+        )
+        var cfUID = uid as CFString
+        var deviceID = AudioDeviceID(kAudioObjectUnknown)
+        var size = UInt32(MemoryLayout<AudioDeviceID>.size)
+        
+        let err = withUnsafeMutablePointer(to: &cfUID) { uidPointer in
+            AudioObjectGetPropertyData(
+                AudioObjectID(kAudioObjectSystemObject),
+                &address,
+                UInt32(MemoryLayout<CFString>.size),
+                uidPointer,
+                &size,
+                &deviceID
+            )
+        }
+        
+        guard err == noErr, deviceID != kAudioObjectUnknown else { return nil }
+        return deviceID
+    }
+    
+    /// 将输入缓冲区转换为 16kHz 单声道并回调
+    private func processBuffer(_ buffer: AVAudioPCMBuffer, time: AVAudioTime) {
+        guard status == .capturing else { return }
+        guard let converter = converter, let outputFormat = outputFormat else { return }
+        
+        let ratio = outputFormat.sampleRate / buffer.format.sampleRate
+        let capacity = AVAudioFrameCount(Double(buffer.frameLength) * ratio) + 1
+        guard let converted = AVAudioPCMBuffer(pcmFormat: outputFormat, frameCapacity: capacity) else { return }
+        
+        var consumed = false
+        var error: NSError?
+        converter.convert(to: converted, error: &error) { _, outStatus in
+            if consumed {
+                outStatus.pointee = .noDataNow
+                return nil
+            }
+            consumed = true
+            outStatus.pointee = .haveData
+            return buffer
+        }
+        
+        if let error = error {
+            reportError("音频格式转换失败: \(error.localizedDescription)")
+            return
+        }
+        
+        guard let channelData = converted.floatChannelData?[0], converted.frameLength > 0 else { return }
+        
+        let timestamp = AVAudioTime.seconds(forHostTime: time.hostTime)
+        audioCallback?(channelData, Int32(converted.frameLength), timestamp, outputFormat.sampleRate, 1)
+    }
+    
+    /// 报告错误
+    private func reportError(_ message: String) {
+        print("[MicrophoneCapture] 错误: \(message)")
+        message.withCString { cString in
+            errorCallback?(cString)
+        }
+    }
+}
+
+// MARK: - C 接口（供 Rust FFI 调用）
+
+/// 检查麦克风权限
+@_cdecl("mic_capture_check_permission")
+public func micCapture_checkPermission() -> Bool {
+    let semaphore = DispatchSemaphore(value: 0)
+    var result = false
+    
+    MicrophoneCaptureManager.shared.checkPermission { granted in
+        result = granted
+        semaphore.signal()
+    }
+    
+    semaphore.wait()
+    return result
+}
+
+/// 开始麦克风捕获
+@_cdecl("mic_capture_start")
+public func micCapture_start() -> Bool {
+    return MicrophoneCaptureManager.shared.startCapture()
+}
+
+/// 停止麦克风捕获
+@_cdecl("mic_capture_stop")
+public func micCapture_stop() {
+    MicrophoneCaptureManager.shared.stopCapture()
+}
+
+/// 获取麦克风捕获状态
+@_cdecl("mic_capture_get_status")
+public func micCapture_getStatus() -> Int32 {
+    return MicrophoneCaptureManager.shared.getStatus().rawValue
+}
+
+/// 设置音频回调
+@_cdecl("mic_capture_set_callback")
+public func micCapture_setCallback(_ callback: @escaping AudioSampleCallback) {
+    MicrophoneCaptureManager.shared.setAudioCallback(callback)
+}
+
+/// 设置错误回调
+@_cdecl("mic_capture_set_error_callback")
+public func micCapture_setErrorCallback(_ callback: @escaping ErrorCallback) {
+    MicrophoneCaptureManager.shared.setErrorCallback(callback)
+}
+
+/// 设置麦克风输入设备（传入 NULL 使用系统默认设备）
+@_cdecl("mic_capture_set_device")
+public func micCapture_setDevice(_ uid: UnsafePointer<CChar>?) {
+    MicrophoneCaptureManager.shared.setDevice(uid.map { String(cString: $0) })
+}
+
+/// 列出音频输入设备，返回 JSON 字符串，调用方需使用 audio_free_string 释放
+@_cdecl("audio_list_devices")
+public func audio_listDevices() -> UnsafeMutablePointer<CChar>? {
+    return strdup(MicrophoneCaptureManager.shared.listDevicesJSON())
+}
+
+/// 释放由 Swift 分配的字符串
+@_cdecl("audio_free_string")
+public func audio_freeString(_ ptr: UnsafeMutablePointer<CChar>?) {
+    free(ptr)
+}
@@ -7,7 +7,19 @@ import Speech
 import AVFoundation
 
 /// 转录结果回调类型
-public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CChar>, Bool) -> Void
+public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CChar>, Bool, Float) -> Void
+
+/// 文件转录进度回调类型（0.0 ~ 1.0）
+public typealias FileProgressCallback = @convention(c) (Double) -> Void
+
+/// 文件转录等待最终结果的最长时间（秒）
+private let fileFinalResultTimeout: Double = 60
+
+/// 预热时等待端侧模型加载完成的最长时间（秒）
+private let prewarmTimeout: Double = 10
+
+/// 预热时送入识别器的静音时长（秒）
+private let prewarmSilenceSeconds: Double = 0.5
 
 /// 语音识别状态
 @objc public enum RecognitionStatus: Int32 {
@@ -42,6 +54,18 @@ public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CCh
     // 当前语言
     private var currentLocale: Locale = Locale(identifier: "zh-CN")
     
+    // 是否强制使用端侧识别（不允许回退到服务器识别）
+    private var requireOnDevice = false
+    
+    // 自定义词汇（上下文短语），提高专有名词识别准确率
+    private var contextualStrings: [String] = []
+    
+    // 识别任务类型提示，默认连续口述
+    private var taskHint: SFSpeechRecognitionTaskHint = .dictation
+    
+    // 文件转录时等待最终结果的信号量
+    private var finalResultSemaphore: DispatchSemaphore?
+    
     // MARK: - 初始化
     private override init() {
         super.init()
@@ -78,11 +102,34 @@ public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CCh
         speechRecognizer = SFSpeechRecognizer(locale: currentLocale)
     }
     
+    /// 设置自定义词汇，下次开始识别时生效
+    @objc public func setContextualStrings(_ phrases: [String]) {
+        contextualStrings = phrases
+    }
+    
+    /// 设置是否强制端侧识别
+    @objc public func setOnDeviceRequired(_ required: Bool) {
+        requireOnDevice = required
+    }
+    
+    /// 设置识别任务类型提示（SFSpeechRecognitionTaskHint 原始值），无效值按口述处理，下次开始识别时生效
+    @objc public func setTaskHint(_ rawValue: Int) {
+        taskHint = SFSpeechRecognitionTaskHint(rawValue: rawValue) ?? .dictation
+    }
+    
     /// 获取支持的语言列表
     @objc public func getSupportedLanguages() -> [String] {
         return SFSpeechRecognizer.supportedLocales().map { $0.identifier }
     }
     
+    /// 检查本机是否支持指定语言的识别
+    @objc public func supportsLanguage(_ languageCode: String) -> Bool {
+        let normalized = languageCode.replacingOccurrences(of: "_", with: "-").lowercased()
+        return SFSpeechRecognizer.supportedLocales().contains { locale in
+            locale.identifier.replacingOccurrences(of: "_", with: "-").lowercased() == normalized
+        }
+    }
+    
     /// 检查语音识别权限
     @objc public func checkPermission(completion: @escaping (Bool) -> Void) {
         SFSpeechRecognizer.requestAuthorization { status in
@@ -109,8 +156,8 @@ public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CCh
         
         status = .starting
         
-        // 创建语音识别器
-        guard let recognizer = SFSpeechRecognizer(locale: currentLocale) else {
+        // 创建语音识别器（已预热或设置语言时创建的同语言识别器直接复用）
+        guard let recognizer = currentRecognizer() else {
             reportError("无法创建语音识别器，语言：\(currentLocale.identifier)")
             status = .idle
             return false
@@ -122,6 +169,12 @@ public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CCh
             return false
         }
         
+        if requireOnDevice && !recognizer.supportsOnDeviceRecognition {
+            reportError("当前语言不支持端侧识别：\(currentLocale.identifier)")
+            status = .idle
+            return false
+        }
+        
         speechRecognizer = recognizer
         
         // 创建识别请求
@@ -135,6 +188,8 @@ public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CCh
         // 配置请求 - 使用端侧识别
         request.shouldReportPartialResults = true
         request.requiresOnDeviceRecognition = recognizer.supportsOnDeviceRecognition
+        request.contextualStrings = contextualStrings
+        request.taskHint = taskHint
         
         // 启用标点符号（iOS 16+ / macOS 13+）
         if #available(macOS 13.0, iOS 16.0, *) {
@@ -159,9 +214,11 @@ public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CCh
                 let nsError = error as NSError
                 if nsError.domain == "kAFAssistantErrorDomain" && nsError.code == 1110 {
                     // 正常结束，不是错误
+                    self.finalResultSemaphore?.signal()
                     return
                 }
                 self.reportError("识别错误: \(error.localizedDescription)")
+                self.finalResultSemaphore?.signal()
                 return
             }
             
@@ -169,21 +226,28 @@ public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CCh
                 let fullTranscription = result.bestTranscription.formattedString
                 let isFinal = result.isFinal
                 
+                // 计算各片段置信度的平均值（部分结果的置信度通常为 0）
+                let segments = result.bestTranscription.segments
+                let confidence: Float = segments.isEmpty
+                    ? 0
+                    : segments.map { $0.confidence }.reduce(0, +) / Float(segments.count)
+                
                 // 对于非最终结果，只发送变化的部分
                 // 对于最终结果，发送完整内容
                 if isFinal {
                     // 最终结果：发送完整转录
                     fullTranscription.withCString { cString in
-                        self.transcriptionCallback?(cString, true)
+                        self.transcriptionCallback?(cString, true, confidence)
                     }
                     lastSentLength = fullTranscription.count
                     print("[SpeechRecognizer] 最终结果: \(fullTranscription)")
+                    self.finalResultSemaphore?.signal()
                 } else {
                     // 部分结果：发送完整的当前转录（让 Rust 层处理差异）
                     // 只有内容有变化时才发送
                     if fullTranscription.count != lastSentLength {
                         fullTranscription.withCString { cString in
-                            self.transcriptionCallback?(cString, false)
+                            self.transcriptionCallback?(cString, false, confidence)
                         }
                         lastSentLength = fullTranscription.count
                     }
@@ -219,6 +283,152 @@ public typealias TranscriptionResultCallback = @convention(c) (UnsafePointer<CCh
         recognitionRequest?.append(buffer)
     }
     
+    /// 当前语言的识别器：复用已创建的同语言识别器，否则新建
+    private func currentRecognizer() -> SFSpeechRecognizer? {
+        if let existing = speechRecognizer, existing.locale.identifier == currentLocale.identifier {
+            return existing
+        }
+        return SFSpeechRecognizer(locale: currentLocale)
+    }
+    
+    /// 预热识别器：创建当前语言的识别器，支持端侧识别时用一小段静音跑一次端侧识别，
+    /// 让系统提前加载（必要时下载）端侧模型，之后第一次开始识别不再等待初始化
+    /// 不启动音频捕获，也不触发转录回调
+    /// - Returns: -1 失败，0 识别器就绪但端侧模型不可用，1 端侧模型已就绪
+    @objc public func prewarm() -> Int32 {
+        guard status == .idle else {
+            return -1
+        }
+        guard let recognizer = currentRecognizer(), recognizer.isAvailable else {
+            print("[SpeechRecognizer] 预热失败：识别器不可用，语言：\(currentLocale.identifier)")
+            return -1
+        }
+        speechRecognizer = recognizer
+        
+        guard recognizer.supportsOnDeviceRecognition,
+              let format = audioFormat,
+              let buffer = AVAudioPCMBuffer(
+                  pcmFormat: format,
+                  frameCapacity: AVAudioFrameCount(format.sampleRate * prewarmSilenceSeconds)
+              ) else {
+            print("[SpeechRecognizer] 预热完成（不支持端侧识别）")
+            return 0
+        }
+        buffer.frameLength = buffer.frameCapacity
+        
+        let request = SFSpeechAudioBufferRecognitionRequest()
+        request.requiresOnDeviceRecognition = true
+        request.shouldReportPartialResults = false
+        
+        let semaphore = DispatchSemaphore(value: 0)
+        var ready = false
+        let task = recognizer.recognitionTask(with: request) { result, error in
+            if let error = error as NSError? {
+                // 静音没有识别结果属于正常结束，说明模型已加载
+                ready = error.domain == "kAFAssistantErrorDomain" && error.code == 1110
+                semaphore.signal()
+            } else if result?.isFinal == true {
+                ready = true
+                semaphore.signal()
+            }
+        }
+        request.append(buffer)
+        request.endAudio()
+        
+        if semaphore.wait(timeout: .now() + prewarmTimeout) == .timedOut {
+            task.cancel()
+            print("[SpeechRecognizer] 预热超时，端侧模型可能仍在下载")
+            return 0
+        }
+        print("[SpeechRecognizer] 预热完成，端侧模型\(ready ? "已就绪" : "不可用")")
+        return ready ? 1 : 0
+    }
+    
+    /// 解码音频文件（WAV / M4A 等 AVAudioFile 支持的格式），转换为 16kHz 单声道后按块送入识别器
+    /// 需先调用 startRecognition；送完后结束音频输入并等待最终结果
+    /// - Returns: 音频时长（秒），失败时返回 -1
+    @objc public func transcribeFile(_ path: String, progress: FileProgressCallback?) -> Double {
+        guard status == .recognizing, let targetFormat = audioFormat else {
+            reportError("识别未开始，无法转录文件")
+            return -1
+        }
+        
+        let file: AVAudioFile
+        do {
+            file = try AVAudioFile(forReading: URL(fileURLWithPath: path))
+        } catch {
+            reportError("无法打开音频文件: \(error.localizedDescription)")
+            return -1
+        }
+        
+        let sourceFormat = file.processingFormat
+        guard let converter = AVAudioConverter(from: sourceFormat, to: targetFormat) else {
+            reportError("不支持的音频格式: \(sourceFormat)")
+            return -1
+        }
+        
+        // 每块约 1 秒
+        let chunkFrames = AVAudioFrameCount(max(sourceFormat.sampleRate, 1))
+        guard let inputBuffer = AVAudioPCMBuffer(pcmFormat: sourceFormat, frameCapacity: chunkFrames) else {
+            reportError("无法创建音频缓冲区")
+            return -1
+        }
+        
+        let semaphore = DispatchSemaphore(value: 0)
+        finalResultSemaphore = semaphore
+        defer { finalResultSemaphore = nil }
+        
+        let totalFrames = max(file.length, 1)
+        let ratio = targetFormat.sampleRate / sourceFormat.sampleRate
+        
+        while file.framePosition < file.length {
+            do {
+                try file.read(into: inputBuffer, frameCount: chunkFrames)
+            } catch {
+                reportError("读取音频文件失败: \(error.localizedDescription)")
+                return -1
+            }
+            if inputBuffer.frameLength == 0 {
+                break
+            }
+            
+            let capacity = AVAudioFrameCount(Double(inputBuffer.frameLength) * ratio) + 1
+            guard let outputBuffer = AVAudioPCMBuffer(pcmFormat: targetFormat, frameCapacity: capacity) else {
+                reportError("无法创建音频缓冲区")
+                return -1
+            }
+            
+            // 每次转换只提供当前块，转换器需要更多数据时返回 noDataNow
+            var consumed = false
+            var conversionError: NSError?
+            converter.convert(to: outputBuffer, error: &conversionError) { _, inputStatus in
+                if consumed {
+                    inputStatus.pointee = .noDataNow
+                    return nil
+                }
+                consumed = true
+                inputStatus.pointee = .haveData
+                return inputBuffer
+            }
+            if let conversionError = conversionError {
+                reportError("音频格式转换失败: \(conversionError.localizedDescription)")
+                return -1
+            }
+            
+            recognitionRequest?.append(outputBuffer)
+            progress?(Double(file.framePosition) / Double(totalFrames))
+        }
+        
+        // 结束音频输入，等待识别器给出最终结果
+        recognitionRequest?.endAudio()
+        if semaphore.wait(timeout: .now() + fileFinalResultTimeout) == .timedOut {
+            print("[SpeechRecognizer] 等待文件最终结果超时")
+        }
+        
+        print("[SpeechRecognizer] 文件转录完成: \(path)")
+        return Double(file.length) / sourceFormat.sampleRate
+    }
+    
     /// 停止语音识别
     @objc public func stopRecognition() {
         guard status == .recognizing else { return }
@@ -277,12 +487,51 @@ public func speech_setLanguage(_ languageCode: UnsafePointer<CChar>) {
     SpeechRecognitionManager.shared.setLanguage(language)
 }
 
+/// 设置自定义词汇
+@_cdecl("speech_set_contextual_strings")
+public func speech_setContextualStrings(_ phrases: UnsafePointer<UnsafePointer<CChar>?>?, _ count: Int32) {
+    var result: [String] = []
+    if let phrases = phrases {
+        for i in 0..<Int(max(count, 0)) {
+            if let phrase = phrases[i] {
+                result.append(String(cString: phrase))
+            }
+        }
+    }
+    SpeechRecognitionManager.shared.setContextualStrings(result)
+}
+
+/// 检查是否支持指定语言
+@_cdecl("speech_supports_language")
+public func speech_supportsLanguage(_ languageCode: UnsafePointer<CChar>) -> Bool {
+    let language = String(cString: languageCode)
+    return SpeechRecognitionManager.shared.supportsLanguage(language)
+}
+
+/// 设置是否强制端侧识别
+@_cdecl("speech_set_on_device")
+public func speech_setOnDevice(_ required: Bool) {
+    SpeechRecognitionManager.shared.setOnDeviceRequired(required)
+}
+
+/// 设置识别任务类型提示
+@_cdecl("speech_set_task_hint")
+public func speech_setTaskHint(_ hint: Int32) {
+    SpeechRecognitionManager.shared.setTaskHint(Int(hint))
+}
+
 /// 检查是否支持端侧识别
 @_cdecl("speech_supports_on_device")
 public func speech_supportsOnDevice() -> Bool {
     return SpeechRecognitionManager.shared.supportsOnDeviceRecognition()
 }
 
+/// 预热识别器，返回 -1 失败、0 识别器就绪、1 端侧模型已就绪
+@_cdecl("speech_prewarm")
+public func speech_prewarm() -> Int32 {
+    return SpeechRecognitionManager.shared.prewarm()
+}
+
 /// 开始语音识别
 @_cdecl("speech_start")
 public func speech_start() -> Bool {
@@ -295,6 +544,12 @@ public func speech_appendAudio(_ samples: UnsafePointer<Float>, _ count: Int32)
     SpeechRecognitionManager.shared.appendAudioData(samples, count: count)
 }
 
+/// 转录音频文件（需先调用 speech_start），返回音频时长（秒），失败返回 -1
+@_cdecl("speech_transcribe_file")
+public func speech_transcribeFile(_ path: UnsafePointer<CChar>, _ progress: FileProgressCallback?) -> Double {
+    return SpeechRecognitionManager.shared.transcribeFile(String(cString: path), progress: progress)
+}
+
 /// 停止语音识别
 @_cdecl("speech_stop")
 public func speech_stop() {
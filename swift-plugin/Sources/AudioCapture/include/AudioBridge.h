@@ -12,6 +12,12 @@
 extern "C" {
 #endif
 
+// ============= 健康检查 =============
+
+/// 健康检查：确认 Swift 运行时可用
+/// @return 原生接口版本，与 Rust 侧 NATIVE_ABI_VERSION 不一致时视为不可用
+int32_t audio_bridge_health_check(void);
+
 // ============= 音频捕获接口 =============
 
 /// 检查屏幕录制权限
@@ -29,6 +35,42 @@ void audio_capture_stop(void);
 /// @return 0=空闲, 1=启动中, 2=捕获中, 3=停止中, -1=错误
 int32_t audio_capture_get_status(void);
 
+/// 设置捕获目标应用，下次开始捕获时生效
+/// @param bundle_id 应用的 bundle ID，NULL 表示捕获整个系统的音频
+void audio_capture_set_target(const char* bundle_id);
+
+/// 列出可单独捕获音频的应用
+/// @return JSON 数组字符串 [{ "name", "bundle_id" }]，需使用 audio_free_string 释放
+char* audio_list_capture_targets(void);
+
+// ============= 麦克风捕获接口 =============
+
+/// 检查麦克风权限
+/// @return true 如果有权限，false 否则
+bool mic_capture_check_permission(void);
+
+/// 开始麦克风捕获
+/// @return true 如果成功启动，false 否则
+bool mic_capture_start(void);
+
+/// 停止麦克风捕获
+void mic_capture_stop(void);
+
+/// 获取麦克风捕获状态
+/// @return 0=空闲, 1=启动中, 2=捕获中, 3=停止中, -1=错误
+int32_t mic_capture_get_status(void);
+
+/// 设置麦克风输入设备，下次开始捕获时生效
+/// @param device_id 设备 ID（AVCaptureDevice.uniqueID），NULL 表示使用系统默认设备
+void mic_capture_set_device(const char* device_id);
+
+/// 列出音频输入设备
+/// @return JSON 数组字符串 [{ "id", "name", "is_default" }]，需使用 audio_free_string 释放
+char* audio_list_devices(void);
+
+/// 释放由 Swift 模块分配的字符串
+void audio_free_string(char* ptr);
+
 // ============= 语音识别接口 =============
 
 /// 检查语音识别权限
@@ -39,10 +81,34 @@ bool speech_check_permission(void);
 /// @param language_code 语言代码，如 "zh-CN", "en-US"
 void speech_set_language(const char* language_code);
 
+/// 设置自定义词汇（上下文短语），下次开始识别时生效
+/// @param phrases 短语字符串数组指针
+/// @param count 短语数量
+void speech_set_contextual_strings(const char* const* phrases, int32_t count);
+
+/// 检查本机是否支持指定语言的识别
+/// @param language_code 语言代码，如 "zh-CN", "en-US"
+/// @return true 如果支持，false 否则
+bool speech_supports_language(const char* language_code);
+
+/// 设置是否强制端侧识别
+/// @param require true 时当前语言不支持端侧识别则启动失败，不回退到服务器识别
+void speech_set_on_device(bool require);
+
+/// 设置识别任务类型提示（SFSpeechRecognitionTaskHint），下次开始识别时生效
+/// 连续口述用 dictation，短关键词用 search，"是 / 停止"之类的短命令用 confirmation
+/// @param hint 0=unspecified, 1=dictation（默认）, 2=search, 3=confirmation
+void speech_set_task_hint(int32_t hint);
+
 /// 检查是否支持端侧识别
 /// @return true 如果支持，false 否则
 bool speech_supports_on_device(void);
 
+/// 预热识别器：创建当前语言的识别器，支持时提前加载端侧模型，不启动捕获
+/// 阻塞直到模型加载完成或超时
+/// @return -1 失败（识别器不可用或正在识别），0 识别器就绪但端侧模型不可用，1 端侧模型已就绪
+int32_t speech_prewarm(void);
+
 /// 开始语音识别
 /// @return true 如果成功启动，false 否则
 bool speech_start(void);
@@ -52,6 +118,16 @@ bool speech_start(void);
 /// @param count 样本数量
 void speech_append_audio(const float* samples, int32_t count);
 
+/// 文件转录进度回调类型，progress 为 0.0 ~ 1.0
+typedef void (*FileProgressCallback)(double progress);
+
+/// 转录音频文件（WAV / M4A），需先调用 speech_start
+/// 文件解码并转换为 16kHz 单声道后按块送入识别器，阻塞直到收到最终结果或超时
+/// @param path 文件路径
+/// @param progress 进度回调，可为 NULL
+/// @return 音频时长（秒），失败时返回 -1
+double speech_transcribe_file(const char* path, FileProgressCallback progress);
+
 /// 停止语音识别
 void speech_stop(void);
 
@@ -62,10 +138,13 @@ int32_t speech_get_status(void);
 // ============= 回调设置 =============
 
 /// 音频样本回调类型
-typedef void (*AudioSampleCallback)(const float* samples, int32_t count, double timestamp);
+/// samples 为交错的 Float32 样本，count 为样本总数（帧数 × 声道数）
+/// sampleRate / channels 为样本的实际格式，Rust 侧会转换为 16kHz 单声道
+typedef void (*AudioSampleCallback)(const float* samples, int32_t count, double timestamp, double sampleRate, int32_t channels);
 
 /// 转录结果回调类型
-typedef void (*TranscriptionCallback)(const char* text, bool is_final);
+/// confidence 为识别置信度（0.0 ~ 1.0），部分结果可能为 0
+typedef void (*TranscriptionCallback)(const char* text, bool is_final, float confidence);
 
 /// 错误回调类型
 typedef void (*ErrorCallback)(const char* message);
@@ -76,6 +155,12 @@ void audio_capture_set_callback(AudioSampleCallback callback);
 /// 设置音频错误回调
 void audio_capture_set_error_callback(ErrorCallback callback);
 
+/// 设置麦克风音频样本回调
+void mic_capture_set_callback(AudioSampleCallback callback);
+
+/// 设置麦克风错误回调
+void mic_capture_set_error_callback(ErrorCallback callback);
+
 /// 设置转录回调
 void speech_set_callback(TranscriptionCallback callback);
 